@@ -0,0 +1,13 @@
+use epidemic::{Model, Rate, Transmission};
+
+fn main() {
+    let mut model = Model::builder()
+        .compartment("Susceptible", 1000)
+        .compartment("Infected", 1)
+        .compartment("Recovered", 0)
+        .flow("Susceptible", "Infected", Transmission::new(0.5))
+        .flow("Infected", "Recovered", Rate::new(0.2))
+        .build()
+        .unwrap();
+    model.run(1).unwrap();
+}
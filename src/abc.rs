@@ -0,0 +1,532 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::ensemble::parallel;
+use crate::{
+    Distribution, FitError, Model, Observations, Output, ParameterError, ParameterSpace, Rng,
+    SimulationResult, Target,
+};
+
+/// How many simulations [`Abc`] runs at most unless told otherwise.
+const DEFAULT_MAX_SIMULATIONS: usize = 1_000_000;
+
+/// A statistic computed from the prevalence and incidence of a run.
+type MeasureFn = dyn Fn(&SimulationResult, &SimulationResult) -> f64 + Sync;
+
+enum Measure {
+    Output(Output),
+    Custom(Box<MeasureFn>),
+}
+
+enum Statistic {
+    Scalar { measure: Measure, observed: f64 },
+    Series(Observations),
+}
+
+impl Statistic {
+    /// Returns how far a simulation with the given prevalence and incidence
+    /// is from the data, relative to the size of the data.
+    fn discrepancy(&self, prevalence: &SimulationResult, incidence: &SimulationResult) -> f64 {
+        match self {
+            Statistic::Scalar { measure, observed } => {
+                let simulated = match measure {
+                    Measure::Output(output) => output.measure(prevalence),
+                    Measure::Custom(measure) => measure(prevalence, incidence),
+                };
+                let scale = if *observed == 0.0 {
+                    1.0
+                } else {
+                    observed.abs()
+                };
+                (simulated - observed) / scale
+            }
+            Statistic::Series(observations) => {
+                let (result, name) = match observations.target() {
+                    Target::Prevalence(name) => (prevalence, name),
+                    Target::Incidence(path) => (incidence, path),
+                };
+                let (mut squares, mut size) = (0.0, 0.0);
+                for (time, observed) in observations.times().iter().zip(observations.values()) {
+                    let simulated = result.value_at(name, *time).unwrap_or(f64::NAN);
+                    squares += (simulated - observed).powi(2);
+                    size += observed.abs();
+                }
+                let count = observations.len() as f64;
+                let scale = if size == 0.0 { 1.0 } else { size / count };
+                (squares / count).sqrt() / scale
+            }
+        }
+    }
+}
+
+/// Weighted samples of a model's parameters from their approximate
+/// posterior distribution, found by [`Abc::rejection`] or [`Abc::smc`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particles {
+    parameters: Vec<String>,
+    samples: Vec<Vec<f64>>,
+    weights: Vec<f64>,
+    distances: Vec<f64>,
+    tolerance: f64,
+    simulations: usize,
+}
+
+impl Particles {
+    /// Returns the path of every parameter sampled.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+    /// Returns every particle, each a value for every parameter.
+    pub fn samples(&self) -> &[Vec<f64>] {
+        &self.samples
+    }
+    /// Returns the weight of every particle. Weights add up to one.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+    /// Returns the distance from the data of the simulation that accepted
+    /// every particle.
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+    /// Returns the tolerance every particle was accepted within.
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+    /// Returns how many simulations were run to find the particles, across
+    /// every generation.
+    pub fn simulations(&self) -> usize {
+        self.simulations
+    }
+    /// Returns the number of particles.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    /// Returns true if there are no particles.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    /// Returns the effective sample size of the weighted particles, the
+    /// number of equally weighted ones they are worth.
+    pub fn effective_sample_size(&self) -> f64 {
+        1.0 / self.weights.iter().map(|w| w * w).sum::<f64>()
+    }
+    /// Returns the value of the parameter at `path` in every particle.
+    pub fn values(&self, path: &str) -> Option<Vec<f64>> {
+        let index = self.parameters.iter().position(|p| p == path)?;
+        Some(self.samples.iter().map(|sample| sample[index]).collect())
+    }
+    /// Returns the weighted posterior mean of the parameter at `path`.
+    pub fn mean(&self, path: &str) -> Option<f64> {
+        let values = self.values(path)?;
+        Some(values.iter().zip(&self.weights).map(|(v, w)| v * w).sum())
+    }
+    /// Returns the equal-tailed credible interval of the parameter at
+    /// `path` holding a fraction `level` of the weighted posterior, such as
+    /// `0.95`.
+    pub fn credible_interval(&self, path: &str, level: f64) -> Option<(f64, f64)> {
+        let values = self.values(path)?;
+        if values.is_empty() {
+            return None;
+        }
+        let mut weighted: Vec<(f64, f64)> = values.into_iter().zip(self.weights.clone()).collect();
+        weighted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let tail = (1.0 - level) / 2.0;
+        Some((
+            weighted_quantile(&weighted, tail),
+            weighted_quantile(&weighted, 1.0 - tail),
+        ))
+    }
+    /// Writes every particle to the file at `path` as CSV, replacing it if
+    /// it exists. See [`write_csv`](Particles::write_csv) for the layout.
+    pub fn to_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+    /// Writes every particle to `writer` as CSV, with a `particle` column,
+    /// a column for each parameter named by its path, and `weight` and
+    /// `distance` columns.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_field("particle")?;
+        for parameter in &self.parameters {
+            writer.write_field(parameter)?;
+        }
+        writer.write_record(["weight", "distance"])?;
+        for (index, sample) in self.samples.iter().enumerate() {
+            writer.write_field(index.to_string())?;
+            for value in sample {
+                writer.write_field(value.to_string())?;
+            }
+            writer.write_record([
+                self.weights[index].to_string(),
+                self.distances[index].to_string(),
+            ])?;
+        }
+        writer.flush()
+    }
+}
+
+/// The smallest value whose cumulative weight reaches `q`, of values sorted
+/// with their weights.
+fn weighted_quantile(sorted: &[(f64, f64)], q: f64) -> f64 {
+    let total: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+    let mut cumulative = 0.0;
+    for (value, weight) in sorted {
+        cumulative += weight / total;
+        if cumulative >= q {
+            return *value;
+        }
+    }
+    sorted[sorted.len() - 1].0
+}
+
+/// Approximate Bayesian computation (ABC) of a model's parameters, for
+/// stochastic models whose likelihood can't be written down.
+///
+/// Instead of a likelihood, parameters drawn from their priors are kept
+/// when a simulation with them lands within a tolerance of the data, as
+/// measured by summary statistics: [`Output`]s such as the size of the
+/// peak, any other number computed from a run, or whole observed time
+/// series. Each statistic contributes its difference from the data
+/// relative to the size of the data, and the distance of a simulation is
+/// the Euclidean norm of the differences, so a tolerance of `0.1` accepts
+/// simulations within roughly a tenth of the data.
+///
+/// Simulations run in parallel like an
+/// [`Ensemble::parallel`](crate::Ensemble::parallel), each on a fresh
+/// model built by `build`, with the free parameters set and a seed of its
+/// own.
+///
+/// ```no_run
+/// # use epidemic::{Abc, Distribution, Model, Output, Rng};
+/// # fn build() -> Model { unimplemented!() }
+/// let abc = Abc::new(build, 100)
+///     .prior("Susceptible.infection.beta", Distribution::Uniform(0.1, 1.0))
+///     .statistic(Output::Peak("Infected".to_owned()), 240.0)
+///     .statistic(Output::Final("Recovered".to_owned()), 880.0);
+/// let particles = abc.smc(500, &[0.5, 0.25, 0.1, 0.05], &mut Rng::new(1)).unwrap();
+/// let beta = particles.mean("Susceptible.infection.beta").unwrap();
+/// ```
+pub struct Abc<F> {
+    build: F,
+    ticks: u64,
+    priors: ParameterSpace,
+    statistics: Vec<Statistic>,
+    jobs: usize,
+    max_simulations: usize,
+}
+
+impl<F: Fn() -> Model + Sync> Abc<F> {
+    /// Creates an ABC fit of the models `build` makes, each simulated for
+    /// `ticks` ticks, with no free parameters or statistics yet.
+    pub fn new(build: F, ticks: u64) -> Abc<F> {
+        Abc {
+            build,
+            ticks,
+            priors: ParameterSpace::new(),
+            statistics: Vec::new(),
+            jobs: 0,
+            max_simulations: DEFAULT_MAX_SIMULATIONS,
+        }
+    }
+    /// Frees the parameter at `path`, with the prior belief that its value
+    /// is distributed as `prior`.
+    pub fn prior(mut self, path: &str, prior: Distribution) -> Abc<F> {
+        self.priors = self.priors.parameter(path, prior);
+        self
+    }
+    /// Compares `output` of every simulation with the value `observed`.
+    pub fn statistic(mut self, output: Output, observed: f64) -> Abc<F> {
+        self.statistics.push(Statistic::Scalar {
+            measure: Measure::Output(output),
+            observed,
+        });
+        self
+    }
+    /// Compares the number `measure` computes from the prevalence and
+    /// incidence of every simulation, as returned by
+    /// [`Model::simulate_flows`], with the value `observed`.
+    pub fn statistic_with<M>(mut self, measure: M, observed: f64) -> Abc<F>
+    where
+        M: Fn(&SimulationResult, &SimulationResult) -> f64 + Sync + 'static,
+    {
+        self.statistics.push(Statistic::Scalar {
+            measure: Measure::Custom(Box::new(measure)),
+            observed,
+        });
+        self
+    }
+    /// Compares every simulation with `observations` point by point, by the
+    /// root mean square of their differences. Simulations must run long
+    /// enough to cover the observations.
+    pub fn observations(mut self, observations: Observations) -> Abc<F> {
+        self.statistics.push(Statistic::Series(observations));
+        self
+    }
+    /// Spreads simulations across `jobs` threads, or one per core if `jobs`
+    /// is 0, the default.
+    pub fn jobs(mut self, jobs: usize) -> Abc<F> {
+        self.jobs = jobs;
+        self
+    }
+    /// Gives up on [`smc`](Abc::smc) after `max_simulations` simulations,
+    /// in case a tolerance is too tight to ever reach. Defaults to a
+    /// million.
+    pub fn max_simulations(mut self, max_simulations: usize) -> Abc<F> {
+        self.max_simulations = max_simulations;
+        self
+    }
+    /// Returns the free parameters with their priors.
+    pub fn priors(&self) -> &ParameterSpace {
+        &self.priors
+    }
+    /// Runs a simulation with the free parameters set to `values` and the
+    /// given seed, returning its distance from the data.
+    pub fn distance(&self, values: &[f64], seed: u64) -> Result<f64, FitError> {
+        let mut model = (self.build)();
+        for (path, value) in self.priors.paths().into_iter().zip(values) {
+            model.set_parameter(path, *value)?;
+        }
+        model.set_seed(seed);
+        let (prevalence, incidence) = model.simulate_flows(self.ticks);
+        Ok(self
+            .statistics
+            .iter()
+            .map(|statistic| statistic.discrepancy(&prevalence, &incidence).powi(2))
+            .sum::<f64>()
+            .sqrt())
+    }
+    /// Draws `draws` parameter values from their priors, simulates each
+    /// once, and keeps those whose simulation lands within `tolerance` of
+    /// the data, all equally weighted. There may be none if the tolerance
+    /// is tight.
+    pub fn rejection(
+        &self,
+        draws: usize,
+        tolerance: f64,
+        rng: &mut Rng,
+    ) -> Result<Particles, FitError> {
+        self.check()?;
+        let candidates = self.priors.random(draws, rng);
+        let seeds: Vec<u64> = (0..draws).map(|_| rng.next_u64()).collect();
+        let distances = self.distances(&candidates, &seeds);
+        let (samples, distances): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .zip(distances)
+            .filter(|(_, distance)| *distance <= tolerance)
+            .unzip();
+        let weights = vec![1.0 / samples.len() as f64; samples.len()];
+        Ok(self.particles(samples, weights, distances, tolerance, draws))
+    }
+    /// Finds `particles` particles by sequential Monte Carlo ABC (ABC-SMC),
+    /// accepting them within each tolerance of the decreasing `schedule` in
+    /// turn.
+    ///
+    /// The first generation is drawn from the priors, and each later one by
+    /// perturbing particles of the last with a Gaussian kernel twice as
+    /// wide as their weighted variance, reweighting them by their prior
+    /// density over the density of proposing them. Tightening the
+    /// tolerance gradually wastes far fewer simulations than rejection
+    /// straight at the last tolerance. Returns an error if a generation
+    /// needs more simulations than
+    /// [`max_simulations`](Abc::max_simulations) allows in all.
+    pub fn smc(
+        &self,
+        particles: usize,
+        schedule: &[f64],
+        rng: &mut Rng,
+    ) -> Result<Particles, FitError> {
+        self.check()?;
+        let priors = self.priors.distributions();
+        let d = priors.len();
+        let mut simulations = 0;
+        let mut generation: Option<Particles> = None;
+        for &tolerance in schedule {
+            let mut kernel = vec![0.0; d];
+            if let Some(previous) = &generation {
+                for (k, spread) in kernel.iter_mut().enumerate() {
+                    let mean: f64 = previous
+                        .samples
+                        .iter()
+                        .zip(&previous.weights)
+                        .map(|(sample, w)| w * sample[k])
+                        .sum();
+                    let variance: f64 = previous
+                        .samples
+                        .iter()
+                        .zip(&previous.weights)
+                        .map(|(sample, w)| w * (sample[k] - mean).powi(2))
+                        .sum();
+                    *spread = (2.0 * variance).sqrt();
+                }
+            }
+            let (mut samples, mut distances) = (Vec::new(), Vec::new());
+            while samples.len() < particles {
+                if simulations >= self.max_simulations {
+                    return Err(FitError::Tolerance(tolerance));
+                }
+                let batch = particles.min(self.max_simulations - simulations);
+                let candidates: Vec<Vec<f64>> = match &generation {
+                    None => self.priors.random(batch, rng),
+                    Some(previous) => (0..batch)
+                        .map(|_| perturb(previous, &priors, &kernel, rng))
+                        .collect(),
+                };
+                let seeds: Vec<u64> = (0..batch).map(|_| rng.next_u64()).collect();
+                simulations += batch;
+                for (candidate, distance) in
+                    candidates.iter().zip(self.distances(&candidates, &seeds))
+                {
+                    if distance <= tolerance && samples.len() < particles {
+                        samples.push(candidate.clone());
+                        distances.push(distance);
+                    }
+                }
+            }
+            let weights = match &generation {
+                None => vec![1.0; samples.len()],
+                Some(previous) => samples
+                    .iter()
+                    .map(|sample| {
+                        let prior: f64 = priors
+                            .iter()
+                            .zip(sample)
+                            .map(|(prior, value)| prior.log_density(*value))
+                            .sum();
+                        let proposal: f64 = previous
+                            .samples
+                            .iter()
+                            .zip(&previous.weights)
+                            .map(|(from, weight)| weight * kernel_density(from, sample, &kernel))
+                            .sum();
+                        prior.exp() / proposal
+                    })
+                    .collect(),
+            };
+            let total: f64 = weights.iter().sum();
+            let weights = weights.iter().map(|weight| weight / total).collect();
+            generation = Some(self.particles(samples, weights, distances, tolerance, simulations));
+        }
+        Ok(
+            generation
+                .unwrap_or_else(|| self.particles(Vec::new(), Vec::new(), Vec::new(), 0.0, 0)),
+        )
+    }
+    /// Checks the free parameters and statistics against a built model.
+    fn check(&self) -> Result<(), FitError> {
+        let model = (self.build)();
+        let known: Vec<String> = model.parameters().iter().map(|p| p.path()).collect();
+        if let Some(path) = self
+            .priors
+            .paths()
+            .into_iter()
+            .find(|path| !known.iter().any(|k| k == path))
+        {
+            return Err(ParameterError::Unknown(path.to_owned()).into());
+        }
+        if self.statistics.is_empty() {
+            return Err(FitError::NoObservations);
+        }
+        for statistic in &self.statistics {
+            let target = match statistic {
+                Statistic::Scalar {
+                    measure: Measure::Output(output),
+                    ..
+                } => Target::Prevalence(output.compartment().to_owned()),
+                Statistic::Scalar { .. } => continue,
+                Statistic::Series(observations) => observations.target().clone(),
+            };
+            let known = match &target {
+                Target::Prevalence(name) => model.bucket(name).is_some(),
+                Target::Incidence(path) => model.flows().iter().any(|(flow, _)| flow == path),
+            };
+            if !known {
+                return Err(FitError::UnknownTarget(target));
+            }
+        }
+        Ok(())
+    }
+    /// Simulates every candidate with the seed of the same index in
+    /// parallel, returning their distances from the data. Failed or NaN
+    /// distances are infinite.
+    fn distances(&self, candidates: &[Vec<f64>], seeds: &[u64]) -> Vec<f64> {
+        parallel(candidates.len(), self.jobs, |index| {
+            match self.distance(&candidates[index], seeds[index]) {
+                Ok(distance) if !distance.is_nan() => distance,
+                _ => f64::INFINITY,
+            }
+        })
+    }
+    fn particles(
+        &self,
+        samples: Vec<Vec<f64>>,
+        weights: Vec<f64>,
+        distances: Vec<f64>,
+        tolerance: f64,
+        simulations: usize,
+    ) -> Particles {
+        Particles {
+            parameters: self
+                .priors
+                .paths()
+                .iter()
+                .map(|p| (*p).to_owned())
+                .collect(),
+            samples,
+            weights,
+            distances,
+            tolerance,
+            simulations,
+        }
+    }
+}
+
+/// Draws a particle of `previous` by weight and perturbs it with a Gaussian
+/// kernel of the given spread along each axis, redrawing until the result
+/// has prior density.
+fn perturb(
+    previous: &Particles,
+    priors: &[Distribution],
+    kernel: &[f64],
+    rng: &mut Rng,
+) -> Vec<f64> {
+    loop {
+        let mut pick = rng.uniform();
+        let index = previous
+            .weights
+            .iter()
+            .position(|weight| {
+                pick -= weight;
+                pick < 0.0
+            })
+            .unwrap_or(previous.len() - 1);
+        let candidate: Vec<f64> = previous.samples[index]
+            .iter()
+            .zip(kernel)
+            .map(|(value, spread)| value + spread * rng.normal())
+            .collect();
+        if priors
+            .iter()
+            .zip(&candidate)
+            .all(|(prior, value)| prior.log_density(*value).is_finite())
+        {
+            return candidate;
+        }
+    }
+}
+
+/// The density of the perturbation kernel moving `from` to `to`.
+fn kernel_density(from: &[f64], to: &[f64], kernel: &[f64]) -> f64 {
+    from.iter()
+        .zip(to)
+        .zip(kernel)
+        .map(|((from, to), spread)| {
+            if *spread == 0.0 {
+                return if from == to { 1.0 } else { 0.0 };
+            }
+            let z = (to - from) / spread;
+            (-0.5 * z * z).exp() / (spread * (2.0 * std::f64::consts::PI).sqrt())
+        })
+        .product()
+}
@@ -0,0 +1,305 @@
+use crate::Ensemble;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The Arrow metadata version written, V5.
+const VERSION: i16 = 4;
+
+/// Message header types.
+const SCHEMA: u8 = 1;
+const RECORD_BATCH: u8 = 3;
+
+/// Column types.
+const INT: u8 = 2;
+const FLOATING_POINT: u8 = 3;
+const UTF8: u8 = 5;
+
+/// The precision of a 64-bit float.
+const DOUBLE: i16 = 2;
+
+/// Marks the start of every message, and with a zero length the end of the
+/// stream.
+const CONTINUATION: u32 = 0xFFFF_FFFF;
+
+/// A node of a flatbuffer, the serialization Arrow writes its metadata in.
+enum Node {
+    /// A table, with each field at its index in the schema.
+    Table(Vec<(u16, Field)>),
+    String(String),
+    /// A vector of tables.
+    Tables(Vec<Node>),
+    /// A vector of structs of two 64-bit integers, the only kind of struct
+    /// Arrow metadata needs.
+    Pairs(Vec<(i64, i64)>),
+}
+
+/// A field of a flatbuffer table.
+enum Field {
+    Bool(bool),
+    U8(u8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Child(Node),
+}
+
+impl Field {
+    /// Returns the size of the field inline in its table, which is also
+    /// its alignment.
+    fn size(&self) -> usize {
+        match self {
+            Field::Bool(_) | Field::U8(_) => 1,
+            Field::I16(_) => 2,
+            Field::I32(_) | Field::Child(_) => 4,
+            Field::I64(_) => 8,
+        }
+    }
+}
+
+/// Serializes `root` as a flatbuffer.
+fn flatbuffer(root: &Node) -> Vec<u8> {
+    let mut buffer = vec![0; 4];
+    let position = write_node(&mut buffer, root);
+    patch(&mut buffer, 0, position);
+    buffer
+}
+
+/// Pads `buffer` with zeros until its length leaves `remainder` modulo
+/// `alignment`.
+fn pad(buffer: &mut Vec<u8>, alignment: usize, remainder: usize) {
+    while buffer.len() % alignment != remainder {
+        buffer.push(0);
+    }
+}
+
+/// Points the offset at `slot` forward to `target`. Flatbuffer offsets are
+/// unsigned, so every node is written after whatever refers to it.
+fn patch(buffer: &mut [u8], slot: usize, target: usize) {
+    let offset = (target - slot) as u32;
+    buffer[slot..slot + 4].copy_from_slice(&offset.to_le_bytes());
+}
+
+/// Appends `node` and everything it refers to, returning where it starts.
+fn write_node(buffer: &mut Vec<u8>, node: &Node) -> usize {
+    match node {
+        Node::Table(fields) => write_table(buffer, fields),
+        Node::String(string) => {
+            pad(buffer, 4, 0);
+            let position = buffer.len();
+            buffer.extend((string.len() as u32).to_le_bytes());
+            buffer.extend(string.as_bytes());
+            buffer.push(0);
+            position
+        }
+        Node::Tables(tables) => {
+            pad(buffer, 4, 0);
+            let position = buffer.len();
+            buffer.extend((tables.len() as u32).to_le_bytes());
+            let slots = buffer.len();
+            buffer.resize(slots + 4 * tables.len(), 0);
+            for (index, table) in tables.iter().enumerate() {
+                let target = write_node(buffer, table);
+                patch(buffer, slots + 4 * index, target);
+            }
+            position
+        }
+        Node::Pairs(pairs) => {
+            // The elements are 8-byte aligned, just after the length.
+            pad(buffer, 8, 4);
+            let position = buffer.len();
+            buffer.extend((pairs.len() as u32).to_le_bytes());
+            for (first, second) in pairs {
+                buffer.extend(first.to_le_bytes());
+                buffer.extend(second.to_le_bytes());
+            }
+            position
+        }
+    }
+}
+
+/// Appends a table with its vtable just before it, then the nodes its
+/// fields refer to, returning where the table starts.
+fn write_table(buffer: &mut Vec<u8>, fields: &[(u16, Field)]) -> usize {
+    // Lays the fields out largest first after the offset to the vtable, so
+    // that each is aligned once the table itself is 8-byte aligned.
+    let mut order: Vec<&(u16, Field)> = fields.iter().collect();
+    order.sort_by_key(|(_, field)| std::cmp::Reverse(field.size()));
+    let mut size = 4;
+    let mut offsets = Vec::new();
+    for (index, field) in &order {
+        size += (field.size() - size % field.size()) % field.size();
+        offsets.push((*index, size));
+        size += field.size();
+    }
+    let slots = fields
+        .iter()
+        .map(|(index, _)| *index + 1)
+        .max()
+        .unwrap_or(0);
+    let mut vtable = vec![0u16; 2 + slots as usize];
+    vtable[0] = 2 * vtable.len() as u16;
+    vtable[1] = size as u16;
+    for (index, offset) in &offsets {
+        vtable[2 + *index as usize] = *offset as u16;
+    }
+    pad(buffer, 2, 0);
+    let vtable_position = buffer.len();
+    vtable
+        .iter()
+        .for_each(|entry| buffer.extend(entry.to_le_bytes()));
+    pad(buffer, 8, 0);
+    let position = buffer.len();
+    buffer.resize(position + size, 0);
+    let back = (position - vtable_position) as i32;
+    buffer[position..position + 4].copy_from_slice(&back.to_le_bytes());
+    let mut children = Vec::new();
+    for ((_, field), (_, offset)) in order.iter().zip(&offsets) {
+        let at = position + offset;
+        match field {
+            Field::Bool(value) => buffer[at] = u8::from(*value),
+            Field::U8(value) => buffer[at] = *value,
+            Field::I16(value) => buffer[at..at + 2].copy_from_slice(&value.to_le_bytes()),
+            Field::I32(value) => buffer[at..at + 4].copy_from_slice(&value.to_le_bytes()),
+            Field::I64(value) => buffer[at..at + 8].copy_from_slice(&value.to_le_bytes()),
+            Field::Child(node) => children.push((at, node)),
+        }
+    }
+    for (slot, node) in children {
+        let target = write_node(buffer, node);
+        patch(buffer, slot, target);
+    }
+    position
+}
+
+/// Describes a non-nullable column called `name` of the type `kind`, one of
+/// the type constants, with the fields of that type.
+fn column(name: &str, kind: u8, fields: Vec<(u16, Field)>) -> Node {
+    Node::Table(vec![
+        (0, Field::Child(Node::String(name.to_owned()))),
+        (1, Field::Bool(false)),
+        (2, Field::U8(kind)),
+        (3, Field::Child(Node::Table(fields))),
+        (5, Field::Child(Node::Tables(Vec::new()))),
+    ])
+}
+
+/// Wraps `header`, a message of type `kind`, with a body of `body` bytes.
+fn message(kind: u8, header: Node, body: usize) -> Node {
+    Node::Table(vec![
+        (0, Field::I16(VERSION)),
+        (1, Field::U8(kind)),
+        (2, Field::Child(header)),
+        (3, Field::I64(body as i64)),
+    ])
+}
+
+/// Writes an encapsulated message: its metadata, padded so that the body
+/// after it starts 8-byte aligned, then the body.
+fn write_message<W: Write>(writer: &mut W, message: &Node, body: &[u8]) -> io::Result<()> {
+    let mut metadata = flatbuffer(message);
+    pad(&mut metadata, 8, 0);
+    writer.write_all(&CONTINUATION.to_le_bytes())?;
+    writer.write_all(&(metadata.len() as u32).to_le_bytes())?;
+    writer.write_all(&metadata)?;
+    writer.write_all(body)
+}
+
+/// The body of a record batch and the position of every buffer in it.
+#[derive(Default)]
+struct Body {
+    bytes: Vec<u8>,
+    buffers: Vec<(i64, i64)>,
+}
+
+impl Body {
+    /// Appends a buffer, 8-byte aligned as Arrow requires.
+    fn buffer(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        let start = self.bytes.len();
+        self.bytes.extend(bytes);
+        self.buffers
+            .push((start as i64, (self.bytes.len() - start) as i64));
+        pad(&mut self.bytes, 8, 0);
+    }
+    /// Appends a column with no nulls, whose validity bitmap can be empty,
+    /// followed by `buffers`.
+    fn column<I, B>(&mut self, buffers: I)
+    where
+        I: IntoIterator<Item = B>,
+        B: IntoIterator<Item = u8>,
+    {
+        self.buffer(Vec::new());
+        buffers.into_iter().for_each(|buffer| self.buffer(buffer));
+    }
+}
+
+impl Ensemble {
+    /// Writes every run to the file at `path` as an Arrow IPC stream,
+    /// replacing it if it exists. See
+    /// [`write_arrow`](Ensemble::write_arrow) for the layout.
+    pub fn to_arrow(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_arrow(&mut writer)?;
+        writer.flush()
+    }
+    /// Writes every run to `writer` as an Arrow IPC stream, in the long
+    /// format of [`write_csv`](Ensemble::write_csv): the columns `run` and
+    /// `seed`, unsigned 32- and 64-bit integers, `time`, a double,
+    /// `compartment`, a string, and `value`, a double, with one row per
+    /// value recorded.
+    ///
+    /// Each run is its own record batch, so the stream is written run by
+    /// run however large the ensemble, and tools such as pyarrow or polars
+    /// read it without parsing text.
+    pub fn write_arrow<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let unsigned = |bits: i32| vec![(0, Field::I32(bits)), (1, Field::Bool(false))];
+        let double = || vec![(0, Field::I16(DOUBLE))];
+        let schema = Node::Table(vec![
+            (0, Field::I16(0)),
+            (
+                1,
+                Field::Child(Node::Tables(vec![
+                    column("run", INT, unsigned(32)),
+                    column("seed", INT, unsigned(64)),
+                    column("time", FLOATING_POINT, double()),
+                    column("compartment", UTF8, Vec::new()),
+                    column("value", FLOATING_POINT, double()),
+                ])),
+            ),
+        ]);
+        write_message(&mut writer, &message(SCHEMA, schema, 0), &[])?;
+        for (run, (result, seed)) in self.runs().iter().zip(self.seeds()).enumerate() {
+            let (mut times, mut values, mut text) = (Vec::new(), Vec::new(), Vec::new());
+            let mut offsets = 0u32.to_le_bytes().to_vec();
+            let mut rows = 0;
+            for series in result.series() {
+                for (time, value) in result.times().iter().zip(series) {
+                    times.extend(time.to_le_bytes());
+                    values.extend(value.to_le_bytes());
+                    text.extend(series.name().as_bytes());
+                    offsets.extend((text.len() as u32).to_le_bytes());
+                    rows += 1;
+                }
+            }
+            let mut body = Body::default();
+            body.column(Some((0..rows).flat_map(|_| (run as u32).to_le_bytes())));
+            body.column(Some((0..rows).flat_map(|_| seed.to_le_bytes())));
+            body.column(Some(times));
+            body.column(vec![offsets, text]);
+            body.column(Some(values));
+            let batch = Node::Table(vec![
+                (0, Field::I64(rows as i64)),
+                (1, Field::Child(Node::Pairs(vec![(rows as i64, 0); 5]))),
+                (2, Field::Child(Node::Pairs(body.buffers))),
+            ]);
+            write_message(
+                &mut writer,
+                &message(RECORD_BATCH, batch, body.bytes.len()),
+                &body.bytes,
+            )?;
+        }
+        writer.write_all(&CONTINUATION.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())
+    }
+}
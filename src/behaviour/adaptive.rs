@@ -0,0 +1,157 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// How strongly contact falls as the signal driving an [`AdaptiveContact`]
+/// grows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Response {
+    /// Contact falls linearly, to nothing once the signal reaches
+    /// `1 / slope`.
+    Linear {
+        /// The fall in contact per unit of signal.
+        slope: f64,
+    },
+    /// Contact falls as `exp(-k * signal)`.
+    Exponential {
+        /// How quickly contact falls.
+        k: f64,
+    },
+    /// Contact falls as `1 / (1 + (signal / half)^n)`, halving when the
+    /// signal reaches `half`.
+    Hill {
+        /// The signal at which contact is halved.
+        half: f64,
+        /// How sharply contact falls around `half`.
+        n: f64,
+    },
+}
+
+impl Response {
+    /// Returns the fraction of contact that remains at the given signal.
+    pub fn factor(self, signal: f64) -> f64 {
+        let signal = signal.max(0.0);
+        match self {
+            Response::Linear { slope } => (1.0 - slope * signal).clamp(0.0, 1.0),
+            Response::Exponential { k } => (-k * signal).exp(),
+            Response::Hill { half, n } => {
+                if half > 0.0 {
+                    1.0 / (1.0 + (signal / half).powf(n))
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+    fn parameters(self) -> Vec<(&'static str, f64)> {
+        match self {
+            Response::Linear { slope } => vec![("slope", slope)],
+            Response::Exponential { k } => vec![("k", k)],
+            Response::Hill { half, n } => vec![("half", half), ("n", n)],
+        }
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> bool {
+        match (self, key) {
+            (Response::Linear { slope }, "slope") => *slope = value,
+            (Response::Exponential { k }, "k") => *k = value,
+            (Response::Hill { half, .. }, "half") => *half = value,
+            (Response::Hill { n, .. }, "n") => *n = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+enum Signal {
+    Prevalence(Vec<Bucket>),
+    Count(Vec<Bucket>),
+}
+
+/// Voluntary distancing, scaling down another behaviour such as an
+/// [`Infection`](crate::Infection) as the epidemic becomes visible.
+///
+/// The signal is either the current prevalence of the infectious buckets or
+/// the size of some buckets, such as a sink of cumulative deaths, and the
+/// [`Response`] gives the fraction of contact that remains. The wrapper
+/// takes the name of the behaviour it wraps.
+pub struct AdaptiveContact {
+    inner: Box<dyn Behaviour>,
+    signal: Signal,
+    response: Response,
+}
+
+impl Behaviour for AdaptiveContact {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let signal = match &self.signal {
+            Signal::Prevalence(buckets) => {
+                let total = context.total();
+                if total > 0.0 {
+                    buckets.iter().map(Bucket::get).sum::<f64>() / total
+                } else {
+                    0.0
+                }
+            }
+            Signal::Count(buckets) => buckets.iter().map(Bucket::get).sum(),
+        };
+        let factor = self.response.factor(signal);
+        let mut transfers = self.inner.update(bucket, context);
+        transfers
+            .iter_mut()
+            .for_each(|transfer| transfer.amount *= factor);
+        transfers
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        let mut parameters = self.inner.parameters();
+        parameters.extend(self.response.parameters());
+        parameters
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        if self.response.set_parameter(key, value) {
+            Ok(())
+        } else {
+            self.inner.set_parameter(key, value)
+        }
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        self.inner.params()
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        self.inner.state(buckets)
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        self.inner.set_state(state, buckets)
+    }
+}
+
+impl AdaptiveContact {
+    /// Scales `inner` by the response to the prevalence of the `infectious`
+    /// buckets in the living population.
+    pub fn prevalence(
+        inner: Box<dyn Behaviour>,
+        infectious: Vec<Bucket>,
+        response: Response,
+    ) -> Box<dyn Behaviour> {
+        Box::new(AdaptiveContact {
+            inner,
+            signal: Signal::Prevalence(infectious),
+            response,
+        })
+    }
+    /// Scales `inner` by the response to the total size of `buckets`, such
+    /// as a sink counting deaths.
+    pub fn count(
+        inner: Box<dyn Behaviour>,
+        buckets: Vec<Bucket>,
+        response: Response,
+    ) -> Box<dyn Behaviour> {
+        Box::new(AdaptiveContact {
+            inner,
+            signal: Signal::Count(buckets),
+            response,
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// A single outflow from the bucket it is attached to, split between
+/// several targets.
+///
+/// A fraction `rate` of the bucket leaves every tick, and each target
+/// receives its probability's share of those leaving. Probabilities are
+/// relative to their sum, so they need not add up to one.
+pub struct Branch {
+    targets: Vec<(Bucket, f64)>,
+    rate: Param,
+}
+
+impl Behaviour for Branch {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let total: f64 = self.targets.iter().map(|(_, p)| p).sum();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+        let leaving = self.rate.value(time) * bucket.get() * context.dt();
+        self.targets
+            .iter()
+            .map(|(target, p)| Transfer::new(bucket.clone(), target.clone(), leaving * p / total))
+            .collect()
+    }
+    fn name(&self) -> &str {
+        "branch"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl Branch {
+    /// Creates an outflow at rate `rate` per tick split between `targets`
+    /// with the given probabilities.
+    pub fn new(rate: impl Into<Param>, targets: Vec<(Bucket, f64)>) -> Box<dyn Behaviour> {
+        Box::new(Branch {
+            targets,
+            rate: rate.into(),
+        })
+    }
+    /// Creates a branching outflow whose time to leaving is `period` ticks on
+    /// average.
+    pub fn with_period(period: f64, targets: Vec<(Bucket, f64)>) -> Box<dyn Behaviour> {
+        Branch::new(1.0 / period, targets)
+    }
+}
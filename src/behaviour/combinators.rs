@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Scaling of another behaviour, multiplying every transfer it makes by
+/// `factor`.
+///
+/// Like [`Seasonal`](crate::Seasonal), this and the other combinators take
+/// the name of the behaviour they wrap and add their own parameters to it.
+pub struct Scaled {
+    inner: Box<dyn Behaviour>,
+    factor: Param,
+}
+
+impl Behaviour for Scaled {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let factor = self.factor.value(context.time());
+        let mut transfers = self.inner.update(bucket, context);
+        transfers
+            .iter_mut()
+            .for_each(|transfer| transfer.amount *= factor);
+        transfers
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        let mut parameters = self.inner.parameters();
+        parameters.push(("factor", self.factor.current()));
+        parameters
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "factor" => self.factor = value.into(),
+            _ => return self.inner.set_parameter(key, value),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        let mut params = self.inner.params();
+        params.push(("factor", &mut self.factor));
+        params
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        self.inner.state(buckets)
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        self.inner.set_state(state, buckets)
+    }
+}
+
+impl Scaled {
+    /// Scales every transfer of `inner` by `factor`.
+    pub fn new(inner: Box<dyn Behaviour>, factor: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Scaled {
+            inner,
+            factor: factor.into(),
+        })
+    }
+}
+
+/// A cap on another behaviour, limiting the total it moves to `max` per
+/// tick, as with limited testing or treatment capacity.
+///
+/// When the wrapped behaviour would move more, all of its transfers are
+/// scaled down together.
+pub struct Saturating {
+    inner: Box<dyn Behaviour>,
+    max: Param,
+}
+
+impl Behaviour for Saturating {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let max = self.max.value(context.time()).max(0.0) * context.dt();
+        let mut transfers = self.inner.update(bucket, context);
+        let total: f64 = transfers.iter().map(|transfer| transfer.amount).sum();
+        if total > max {
+            let scale = max / total;
+            transfers
+                .iter_mut()
+                .for_each(|transfer| transfer.amount *= scale);
+        }
+        transfers
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        let mut parameters = self.inner.parameters();
+        parameters.push(("max", self.max.current()));
+        parameters
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "max" => self.max = value.into(),
+            _ => return self.inner.set_parameter(key, value),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        let mut params = self.inner.params();
+        params.push(("max", &mut self.max));
+        params
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        self.inner.state(buckets)
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        self.inner.set_state(state, buckets)
+    }
+}
+
+impl Saturating {
+    /// Limits `inner` to moving at most `max` per tick.
+    pub fn new(inner: Box<dyn Behaviour>, max: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Saturating {
+            inner,
+            max: max.into(),
+        })
+    }
+}
+
+/// A lag on another behaviour, applying each of its transfers `lag` ticks
+/// after it was made.
+///
+/// Transfers are computed from the state of the model when they are made,
+/// and are limited by what their source buckets hold when they are applied.
+pub struct Delayed {
+    inner: Box<dyn Behaviour>,
+    lag: f64,
+    pending: VecDeque<(f64, Vec<Transfer>)>,
+}
+
+impl Behaviour for Delayed {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let transfers = self.inner.update(bucket, context);
+        self.pending.push_back((time + self.lag, transfers));
+        let mut due = Vec::new();
+        while let Some((release, _)) = self.pending.front() {
+            if *release > time {
+                break;
+            }
+            let (_, transfers) = self.pending.pop_front().expect("front exists");
+            due.extend(transfers);
+        }
+        due
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        let mut parameters = self.inner.parameters();
+        parameters.push(("lag", self.lag));
+        parameters
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "lag" => self.lag = value.max(0.0),
+            _ => return self.inner.set_parameter(key, value),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        self.inner.params()
+    }
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        // The number of batches waiting, then each as its release time, its
+        // number of transfers and the transfers, then the state of `inner`.
+        let mut state = vec![self.pending.len() as f64];
+        for (release, transfers) in &self.pending {
+            state.extend([*release, transfers.len() as f64]);
+            state.extend(
+                transfers
+                    .iter()
+                    .flat_map(|transfer| transfer.encode(buckets)),
+            );
+        }
+        state.extend(self.inner.state(buckets));
+        state
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        let count = |value: Option<&f64>| {
+            value
+                .filter(|value| **value >= 0.0 && value.fract() == 0.0)
+                .map(|value| *value as usize)
+        };
+        let batches = match count(state.first()) {
+            Some(batches) => batches,
+            None => return false,
+        };
+        let mut pending = VecDeque::new();
+        let mut rest = &state[1..];
+        for _ in 0..batches {
+            let (release, transfers) = match (rest.first(), count(rest.get(1))) {
+                (Some(release), Some(transfers)) => (*release, transfers),
+                _ => return false,
+            };
+            rest = &rest[2..];
+            if rest.len() < 3 * transfers {
+                return false;
+            }
+            let decoded: Option<Vec<Transfer>> = rest[..3 * transfers]
+                .chunks(3)
+                .map(|encoded| Transfer::decode(encoded, buckets))
+                .collect();
+            match decoded {
+                Some(decoded) => pending.push_back((release, decoded)),
+                None => return false,
+            }
+            rest = &rest[3 * transfers..];
+        }
+        if !self.inner.set_state(rest, buckets) {
+            return false;
+        }
+        self.pending = pending;
+        true
+    }
+}
+
+impl Delayed {
+    /// Delays every transfer of `inner` by `lag` ticks.
+    pub fn new(inner: Box<dyn Behaviour>, lag: f64) -> Box<dyn Behaviour> {
+        Box::new(Delayed {
+            inner,
+            lag,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+/// A gate on another behaviour, which only acts while `condition` holds,
+/// as with an intervention triggered by prevalence.
+///
+/// The wrapped behaviour is not updated at all while the gate is closed.
+pub struct Conditional {
+    inner: Box<dyn Behaviour>,
+    condition: Box<dyn FnMut(&Context) -> bool>,
+}
+
+impl Behaviour for Conditional {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        if (self.condition)(context) {
+            self.inner.update(bucket, context)
+        } else {
+            Vec::new()
+        }
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        self.inner.parameters()
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        self.inner.set_parameter(key, value)
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        self.inner.params()
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        self.inner.state(buckets)
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        self.inner.set_state(state, buckets)
+    }
+}
+
+impl Conditional {
+    /// Gates `inner` on `condition`, which is checked at every update.
+    pub fn new(
+        inner: Box<dyn Behaviour>,
+        condition: impl FnMut(&Context) -> bool + 'static,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Conditional {
+            inner,
+            condition: Box::new(condition),
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{Behaviour, Bucket, Context, Counter, Param, ParameterError, Transfer};
+
+/// A [`Counter`] on another behaviour, adding up everything its transfers
+/// actually move once they are applied.
+///
+/// The wrapper takes the name and parameters of the behaviour it wraps.
+pub struct Counted {
+    inner: Box<dyn Behaviour>,
+    counter: Counter,
+}
+
+impl Behaviour for Counted {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        self.inner
+            .update(bucket, context)
+            .into_iter()
+            .map(|transfer| transfer.counted(self.counter.clone()))
+            .collect()
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        self.inner.parameters()
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        self.inner.set_parameter(key, value)
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        self.inner.params()
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        self.inner.state(buckets)
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        self.inner.set_state(state, buckets)
+    }
+}
+
+impl Counted {
+    /// Counts everything moved by `inner` into `counter`.
+    pub fn new(inner: Box<dyn Behaviour>, counter: Counter) -> Box<dyn Behaviour> {
+        Box::new(Counted { inner, counter })
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+enum Shipments {
+    Constant(Param),
+    Scheduled(Vec<(u64, f64)>),
+}
+
+/// Deliveries of supplies, such as vaccine doses, into the stockpile bucket
+/// it is attached to.
+pub struct Delivery {
+    shipments: Shipments,
+}
+
+impl Behaviour for Delivery {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let start = context.time();
+        let end = start + context.dt();
+        let delivered = match &self.shipments {
+            Shipments::Constant(rate) => rate.value(start) * context.dt(),
+            Shipments::Scheduled(shipments) => shipments
+                .iter()
+                .filter(|(time, _)| (start..end).contains(&(*time as f64)))
+                .map(|(_, amount)| amount)
+                .sum(),
+        };
+        vec![Transfer::inflow(bucket, delivered)]
+    }
+    fn name(&self) -> &str {
+        "delivery"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        match &self.shipments {
+            Shipments::Constant(rate) => vec![("rate", rate.current())],
+            Shipments::Scheduled(_) => Vec::new(),
+        }
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match (&mut self.shipments, key) {
+            (Shipments::Constant(rate), "rate") => *rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        match &mut self.shipments {
+            Shipments::Constant(rate) => vec![("rate", rate)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Delivery {
+    /// Creates a steady supply of `rate` per tick.
+    pub fn constant(rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Delivery {
+            shipments: Shipments::Constant(rate.into()),
+        })
+    }
+    /// Creates shipments of the given amounts arriving at the given ticks.
+    pub fn at(shipments: Vec<(u64, f64)>) -> Box<dyn Behaviour> {
+        Box::new(Delivery {
+            shipments: Shipments::Scheduled(shipments),
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use crate::random::binomial_or_mean;
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Moves a fixed fraction of its bucket into `target` every tick.
+///
+/// A stochastic diffusion instead moves each whole individual in its bucket
+/// independently with that probability, a binomially distributed number.
+pub struct Diffusion {
+    target: Bucket,
+    probability: Param,
+    stochastic: bool,
+}
+
+impl Behaviour for Diffusion {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let p = self.probability.value(time) * context.dt();
+        let to_move = binomial_or_mean(self.stochastic, context, bucket.get(), p);
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "diffusion"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("probability", self.probability.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "probability" => self.probability = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("probability", &mut self.probability)]
+    }
+}
+
+impl Diffusion {
+    /// Creates a diffusion into `target` moving `probability` of the bucket
+    /// per tick.
+    pub fn new(target: Bucket, probability: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Diffusion {
+            target,
+            probability: probability.into(),
+            stochastic: false,
+        })
+    }
+    /// Creates a stochastic diffusion into `target` moving each individual
+    /// with `probability` per tick, drawn from the model's random number
+    /// generator.
+    pub fn stochastic(target: Bucket, probability: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Diffusion {
+            target,
+            probability: probability.into(),
+            stochastic: true,
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Logistic growth of the bucket it is attached to, with intrinsic growth
+/// rate `r` and carrying capacity `k`.
+///
+/// The bucket grows by `r * X * (1 - X / k)` per tick, shrinking instead
+/// when it is above its carrying capacity.
+pub struct LogisticGrowth {
+    r: Param,
+    k: Param,
+}
+
+impl Behaviour for LogisticGrowth {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let x = bucket.get();
+        let k = self.k.value(time);
+        if k <= 0.0 {
+            return vec![Transfer::outflow(bucket, x)];
+        }
+        let growth = self.r.value(time) * x * (1.0 - x / k) * context.dt();
+        if growth >= 0.0 {
+            vec![Transfer::inflow(bucket, growth)]
+        } else {
+            vec![Transfer::outflow(bucket, -growth)]
+        }
+    }
+    fn name(&self) -> &str {
+        "logistic_growth"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("r", self.r.current()), ("k", self.k.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "r" => self.r = value.into(),
+            "k" => self.k = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("r", &mut self.r), ("k", &mut self.k)]
+    }
+}
+
+impl LogisticGrowth {
+    /// Creates logistic growth at intrinsic rate `r` per tick towards the
+    /// carrying capacity `k`.
+    pub fn new(r: impl Into<Param>, k: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(LogisticGrowth {
+            r: r.into(),
+            k: k.into(),
+        })
+    }
+}
+
+/// Bilinear coupling between the bucket it is attached to and `other`, as
+/// between prey and predator or resource and consumer.
+///
+/// Every tick the bucket loses `loss * X * Y` and `other` gains
+/// `gain * X * Y`, where `X` and `Y` are their sizes. Together with growth
+/// of the prey and deaths of the predators this gives the Lotka-Volterra
+/// equations.
+pub struct Interaction {
+    other: Bucket,
+    gain: Param,
+    loss: Param,
+}
+
+impl Behaviour for Interaction {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let encounters = bucket.get() * self.other.get() * context.dt();
+        vec![
+            Transfer::outflow(bucket, self.loss.value(time) * encounters),
+            Transfer::inflow(self.other.clone(), self.gain.value(time) * encounters),
+        ]
+    }
+    fn name(&self) -> &str {
+        "interaction"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("gain", self.gain.current()), ("loss", self.loss.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "gain" => self.gain = value.into(),
+            "loss" => self.loss = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("gain", &mut self.gain), ("loss", &mut self.loss)]
+    }
+}
+
+impl Interaction {
+    /// Creates an interaction in which `other` gains `gain` and the bucket
+    /// loses `loss` per encounter between them.
+    pub fn new(
+        other: Bucket,
+        gain: impl Into<Param>,
+        loss: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Interaction {
+            other,
+            gain: gain.into(),
+            loss: loss.into(),
+        })
+    }
+}
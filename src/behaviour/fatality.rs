@@ -0,0 +1,48 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Deaths caused by the disease, moving a fraction of the infectious bucket
+/// it is attached to into a dedicated `deaths` bucket every tick.
+pub struct InfectionFatality {
+    deaths: Bucket,
+    rate: Param,
+}
+
+impl Behaviour for InfectionFatality {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move = self.rate.value(time) * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.deaths.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "infection_fatality"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl InfectionFatality {
+    /// Creates deaths into `deaths` at per-capita rate `rate` per tick.
+    pub fn new(deaths: Bucket, rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(InfectionFatality {
+            deaths,
+            rate: rate.into(),
+        })
+    }
+    /// Creates deaths into `deaths` such that a fraction `ratio` of
+    /// infections end in death, for a bucket that also recovers at rate
+    /// `recovery` per tick.
+    pub fn with_ratio(deaths: Bucket, ratio: f64, recovery: f64) -> Box<dyn Behaviour> {
+        InfectionFatality::new(deaths, ratio * recovery / (1.0 - ratio))
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{Behaviour, Bucket, Context, Mixing, Param, ParameterError, Transfer};
+
+/// Mass-action transmission from its bucket of susceptibles into `target`,
+/// driven by several infectious buckets of differing infectiousness.
+///
+/// Each infectious bucket contributes its size times its weight to the
+/// force of infection, so an asymptomatic bucket half as infectious as a
+/// symptomatic one would have weight 0.5 against the symptomatic bucket's 1.
+pub struct ForceOfInfection {
+    target: Bucket,
+    sources: Vec<(Bucket, f64)>,
+    beta: Param,
+    mixing: Mixing,
+}
+
+impl Behaviour for ForceOfInfection {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let beta = self.beta.value(context.time());
+        let i: f64 = self
+            .sources
+            .iter()
+            .map(|(source, weight)| source.get() * weight)
+            .sum();
+        let contacts = self.mixing.contacts(i, context);
+        let to_move = beta * bucket.get() * contacts * context.dt();
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "force_of_infection"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("beta", self.beta.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "beta" => self.beta = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("beta", &mut self.beta)]
+    }
+}
+
+impl ForceOfInfection {
+    /// Creates frequency-dependent transmission into `target` from the
+    /// given infectious buckets and their weights, with transmission rate
+    /// `beta`.
+    pub fn new(
+        target: Bucket,
+        sources: Vec<(Bucket, f64)>,
+        beta: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        ForceOfInfection::with_mixing(target, sources, beta, Mixing::Frequency)
+    }
+    /// Creates transmission into `target` from the given infectious buckets
+    /// and their weights, with transmission rate `beta` and the given mixing
+    /// assumption.
+    pub fn with_mixing(
+        target: Bucket,
+        sources: Vec<(Bucket, f64)>,
+        beta: impl Into<Param>,
+        mixing: Mixing,
+    ) -> Box<dyn Behaviour> {
+        Box::new(ForceOfInfection {
+            target,
+            sources,
+            beta: beta.into(),
+            mixing,
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Admission to hospital, moving a fraction `rate` of the infectious bucket
+/// it is attached to into `hospital` every tick.
+pub struct Hospitalization {
+    hospital: Bucket,
+    rate: Param,
+}
+
+impl Behaviour for Hospitalization {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move = self.rate.value(time) * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.hospital.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "hospitalization"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl Hospitalization {
+    /// Creates admission into `hospital` at rate `rate` per tick.
+    pub fn new(hospital: Bucket, rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Hospitalization {
+            hospital,
+            rate: rate.into(),
+        })
+    }
+}
+
+/// Discharge from the hospital bucket it is attached to, either recovered
+/// or dead.
+///
+/// A fraction `rate` of patients leave every tick. Patients occupying one of
+/// the `capacity` beds die with probability `fatality`, and the rest die
+/// with the higher probability `strained_fatality`.
+pub struct HospitalOutcome {
+    recovered: Bucket,
+    deaths: Bucket,
+    rate: Param,
+    capacity: Param,
+    fatality: Param,
+    strained_fatality: Param,
+}
+
+impl Behaviour for HospitalOutcome {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let occupancy = bucket.get();
+        let treated = occupancy.min(self.capacity.value(time).max(0.0));
+        let untreated = occupancy - treated;
+        let scale = self.rate.value(time) * context.dt();
+        let deaths = scale
+            * (treated * self.fatality.value(time)
+                + untreated * self.strained_fatality.value(time));
+        let discharged = scale * occupancy;
+        vec![
+            Transfer::new(bucket.clone(), self.deaths.clone(), deaths),
+            Transfer::new(bucket, self.recovered.clone(), discharged - deaths),
+        ]
+    }
+    fn name(&self) -> &str {
+        "hospital_outcome"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("rate", self.rate.current()),
+            ("capacity", self.capacity.current()),
+            ("fatality", self.fatality.current()),
+            ("strained_fatality", self.strained_fatality.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            "capacity" => self.capacity = value.into(),
+            "fatality" => self.fatality = value.into(),
+            "strained_fatality" => self.strained_fatality = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![
+            ("rate", &mut self.rate),
+            ("capacity", &mut self.capacity),
+            ("fatality", &mut self.fatality),
+            ("strained_fatality", &mut self.strained_fatality),
+        ]
+    }
+}
+
+impl HospitalOutcome {
+    /// Creates discharge into `recovered` and `deaths` at rate `rate` per
+    /// tick, with `capacity` beds and the fatality probabilities of patients
+    /// within and beyond capacity.
+    pub fn new(
+        recovered: Bucket,
+        deaths: Bucket,
+        rate: impl Into<Param>,
+        capacity: impl Into<Param>,
+        fatality: impl Into<Param>,
+        strained_fatality: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(HospitalOutcome {
+            recovered,
+            deaths,
+            rate: rate.into(),
+            capacity: capacity.into(),
+            fatality: fatality.into(),
+            strained_fatality: strained_fatality.into(),
+        })
+    }
+}
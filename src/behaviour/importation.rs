@@ -0,0 +1,70 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+enum Arrivals {
+    Scheduled(Vec<(u64, f64)>),
+    Poisson(Param),
+}
+
+/// Infections arriving from outside the model into the bucket it is
+/// attached to.
+pub struct Importation {
+    arrivals: Arrivals,
+}
+
+impl Behaviour for Importation {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let start = context.time();
+        let end = start + context.dt();
+        let imported = match &mut self.arrivals {
+            Arrivals::Scheduled(arrivals) => arrivals
+                .iter()
+                .filter(|(time, _)| (start..end).contains(&(*time as f64)))
+                .map(|(_, amount)| amount)
+                .sum(),
+            Arrivals::Poisson(rate) => {
+                let mean = rate.value(start) * context.dt();
+                context.rng().poisson(mean) as f64
+            }
+        };
+        vec![Transfer::inflow(bucket, imported)]
+    }
+    fn name(&self) -> &str {
+        "importation"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        match &self.arrivals {
+            Arrivals::Scheduled(_) => Vec::new(),
+            Arrivals::Poisson(rate) => vec![("rate", rate.current())],
+        }
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match (&mut self.arrivals, key) {
+            (Arrivals::Poisson(rate), "rate") => *rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        match &mut self.arrivals {
+            Arrivals::Poisson(rate) => vec![("rate", rate)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Importation {
+    /// Creates importation of the given amounts at the given ticks.
+    pub fn at(arrivals: Vec<(u64, f64)>) -> Box<dyn Behaviour> {
+        Box::new(Importation {
+            arrivals: Arrivals::Scheduled(arrivals),
+        })
+    }
+    /// Creates importation of single infections arriving as a Poisson
+    /// process with `rate` arrivals per tick, drawn from the model's random
+    /// number generator.
+    pub fn poisson(rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Importation {
+            arrivals: Arrivals::Poisson(rate.into()),
+        })
+    }
+}
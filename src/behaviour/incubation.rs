@@ -0,0 +1,50 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Progression from an exposed bucket into `target` once the latent period
+/// is over.
+///
+/// Latent periods are exponentially distributed, so a fraction `sigma` of
+/// the exposed bucket becomes infectious every tick.
+pub struct Incubation {
+    target: Bucket,
+    sigma: Param,
+}
+
+impl Behaviour for Incubation {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move = self.sigma.value(time) * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "incubation"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("sigma", self.sigma.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "sigma" => self.sigma = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("sigma", &mut self.sigma)]
+    }
+}
+
+impl Incubation {
+    /// Creates incubation into `target` at rate `sigma` per tick.
+    pub fn new(target: Bucket, sigma: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Incubation {
+            target,
+            sigma: sigma.into(),
+        })
+    }
+    /// Creates incubation into `target` with a mean latent period of
+    /// `period` ticks.
+    pub fn with_period(target: Bucket, period: f64) -> Box<dyn Behaviour> {
+        Incubation::new(target, 1.0 / period)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::random::binomial_or_mean;
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// How the rate of contact between individuals scales with population.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mixing {
+    /// Contacts per individual are independent of population size, so
+    /// transmission is `beta * S * I / N`.
+    Frequency,
+    /// Contacts per individual grow with population size, so transmission
+    /// is `beta * S * I`.
+    Density,
+}
+
+impl Mixing {
+    /// Returns the effective number of infectious contacts per susceptible,
+    /// given the number of infectious individuals.
+    pub fn contacts(self, infectious: f64, context: &Context) -> f64 {
+        match self {
+            Mixing::Frequency => {
+                let n = context.total();
+                if n > 0.0 {
+                    infectious / n
+                } else {
+                    0.0
+                }
+            }
+            Mixing::Density => infectious,
+        }
+    }
+}
+
+/// Mass-action transmission from its bucket of susceptibles into `target`,
+/// driven by contact with the buckets of infectious individuals.
+///
+/// Only the listed infectious buckets contribute to the force of infection,
+/// so individuals can be removed from transmission by moving them elsewhere,
+/// as with [`Isolation`](crate::Isolation).
+///
+/// Stochastic transmission instead infects each whole susceptible
+/// independently, with probability `beta * I / N * dt` under frequency
+/// dependence, a binomially distributed number.
+pub struct Infection {
+    target: Bucket,
+    infectious: Vec<Bucket>,
+    beta: Param,
+    mixing: Mixing,
+    stochastic: bool,
+}
+
+impl Behaviour for Infection {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let s = bucket.get();
+        let i: f64 = self.infectious.iter().map(Bucket::get).sum();
+        let contacts = self.mixing.contacts(i, context);
+        let p = self.beta.value(time) * contacts * context.dt();
+        let to_move = binomial_or_mean(self.stochastic, context, s, p);
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "infection"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("beta", self.beta.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "beta" => self.beta = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("beta", &mut self.beta)]
+    }
+}
+
+impl Infection {
+    /// Creates frequency-dependent transmission into `target`, which is
+    /// also the infectious bucket, with transmission rate `beta`.
+    pub fn new(target: Bucket, beta: impl Into<Param>) -> Box<dyn Behaviour> {
+        Infection::with_mixing(target.clone(), vec![target], beta, Mixing::Frequency)
+    }
+    /// Creates density-dependent transmission into `target`, which is also
+    /// the infectious bucket, with transmission rate `beta`.
+    pub fn density(target: Bucket, beta: impl Into<Param>) -> Box<dyn Behaviour> {
+        Infection::with_mixing(target.clone(), vec![target], beta, Mixing::Density)
+    }
+    /// Creates frequency-dependent transmission into `target` driven by a
+    /// separate `infectious` bucket, as in a model with a latent stage.
+    pub fn exposing(
+        target: Bucket,
+        infectious: Bucket,
+        beta: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Infection::with_mixing(target, vec![infectious], beta, Mixing::Frequency)
+    }
+    /// Creates transmission into `target` driven by every bucket in
+    /// `infectious` with transmission rate `beta` and the given mixing
+    /// assumption.
+    pub fn with_mixing(
+        target: Bucket,
+        infectious: Vec<Bucket>,
+        beta: impl Into<Param>,
+        mixing: Mixing,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Infection {
+            target,
+            infectious,
+            beta: beta.into(),
+            mixing,
+            stochastic: false,
+        })
+    }
+    /// Creates stochastic transmission into `target` driven by every bucket
+    /// in `infectious`, drawn from the model's random number generator.
+    pub fn stochastic(
+        target: Bucket,
+        infectious: Vec<Bucket>,
+        beta: impl Into<Param>,
+        mixing: Mixing,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Infection {
+            target,
+            infectious,
+            beta: beta.into(),
+            mixing,
+            stochastic: true,
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Isolation of infectious individuals, moving a fraction `rate` of the
+/// bucket it is attached to into `quarantine` every tick.
+///
+/// The quarantine bucket should not be listed as infectious by any
+/// [`Infection`](crate::Infection), so isolated individuals stop
+/// contributing to transmission.
+pub struct Isolation {
+    quarantine: Bucket,
+    rate: Param,
+}
+
+impl Behaviour for Isolation {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move = self.rate.value(time) * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.quarantine.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "isolation"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl Isolation {
+    /// Creates isolation into `quarantine` at rate `rate` per tick.
+    pub fn new(quarantine: Bucket, rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Isolation {
+            quarantine,
+            rate: rate.into(),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Maternal immunity for MSIR models, attached to the bucket of newborns
+/// protected by their mother's antibodies.
+///
+/// Newborns arrive at per-capita rate `birth` across the whole model's
+/// population, and their protection wanes into `susceptible` at rate
+/// `delta`, lasting an exponentially distributed time.
+pub struct MaternalImmunity {
+    susceptible: Bucket,
+    birth: Param,
+    delta: Param,
+}
+
+impl Behaviour for MaternalImmunity {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let dt = context.dt();
+        let births = self.birth.value(time) * context.total() * dt;
+        let to_move = self.delta.value(time) * bucket.get() * dt;
+        vec![
+            Transfer::inflow(bucket.clone(), births),
+            Transfer::new(bucket, self.susceptible.clone(), to_move),
+        ]
+    }
+    fn name(&self) -> &str {
+        "maternal_immunity"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("birth", self.birth.current()),
+            ("delta", self.delta.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "birth" => self.birth = value.into(),
+            "delta" => self.delta = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("birth", &mut self.birth), ("delta", &mut self.delta)]
+    }
+}
+
+impl MaternalImmunity {
+    /// Creates maternal immunity with per-capita birth rate `birth`, waning
+    /// into `susceptible` at rate `delta` per tick.
+    pub fn new(
+        susceptible: Bucket,
+        birth: impl Into<Param>,
+        delta: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(MaternalImmunity {
+            susceptible,
+            birth: birth.into(),
+            delta: delta.into(),
+        })
+    }
+    /// Creates maternal immunity lasting `duration` ticks on average.
+    pub fn with_duration(
+        susceptible: Bucket,
+        birth: impl Into<Param>,
+        duration: f64,
+    ) -> Box<dyn Behaviour> {
+        MaternalImmunity::new(susceptible, birth, 1.0 / duration)
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{Bucket, Context, Param, ParameterError, Transfer};
+
+mod adaptive;
+mod branch;
+mod combinators;
+mod counted;
+mod delivery;
+mod diffusion;
+mod ecology;
+mod fatality;
+mod force;
+mod hospital;
+mod importation;
+mod incubation;
+mod infection;
+mod isolation;
+mod maternal;
+mod rate_flow;
+mod reaction;
+mod reed_frost;
+mod reinfection;
+mod renewal;
+mod seasonal;
+mod spillover;
+mod testing;
+mod tracing;
+mod treatment;
+mod two_dose;
+mod vaccination;
+mod vector;
+mod vital;
+mod waning;
+
+pub use adaptive::{AdaptiveContact, Response};
+pub use branch::Branch;
+pub use combinators::{Conditional, Delayed, Saturating, Scaled};
+pub use counted::Counted;
+pub use delivery::Delivery;
+pub use diffusion::Diffusion;
+pub use ecology::{Interaction, LogisticGrowth};
+pub use fatality::InfectionFatality;
+pub use force::ForceOfInfection;
+pub use hospital::{HospitalOutcome, Hospitalization};
+pub use importation::Importation;
+pub use incubation::Incubation;
+pub use infection::{Infection, Mixing};
+pub use isolation::Isolation;
+pub use maternal::MaternalImmunity;
+pub use rate_flow::RateFlow;
+pub use reaction::Reaction;
+pub use reed_frost::ReedFrost;
+pub use reinfection::Reinfection;
+pub use renewal::Renewal;
+pub use seasonal::Seasonal;
+pub use spillover::Spillover;
+pub use testing::Testing;
+pub use tracing::ContactTracing;
+pub use treatment::Treatment;
+pub use two_dose::TwoDoseVaccination;
+pub use vaccination::Vaccination;
+pub use vector::{VectorBirth, VectorTransmission};
+pub use vital::{Birth, NaturalDeath};
+pub use waning::Waning;
+
+/// Something that moves population out of, or into, the bucket it is
+/// attached to.
+pub trait Behaviour {
+    /// Advances the behaviour by a time step of `context.dt()` against
+    /// `bucket`, the bucket it is attached to, returning the transfers to
+    /// apply. Rates are per unit of time, so flows scale with the step.
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer>;
+    /// Returns a short name for the kind of behaviour, such as `infection`.
+    fn name(&self) -> &str;
+    /// Returns the current value of every tunable parameter.
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+    /// Sets the parameter called `key`, returning an error if the behaviour
+    /// has no such parameter.
+    fn set_parameter(&mut self, key: &str, _value: f64) -> Result<(), ParameterError> {
+        Err(ParameterError::Unknown(key.to_owned()))
+    }
+    /// Returns every parameter that is a [`Param`], by the same keys as
+    /// [`parameters`](Behaviour::parameters), so that it can be saved and
+    /// restored whole, schedule and all, rather than as its current value.
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        Vec::new()
+    }
+    /// Forgets anything the behaviour has accumulated over a run, such as
+    /// queued transfers, so the model can be run again from its start.
+    fn reset(&mut self) {}
+    /// Returns what [`reset`](Behaviour::reset) would forget as numbers, so
+    /// that a checkpoint can hold it. Buckets are written as their position
+    /// in `buckets`, the buckets of the model.
+    fn state(&self, _buckets: &[Bucket]) -> Vec<f64> {
+        Vec::new()
+    }
+    /// Restores what [`state`](Behaviour::state) returned, returning false
+    /// and leaving the behaviour as it was if `state` can't be understood.
+    fn set_state(&mut self, state: &[f64], _buckets: &[Bucket]) -> bool {
+        state.is_empty()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{Behaviour, Bucket, Context, State, Transfer};
+
+enum Destination {
+    Bucket(Bucket),
+    Outflow,
+    Inflow,
+}
+
+/// A flow computed by a closure from a snapshot of the whole model, for
+/// custom dynamics that do not need a behaviour of their own.
+///
+/// The closure returns the amount to move per tick, such as
+/// `|state: &State| beta * state["S"] * state["I"] / state.total()`.
+/// Parameters captured by the closure are not visible to
+/// [`Model::parameters`](crate::Model::parameters).
+pub struct RateFlow {
+    destination: Destination,
+    rate: Box<dyn FnMut(&State) -> f64>,
+}
+
+impl Behaviour for RateFlow {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let state = context.state();
+        let amount = (self.rate)(&state) * context.dt();
+        let transfer = match &self.destination {
+            Destination::Bucket(target) => Transfer::new(bucket, target.clone(), amount),
+            Destination::Outflow => Transfer::outflow(bucket, amount),
+            Destination::Inflow => Transfer::inflow(bucket, amount),
+        };
+        vec![transfer]
+    }
+    fn name(&self) -> &str {
+        "rate_flow"
+    }
+}
+
+impl RateFlow {
+    /// Creates a flow from the bucket it is attached to into `target` at
+    /// the rate computed by `rate`.
+    pub fn new(target: Bucket, rate: impl FnMut(&State) -> f64 + 'static) -> Box<dyn Behaviour> {
+        Box::new(RateFlow {
+            destination: Destination::Bucket(target),
+            rate: Box::new(rate),
+        })
+    }
+    /// Creates a flow out of the model from the bucket it is attached to.
+    pub fn outflow(rate: impl FnMut(&State) -> f64 + 'static) -> Box<dyn Behaviour> {
+        Box::new(RateFlow {
+            destination: Destination::Outflow,
+            rate: Box::new(rate),
+        })
+    }
+    /// Creates a flow of new population into the bucket it is attached to.
+    pub fn inflow(rate: impl FnMut(&State) -> f64 + 'static) -> Box<dyn Behaviour> {
+        Box::new(RateFlow {
+            destination: Destination::Inflow,
+            rate: Box::new(rate),
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{Behaviour, Bucket, Context, State, Transfer};
+
+/// A reaction with mass-action kinetics, as in chemical or epidemic
+/// reaction notation, such as `S + I -> 2I`.
+///
+/// The reaction happens `rate * S^a * I^b ...` times per tick, where `rate`
+/// is computed by a closure from a snapshot of the whole model and `a`,
+/// `b` and so on are the coefficients of the reactants. Each time it
+/// happens it consumes its reactants and produces its products, so only the
+/// net change moves population: `S + I -> 2I` moves one individual from
+/// `S` into `I`. Compartments the reaction empties are matched with those
+/// it fills in order, and whatever is left over leaves the model or enters
+/// it. Parameters captured by the closure are not visible to
+/// [`Model::parameters`](crate::Model::parameters).
+pub struct Reaction {
+    reactants: Vec<(Bucket, u32)>,
+    moves: Vec<(Option<Bucket>, Option<Bucket>, f64)>,
+    rate: Box<dyn FnMut(&State) -> f64>,
+}
+
+impl Behaviour for Reaction {
+    fn update(&mut self, _: Bucket, context: &Context) -> Vec<Transfer> {
+        let state = context.state();
+        let propensity = self
+            .reactants
+            .iter()
+            .fold((self.rate)(&state), |propensity, (bucket, count)| {
+                propensity * bucket.get().powi(*count as i32)
+            });
+        let amount = propensity * context.dt();
+        self.moves
+            .iter()
+            .map(|(from, to, count)| match (from, to) {
+                (Some(from), Some(to)) => Transfer::new(from.clone(), to.clone(), amount * count),
+                (Some(from), None) => Transfer::outflow(from.clone(), amount * count),
+                (None, Some(to)) => Transfer::inflow(to.clone(), amount * count),
+                (None, None) => unreachable!("every move has a bucket"),
+            })
+            .collect()
+    }
+    fn name(&self) -> &str {
+        "reaction"
+    }
+}
+
+impl Reaction {
+    /// Creates a reaction consuming `reactants` and producing `products`,
+    /// each with its coefficient, that happens `rate` times per tick for
+    /// every combination of reactants. The reaction can be attached to any
+    /// bucket.
+    pub fn new(
+        reactants: Vec<(Bucket, u32)>,
+        products: Vec<(Bucket, u32)>,
+        rate: impl FnMut(&State) -> f64 + 'static,
+    ) -> Box<dyn Behaviour> {
+        let mut net: Vec<(Bucket, i64)> = Vec::new();
+        let terms = reactants
+            .iter()
+            .map(|(bucket, count)| (bucket, -i64::from(*count)))
+            .chain(
+                products
+                    .iter()
+                    .map(|(bucket, count)| (bucket, i64::from(*count))),
+            );
+        for (bucket, change) in terms {
+            match net.iter_mut().find(|(existing, _)| existing.ptr_eq(bucket)) {
+                Some((_, total)) => *total += change,
+                None => net.push((bucket.clone(), change)),
+            }
+        }
+        let mut emptied: Vec<(Bucket, i64)> = net
+            .iter()
+            .filter(|(_, change)| *change < 0)
+            .map(|(bucket, change)| (bucket.clone(), -change))
+            .collect();
+        let mut filled: Vec<(Bucket, i64)> = net.into_iter().filter(|(_, c)| *c > 0).collect();
+        let mut moves = Vec::new();
+        let (mut from, mut to) = (0, 0);
+        while from < emptied.len() && to < filled.len() {
+            let count = emptied[from].1.min(filled[to].1);
+            moves.push((
+                Some(emptied[from].0.clone()),
+                Some(filled[to].0.clone()),
+                count as f64,
+            ));
+            emptied[from].1 -= count;
+            filled[to].1 -= count;
+            if emptied[from].1 == 0 {
+                from += 1;
+            }
+            if filled[to].1 == 0 {
+                to += 1;
+            }
+        }
+        for (bucket, count) in emptied.drain(from..).filter(|(_, count)| *count > 0) {
+            moves.push((Some(bucket), None, count as f64));
+        }
+        for (bucket, count) in filled.drain(to..).filter(|(_, count)| *count > 0) {
+            moves.push((None, Some(bucket), count as f64));
+        }
+        Box::new(Reaction {
+            reactants,
+            moves,
+            rate: Box::new(rate),
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Chain-binomial transmission in the style of Reed and Frost, from its
+/// bucket of susceptibles into `target`.
+///
+/// Every tick is one generation, in which each susceptible independently
+/// escapes infection by each of the `I` infectious individuals with
+/// probability `1 - p`, and so is infected with probability `1 - (1 - p)^I`.
+/// The classic model has infectious individuals recover after a single
+/// generation, which is a [`Diffusion`](crate::Diffusion) of rate 1 out of
+/// `target`.
+pub struct ReedFrost {
+    target: Bucket,
+    infectious: Vec<Bucket>,
+    p: Param,
+}
+
+impl Behaviour for ReedFrost {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let p = self.p.value(context.time());
+        let i: f64 = self.infectious.iter().map(Bucket::get).sum();
+        let escape = (1.0 - p).powf(i * context.dt());
+        let to_move = bucket.get() * (1.0 - escape);
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "reed_frost"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("p", self.p.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "p" => self.p = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("p", &mut self.p)]
+    }
+}
+
+impl ReedFrost {
+    /// Creates chain-binomial transmission into `target`, which is also the
+    /// infectious bucket, with per-contact infection probability `p`.
+    pub fn new(target: Bucket, p: impl Into<Param>) -> Box<dyn Behaviour> {
+        ReedFrost::with_sources(target.clone(), vec![target], p)
+    }
+    /// Creates chain-binomial transmission into `target` driven by every
+    /// bucket in `infectious`, with per-contact infection probability `p`.
+    pub fn with_sources(
+        target: Bucket,
+        infectious: Vec<Bucket>,
+        p: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(ReedFrost {
+            target,
+            infectious,
+            p: p.into(),
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{Behaviour, Bucket, Context, Mixing, Param, ParameterError, Transfer};
+
+/// Reinfection of a previously infected bucket, such as the recovered, into
+/// `target`, for immunity that only partly protects against infection.
+///
+/// Transmission works as with [`ForceOfInfection`](crate::ForceOfInfection),
+/// but the bucket's susceptibility is reduced by the fraction `reduction`,
+/// so a reduction of 1 is complete immunity and 0 is none at all.
+pub struct Reinfection {
+    target: Bucket,
+    sources: Vec<(Bucket, f64)>,
+    beta: Param,
+    reduction: Param,
+    mixing: Mixing,
+}
+
+impl Behaviour for Reinfection {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let susceptibility = (1.0 - self.reduction.value(time)).max(0.0);
+        let i: f64 = self
+            .sources
+            .iter()
+            .map(|(source, weight)| source.get() * weight)
+            .sum();
+        let contacts = self.mixing.contacts(i, context);
+        let to_move =
+            self.beta.value(time) * susceptibility * bucket.get() * contacts * context.dt();
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "reinfection"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("beta", self.beta.current()),
+            ("reduction", self.reduction.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "beta" => self.beta = value.into(),
+            "reduction" => self.reduction = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("beta", &mut self.beta), ("reduction", &mut self.reduction)]
+    }
+}
+
+impl Reinfection {
+    /// Creates frequency-dependent reinfection into `target` from the given
+    /// infectious buckets and their weights, with transmission rate `beta`
+    /// and susceptibility reduced by `reduction`.
+    pub fn new(
+        target: Bucket,
+        sources: Vec<(Bucket, f64)>,
+        beta: impl Into<Param>,
+        reduction: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Reinfection::with_mixing(target, sources, beta, reduction, Mixing::Frequency)
+    }
+    /// Creates reinfection as with [`new`](Reinfection::new) under the given
+    /// mixing assumption.
+    pub fn with_mixing(
+        target: Bucket,
+        sources: Vec<(Bucket, f64)>,
+        beta: impl Into<Param>,
+        reduction: impl Into<Param>,
+        mixing: Mixing,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Reinfection {
+            target,
+            sources,
+            beta: beta.into(),
+            reduction: reduction.into(),
+            mixing,
+        })
+    }
+}
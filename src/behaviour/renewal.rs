@@ -0,0 +1,83 @@
+use crate::random::ln_gamma;
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Transmission by the renewal equation, infecting the susceptible bucket
+/// it is attached to into `target` according to the recent incidence of
+/// `target` and a generation-interval kernel.
+///
+/// New infections in a step are
+/// `r * S / N * (w_1 * I_{t-1} + w_2 * I_{t-2} + ...)`, where `I_{t-s}` is
+/// the [incidence](Bucket::incidence) of `target` `s` steps ago and `w_s` is
+/// the kernel, normalised to sum to one, so `r` is the reproduction number.
+/// The epidemic has to be seeded by incidence into `target`, for instance
+/// with an [`Importation`](crate::Importation), since individuals present at
+/// the start have no recorded infection.
+pub struct Renewal {
+    target: Bucket,
+    kernel: Vec<f64>,
+    r: Param,
+}
+
+impl Behaviour for Renewal {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let n = context.total();
+        let weight: f64 = self.kernel.iter().sum();
+        if n <= 0.0 || weight <= 0.0 {
+            return Vec::new();
+        }
+        let pressure: f64 = self
+            .kernel
+            .iter()
+            .enumerate()
+            .map(|(lag, w)| w * self.target.incidence(lag))
+            .sum();
+        let to_move = self.r.value(time) * bucket.get() / n * pressure / weight;
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "renewal"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("r", self.r.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "r" => self.r = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("r", &mut self.r)]
+    }
+}
+
+impl Renewal {
+    /// Creates renewal-equation transmission into `target` with
+    /// reproduction number `r`, where `kernel[s - 1]` weighs the incidence
+    /// `s` steps ago.
+    pub fn new(target: Bucket, r: impl Into<Param>, kernel: Vec<f64>) -> Box<dyn Behaviour> {
+        Box::new(Renewal {
+            target,
+            kernel,
+            r: r.into(),
+        })
+    }
+    /// Creates renewal-equation transmission with a gamma-distributed
+    /// generation interval of the given `mean` and standard deviation `sd`
+    /// in steps, discretised and truncated four standard deviations past
+    /// the mean.
+    pub fn gamma(target: Bucket, r: impl Into<Param>, mean: f64, sd: f64) -> Box<dyn Behaviour> {
+        let shape = (mean / sd).powi(2);
+        let scale = sd * sd / mean;
+        let length = (mean + 4.0 * sd).ceil().max(1.0) as usize;
+        let kernel = (1..=length)
+            .map(|s| {
+                let s = s as f64;
+                ((shape - 1.0) * s.ln() - s / scale - ln_gamma(shape) - shape * scale.ln()).exp()
+            })
+            .collect();
+        Renewal::new(target, r, kernel)
+    }
+}
@@ -0,0 +1,98 @@
+use std::f64::consts::PI;
+
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Periodic forcing of another behaviour, such as seasonal transmission.
+///
+/// Every transfer of the wrapped behaviour is scaled by
+/// `1 + amplitude * cos(2π(t - phase) / period)`, so forcing an
+/// [`Infection`](crate::Infection) gives a transmission rate of
+/// `beta(t) = beta * (1 + amplitude * cos(2π(t - phase) / period))`.
+///
+/// The wrapper takes the name of the behaviour it wraps, so parameters of
+/// both are addressed through the same path.
+pub struct Seasonal {
+    inner: Box<dyn Behaviour>,
+    amplitude: Param,
+    period: Param,
+    phase: Param,
+}
+
+impl Seasonal {
+    /// Forces `inner` with the given relative `amplitude` and a `period` in
+    /// ticks, peaking at tick 0.
+    pub fn new(
+        inner: Box<dyn Behaviour>,
+        amplitude: impl Into<Param>,
+        period: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Seasonal::with_phase(inner, amplitude, period, 0.0)
+    }
+    /// Forces `inner` with the given relative `amplitude` and a `period` in
+    /// ticks, peaking at tick `phase`.
+    pub fn with_phase(
+        inner: Box<dyn Behaviour>,
+        amplitude: impl Into<Param>,
+        period: impl Into<Param>,
+        phase: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Seasonal {
+            inner,
+            amplitude: amplitude.into(),
+            period: period.into(),
+            phase: phase.into(),
+        })
+    }
+    fn factor(&self, time: f64) -> f64 {
+        let amplitude = self.amplitude.value(time);
+        let phase = self.phase.value(time);
+        let period = self.period.value(time);
+        1.0 + amplitude * (2.0 * PI * (time - phase) / period).cos()
+    }
+}
+
+impl Behaviour for Seasonal {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let factor = self.factor(context.time());
+        let mut transfers = self.inner.update(bucket, context);
+        transfers
+            .iter_mut()
+            .for_each(|transfer| transfer.amount *= factor);
+        transfers
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        let mut parameters = self.inner.parameters();
+        parameters.push(("amplitude", self.amplitude.current()));
+        parameters.push(("period", self.period.current()));
+        parameters.push(("phase", self.phase.current()));
+        parameters
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "amplitude" => self.amplitude = value.into(),
+            "period" => self.period = value.into(),
+            "phase" => self.phase = value.into(),
+            _ => return self.inner.set_parameter(key, value),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        let mut params = self.inner.params();
+        params.push(("amplitude", &mut self.amplitude));
+        params.push(("period", &mut self.period));
+        params.push(("phase", &mut self.phase));
+        params
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+    fn state(&self, buckets: &[Bucket]) -> Vec<f64> {
+        self.inner.state(buckets)
+    }
+    fn set_state(&mut self, state: &[f64], buckets: &[Bucket]) -> bool {
+        self.inner.set_state(state, buckets)
+    }
+}
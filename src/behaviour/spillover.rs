@@ -0,0 +1,55 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Zoonotic spillover, infecting the susceptibles of the bucket it is
+/// attached to from an animal reservoir that shares the model.
+///
+/// Each susceptible is infected into `target` at rate `rate` times the size
+/// of the infected `reservoir` buckets, independently of transmission
+/// between humans, so animal and human sub-models can be coupled in one
+/// [`Model`](crate::Model).
+pub struct Spillover {
+    target: Bucket,
+    reservoir: Vec<Bucket>,
+    rate: Param,
+}
+
+impl Behaviour for Spillover {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let reservoir: f64 = self.reservoir.iter().map(Bucket::get).sum();
+        let to_move = self.rate.value(time) * reservoir * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "spillover"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl Spillover {
+    /// Creates spillover into `target` from the infected `reservoir` at rate
+    /// `rate` per susceptible and infected animal per tick.
+    pub fn new(
+        target: Bucket,
+        reservoir: Vec<Bucket>,
+        rate: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Spillover {
+            target,
+            reservoir,
+            rate: rate.into(),
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Testing of the bucket it is attached to, moving the cases it detects
+/// into `detected` and leaving undetected cases where they are.
+///
+/// A fraction `rate` of the bucket is tested every tick and a fraction
+/// `sensitivity` of those tests detect the infection. Detected cases can
+/// also be counted into a `reported` sink, giving the reported cases as a
+/// series of their own, apart from the true incidence.
+pub struct Testing {
+    detected: Bucket,
+    reported: Option<Bucket>,
+    rate: Param,
+    sensitivity: Param,
+}
+
+impl Behaviour for Testing {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move =
+            self.rate.value(time) * self.sensitivity.value(time) * bucket.get() * context.dt();
+        let mut transfers = vec![Transfer::new(bucket, self.detected.clone(), to_move)];
+        if let Some(reported) = &self.reported {
+            transfers.push(Transfer::inflow(reported.clone(), to_move));
+        }
+        transfers
+    }
+    fn name(&self) -> &str {
+        "testing"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("rate", self.rate.current()),
+            ("sensitivity", self.sensitivity.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            "sensitivity" => self.sensitivity = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![
+            ("rate", &mut self.rate),
+            ("sensitivity", &mut self.sensitivity),
+        ]
+    }
+}
+
+impl Testing {
+    /// Creates testing at rate `rate` per tick with the given sensitivity,
+    /// moving detected cases into `detected`.
+    pub fn new(
+        detected: Bucket,
+        rate: impl Into<Param>,
+        sensitivity: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Testing {
+            detected,
+            reported: None,
+            rate: rate.into(),
+            sensitivity: sensitivity.into(),
+        })
+    }
+    /// Creates testing as with [`new`](Testing::new) that also counts every
+    /// detected case into the sink `reported`.
+    pub fn reporting(
+        detected: Bucket,
+        reported: Bucket,
+        rate: impl Into<Param>,
+        sensitivity: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Testing {
+            detected,
+            reported: Some(reported),
+            rate: rate.into(),
+            sensitivity: sensitivity.into(),
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Contact tracing, moving the traced contacts of newly detected cases from
+/// the bucket it is attached to into `quarantine`.
+///
+/// Every growth of the `detected` bucket counts as newly detected cases, so
+/// it should be a compartment that only fills, such as a sink counting
+/// detections. Each case names `contacts` contacts, a fraction `coverage`
+/// of which are found `delay` ticks after the case was detected. Contacts
+/// are spread over the living population, so the bucket loses its share
+/// of them.
+pub struct ContactTracing {
+    quarantine: Bucket,
+    detected: Bucket,
+    contacts: Param,
+    coverage: Param,
+    delay: f64,
+    last: Option<f64>,
+    pending: VecDeque<(f64, f64)>,
+}
+
+impl Behaviour for ContactTracing {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let detected = self.detected.get();
+        let cases = (detected - self.last.unwrap_or(detected)).max(0.0);
+        self.last = Some(detected);
+        if cases > 0.0 {
+            self.pending.push_back((time, cases));
+        }
+        let mut due = 0.0;
+        while let Some(&(detection, cases)) = self.pending.front() {
+            if detection + self.delay > time {
+                break;
+            }
+            due += cases;
+            self.pending.pop_front();
+        }
+        let total = context.total();
+        if due == 0.0 || total <= 0.0 {
+            return Vec::new();
+        }
+        let traced = self.contacts.value(time) * self.coverage.value(time) * due;
+        let to_move = traced * bucket.get() / total;
+        vec![Transfer::new(bucket, self.quarantine.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "contact_tracing"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("contacts", self.contacts.current()),
+            ("coverage", self.coverage.current()),
+            ("delay", self.delay),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "contacts" => self.contacts = value.into(),
+            "coverage" => self.coverage = value.into(),
+            "delay" => self.delay = value.max(0.0),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![
+            ("contacts", &mut self.contacts),
+            ("coverage", &mut self.coverage),
+        ]
+    }
+    fn reset(&mut self) {
+        self.last = None;
+        self.pending.clear();
+    }
+    fn state(&self, _: &[Bucket]) -> Vec<f64> {
+        // The last detected count, or NaN before the first update, then the
+        // cases awaiting tracing.
+        let mut state = vec![self.last.unwrap_or(f64::NAN)];
+        state.extend(
+            self.pending
+                .iter()
+                .flat_map(|(detection, cases)| [*detection, *cases]),
+        );
+        state
+    }
+    fn set_state(&mut self, state: &[f64], _: &[Bucket]) -> bool {
+        let (last, pending) = match state.split_first() {
+            Some(split) if split.1.len().is_multiple_of(2) => split,
+            _ => return false,
+        };
+        self.last = Some(*last).filter(|last| !last.is_nan());
+        self.pending = pending.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        true
+    }
+}
+
+impl ContactTracing {
+    /// Creates tracing into `quarantine` of `contacts` contacts per case
+    /// detected into `detected`, finding a fraction `coverage` of them after
+    /// `delay` ticks.
+    pub fn new(
+        quarantine: Bucket,
+        detected: Bucket,
+        contacts: impl Into<Param>,
+        coverage: impl Into<Param>,
+        delay: f64,
+    ) -> Box<dyn Behaviour> {
+        Box::new(ContactTracing {
+            quarantine,
+            detected,
+            contacts: contacts.into(),
+            coverage: coverage.into(),
+            delay,
+            last: None,
+            pending: VecDeque::new(),
+        })
+    }
+}
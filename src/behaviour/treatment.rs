@@ -0,0 +1,69 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Treatment of infectious individuals, moving the bucket it is attached to
+/// onto the faster recovery track of `treated`.
+///
+/// A fraction `coverage` of the bucket has access to treatment, and those
+/// with access start it at rate `rate`, competing with recovery while they
+/// wait. The treated bucket should recover
+/// faster than the untreated one, for instance through a higher
+/// [`Rate`](crate::Rate), and be less infectious or not infectious at all.
+pub struct Treatment {
+    treated: Bucket,
+    coverage: Param,
+    rate: Param,
+}
+
+impl Behaviour for Treatment {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move =
+            self.coverage.value(time) * self.rate.value(time) * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.treated.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "treatment"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("coverage", self.coverage.current()),
+            ("rate", self.rate.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "coverage" => self.coverage = value.into(),
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("coverage", &mut self.coverage), ("rate", &mut self.rate)]
+    }
+}
+
+impl Treatment {
+    /// Creates treatment into `treated` for a fraction `coverage` of the
+    /// bucket, started at rate `rate` per tick.
+    pub fn new(
+        treated: Bucket,
+        coverage: impl Into<Param>,
+        rate: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Treatment {
+            treated,
+            coverage: coverage.into(),
+            rate: rate.into(),
+        })
+    }
+    /// Creates treatment started `delay` ticks after infection on average by
+    /// those with access to it.
+    pub fn with_delay(
+        treated: Bucket,
+        coverage: impl Into<Param>,
+        delay: f64,
+    ) -> Box<dyn Behaviour> {
+        Treatment::new(treated, coverage, 1.0 / delay)
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// A two-dose vaccination campaign for the susceptible bucket it is attached
+/// to, moving recipients of a first dose into `first` and, `interval` ticks
+/// later, on into `second` as they receive their second dose.
+///
+/// Up to `doses` first doses are given every tick. Second doses follow each
+/// day's recipients as a delayed flow, so those who have since left `first`,
+/// by infection or death, are not given one. Protection differs between the
+/// two compartments through their susceptibility, for instance with
+/// [`Transmission::reduced`](crate::Transmission::reduced) set to each
+/// dose's efficacy.
+///
+/// When drawing from a `stockpile`, as with
+/// [`Vaccination`](crate::Vaccination), second doses are given before any
+/// new first doses, and those that cannot be given yet wait for the next
+/// delivery.
+pub struct TwoDoseVaccination {
+    first: Bucket,
+    second: Bucket,
+    stockpile: Option<Bucket>,
+    doses: Param,
+    interval: f64,
+    pending: VecDeque<(f64, f64)>,
+}
+
+impl Behaviour for TwoDoseVaccination {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let awaiting: f64 = self.pending.iter().map(|(_, doses)| doses).sum();
+        let remaining = if awaiting > 0.0 {
+            (self.first.get() / awaiting).min(1.0)
+        } else {
+            1.0
+        };
+        let mut due = 0.0;
+        while let Some(&(given, doses)) = self.pending.front() {
+            if given + self.interval > time {
+                break;
+            }
+            due += doses;
+            self.pending.pop_front();
+        }
+        let wanted = due * remaining;
+        let mut second_doses = wanted;
+        let mut doses = (self.doses.value(time) * context.dt()).min(bucket.get());
+        if let Some(stockpile) = &self.stockpile {
+            let stock = stockpile.get().max(0.0);
+            second_doses = second_doses.min(stock);
+            doses = doses.min(stock - second_doses);
+            if second_doses < wanted {
+                let waiting = due * (wanted - second_doses) / wanted;
+                let overdue = time - self.interval;
+                self.pending.push_front((overdue, waiting));
+            }
+        }
+        if doses > 0.0 {
+            self.pending.push_back((time, doses));
+        }
+        let mut transfers = vec![
+            Transfer::new(bucket, self.first.clone(), doses),
+            Transfer::new(self.first.clone(), self.second.clone(), second_doses),
+        ];
+        if let Some(stockpile) = &self.stockpile {
+            transfers.push(Transfer::outflow(stockpile.clone(), doses + second_doses));
+        }
+        transfers
+    }
+    fn name(&self) -> &str {
+        "two_dose_vaccination"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("doses", self.doses.current()), ("interval", self.interval)]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "doses" => self.doses = value.into(),
+            "interval" => self.interval = value.max(0.0),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("doses", &mut self.doses)]
+    }
+    fn reset(&mut self) {
+        self.pending.clear();
+    }
+    fn state(&self, _: &[Bucket]) -> Vec<f64> {
+        self.pending
+            .iter()
+            .flat_map(|(given, doses)| [*given, *doses])
+            .collect()
+    }
+    fn set_state(&mut self, state: &[f64], _: &[Bucket]) -> bool {
+        if !state.len().is_multiple_of(2) {
+            return false;
+        }
+        self.pending = state.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        true
+    }
+}
+
+impl TwoDoseVaccination {
+    /// Creates a campaign of up to `doses` first doses per tick into
+    /// `first`, each followed by a second dose into `second` after
+    /// `interval` ticks.
+    pub fn new(
+        first: Bucket,
+        second: Bucket,
+        doses: impl Into<Param>,
+        interval: f64,
+    ) -> Box<dyn Behaviour> {
+        Box::new(TwoDoseVaccination {
+            first,
+            second,
+            stockpile: None,
+            doses: doses.into(),
+            interval,
+            pending: VecDeque::new(),
+        })
+    }
+    /// Creates a campaign as with [`new`](TwoDoseVaccination::new), drawing
+    /// both doses from `stockpile`.
+    pub fn from_stockpile(
+        first: Bucket,
+        second: Bucket,
+        stockpile: Bucket,
+        doses: impl Into<Param>,
+        interval: f64,
+    ) -> Box<dyn Behaviour> {
+        Box::new(TwoDoseVaccination {
+            first,
+            second,
+            stockpile: Some(stockpile),
+            doses: doses.into(),
+            interval,
+            pending: VecDeque::new(),
+        })
+    }
+}
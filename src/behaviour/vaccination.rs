@@ -0,0 +1,95 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Vaccination of the susceptible bucket it is attached to, moving those
+/// protected by a dose into `target`.
+///
+/// Up to `doses` doses are given every tick, and each protects its recipient
+/// with probability `efficacy`. Recipients who are not protected stay in the
+/// susceptible bucket.
+///
+/// Doses can be drawn from a `stockpile` bucket, so no more are given than
+/// it holds. The stockpile should be a [sink](Bucket::sink) so that stored
+/// doses are not counted as population, and is refilled by a
+/// [`Delivery`](crate::Delivery).
+pub struct Vaccination {
+    target: Bucket,
+    stockpile: Option<Bucket>,
+    doses: Param,
+    efficacy: Param,
+}
+
+impl Behaviour for Vaccination {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let mut doses = (self.doses.value(time) * context.dt()).min(bucket.get());
+        if let Some(stockpile) = &self.stockpile {
+            doses = doses.min(stockpile.get().max(0.0));
+        }
+        let mut transfers = vec![Transfer::new(
+            bucket,
+            self.target.clone(),
+            doses * self.efficacy.value(time),
+        )];
+        if let Some(stockpile) = &self.stockpile {
+            transfers.push(Transfer::outflow(stockpile.clone(), doses));
+        }
+        transfers
+    }
+    fn name(&self) -> &str {
+        "vaccination"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("doses", self.doses.current()),
+            ("efficacy", self.efficacy.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "doses" => self.doses = value.into(),
+            "efficacy" => self.efficacy = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("doses", &mut self.doses), ("efficacy", &mut self.efficacy)]
+    }
+}
+
+impl Vaccination {
+    /// Creates vaccination into `target` of up to `doses` perfectly
+    /// effective doses per tick.
+    pub fn new(target: Bucket, doses: impl Into<Param>) -> Box<dyn Behaviour> {
+        Vaccination::with_efficacy(target, doses, 1.0)
+    }
+    /// Creates vaccination into `target` of up to `doses` doses per tick,
+    /// each protecting its recipient with probability `efficacy`.
+    pub fn with_efficacy(
+        target: Bucket,
+        doses: impl Into<Param>,
+        efficacy: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Vaccination {
+            target,
+            stockpile: None,
+            doses: doses.into(),
+            efficacy: efficacy.into(),
+        })
+    }
+    /// Creates vaccination as with [`with_efficacy`](Vaccination::with_efficacy),
+    /// drawing every dose given from `stockpile`.
+    pub fn from_stockpile(
+        target: Bucket,
+        stockpile: Bucket,
+        doses: impl Into<Param>,
+        efficacy: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(Vaccination {
+            target,
+            stockpile: Some(stockpile),
+            doses: doses.into(),
+            efficacy: efficacy.into(),
+        })
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Which way a [`VectorTransmission`] carries infection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    ToHost,
+    ToVector,
+}
+
+/// Transmission between hosts and the vectors that bite them, as in the
+/// Ross-Macdonald model of malaria.
+///
+/// Each vector bites `biting_rate` hosts per tick and a bite passes on
+/// infection with probability `probability`. Bites are spread across the
+/// host population given by `hosts`, so infection of susceptible hosts is
+/// `a * b * S_h * I_v / N_h` and infection of susceptible vectors is
+/// `a * c * S_v * I_h / N_h`. Host and vector buckets live in the same
+/// model, so the host population is listed explicitly rather than taken
+/// from the model's total.
+pub struct VectorTransmission {
+    target: Bucket,
+    infectious: Vec<Bucket>,
+    hosts: Vec<Bucket>,
+    biting_rate: Param,
+    probability: Param,
+    direction: Direction,
+}
+
+impl Behaviour for VectorTransmission {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let hosts: f64 = self.hosts.iter().map(Bucket::get).sum();
+        if hosts <= 0.0 {
+            return Vec::new();
+        }
+        let infectious: f64 = self.infectious.iter().map(Bucket::get).sum();
+        let bites = self.biting_rate.value(time) * self.probability.value(time);
+        let to_move = bites * bucket.get() * infectious / hosts * context.dt();
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        match self.direction {
+            Direction::ToHost => "vector_to_host",
+            Direction::ToVector => "host_to_vector",
+        }
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("biting_rate", self.biting_rate.current()),
+            ("probability", self.probability.current()),
+        ]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "biting_rate" => self.biting_rate = value.into(),
+            "probability" => self.probability = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![
+            ("biting_rate", &mut self.biting_rate),
+            ("probability", &mut self.probability),
+        ]
+    }
+}
+
+impl VectorTransmission {
+    /// Creates infection of the susceptible hosts it is attached to into
+    /// `target` by the `infectious` vectors, biting the `hosts`.
+    pub fn to_host(
+        target: Bucket,
+        infectious: Vec<Bucket>,
+        hosts: Vec<Bucket>,
+        biting_rate: impl Into<Param>,
+        probability: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(VectorTransmission {
+            target,
+            infectious,
+            hosts,
+            biting_rate: biting_rate.into(),
+            probability: probability.into(),
+            direction: Direction::ToHost,
+        })
+    }
+    /// Creates infection of the susceptible vectors it is attached to into
+    /// `target` by biting the `infectious` hosts, out of all `hosts`.
+    pub fn to_vector(
+        target: Bucket,
+        infectious: Vec<Bucket>,
+        hosts: Vec<Bucket>,
+        biting_rate: impl Into<Param>,
+        probability: impl Into<Param>,
+    ) -> Box<dyn Behaviour> {
+        Box::new(VectorTransmission {
+            target,
+            infectious,
+            hosts,
+            biting_rate: biting_rate.into(),
+            probability: probability.into(),
+            direction: Direction::ToVector,
+        })
+    }
+}
+
+/// Emergence of new vectors into the bucket it is attached to, at
+/// per-capita rate `rate` across the whole vector population `vectors`.
+///
+/// With vector deaths at the same rate, through
+/// [`NaturalDeath`](crate::NaturalDeath) on every vector bucket, the vector
+/// population stays constant while its turnover clears infection.
+pub struct VectorBirth {
+    vectors: Vec<Bucket>,
+    rate: Param,
+}
+
+impl Behaviour for VectorBirth {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let vectors: f64 = self.vectors.iter().map(Bucket::get).sum();
+        let births = self.rate.value(time) * vectors * context.dt();
+        vec![Transfer::inflow(bucket, births)]
+    }
+    fn name(&self) -> &str {
+        "vector_birth"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl VectorBirth {
+    /// Creates `rate` births per vector in `vectors` every tick.
+    pub fn new(vectors: Vec<Bucket>, rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(VectorBirth {
+            vectors,
+            rate: rate.into(),
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Births into the bucket it is attached to.
+pub struct Birth {
+    rate: Param,
+    per_capita: bool,
+}
+
+impl Behaviour for Birth {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let births = if self.per_capita {
+            self.rate.value(time) * context.total()
+        } else {
+            self.rate.value(time)
+        };
+        vec![Transfer::inflow(bucket, births * context.dt())]
+    }
+    fn name(&self) -> &str {
+        "birth"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("rate", self.rate.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "rate" => self.rate = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("rate", &mut self.rate)]
+    }
+}
+
+impl Birth {
+    /// Creates `rate` births every tick, regardless of population size.
+    pub fn constant(rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Birth {
+            rate: rate.into(),
+            per_capita: false,
+        })
+    }
+    /// Creates `rate` births per member of the whole model's population
+    /// every tick.
+    pub fn per_capita(rate: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Birth {
+            rate: rate.into(),
+            per_capita: true,
+        })
+    }
+}
+
+/// Deaths from causes other than the disease, removing a fraction `mu` of
+/// the bucket it is attached to every tick.
+pub struct NaturalDeath {
+    mu: Param,
+}
+
+impl Behaviour for NaturalDeath {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let deaths = self.mu.value(time) * bucket.get() * context.dt();
+        vec![Transfer::outflow(bucket, deaths)]
+    }
+    fn name(&self) -> &str {
+        "natural_death"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("mu", self.mu.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "mu" => self.mu = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("mu", &mut self.mu)]
+    }
+}
+
+impl NaturalDeath {
+    /// Creates deaths at per-capita rate `mu` per tick.
+    pub fn new(mu: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(NaturalDeath { mu: mu.into() })
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{Behaviour, Bucket, Context, Param, ParameterError, Transfer};
+
+/// Loss of immunity, moving a recovered bucket back into `target`.
+///
+/// Immunity lasts an exponentially distributed time, so a fraction `omega`
+/// of the bucket loses its immunity every tick.
+pub struct Waning {
+    target: Bucket,
+    omega: Param,
+}
+
+impl Behaviour for Waning {
+    fn update(&mut self, bucket: Bucket, context: &Context) -> Vec<Transfer> {
+        let time = context.time();
+        let to_move = self.omega.value(time) * bucket.get() * context.dt();
+        vec![Transfer::new(bucket, self.target.clone(), to_move)]
+    }
+    fn name(&self) -> &str {
+        "waning"
+    }
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("omega", self.omega.current())]
+    }
+    fn set_parameter(&mut self, key: &str, value: f64) -> Result<(), ParameterError> {
+        match key {
+            "omega" => self.omega = value.into(),
+            _ => return Err(ParameterError::Unknown(key.to_owned())),
+        }
+        Ok(())
+    }
+    fn params(&mut self) -> Vec<(&'static str, &mut Param)> {
+        vec![("omega", &mut self.omega)]
+    }
+}
+
+impl Waning {
+    /// Creates waning into `target` at rate `omega` per tick.
+    pub fn new(target: Bucket, omega: impl Into<Param>) -> Box<dyn Behaviour> {
+        Box::new(Waning {
+            target,
+            omega: omega.into(),
+        })
+    }
+    /// Creates waning into `target` where immunity lasts `duration` ticks on
+    /// average.
+    pub fn with_duration(target: Bucket, duration: f64) -> Box<dyn Behaviour> {
+        Waning::new(target, 1.0 / duration)
+    }
+}
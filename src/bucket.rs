@@ -0,0 +1,291 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+use std::ops::{AddAssign, SubAssign};
+
+use crate::{Behaviour, Context, Counter, Transfer};
+
+/// A behaviour attached to a bucket, shared so it can be inspected and tuned
+/// while the model runs.
+pub type SharedBehaviour = Rc<RefCell<Box<dyn Behaviour>>>;
+
+/// A bucket holding a quantity that no population can have.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuantityError {
+    /// The bucket with the given name would hold a negative quantity.
+    Negative(String, f64),
+    /// The bucket with the given name would hold an infinite or NaN
+    /// quantity.
+    NotFinite(String, f64),
+}
+
+impl QuantityError {
+    /// Returns an error if `quantity` can't be held by the bucket called
+    /// `name`.
+    pub(crate) fn check(name: &str, quantity: f64) -> Result<(), QuantityError> {
+        if !quantity.is_finite() {
+            Err(QuantityError::NotFinite(name.to_owned(), quantity))
+        } else if quantity < 0.0 {
+            Err(QuantityError::Negative(name.to_owned(), quantity))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Display for QuantityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantityError::Negative(name, quantity) => {
+                write!(f, "bucket `{}` would hold negative {}", name, quantity)
+            }
+            QuantityError::NotFinite(name, quantity) => {
+                write!(f, "bucket `{}` would hold {}", name, quantity)
+            }
+        }
+    }
+}
+
+impl Error for QuantityError {}
+
+/// The shared state behind a [`Bucket`] handle.
+#[derive(Default)]
+pub struct BucketState {
+    name: String,
+    quantity: f64,
+    sink: bool,
+    behaviours: Vec<SharedBehaviour>,
+    /// How much each behaviour has moved, in the order of `behaviours`.
+    flows: Vec<Counter>,
+    inflow: f64,
+    incidence: VecDeque<f64>,
+    cumulative: f64,
+}
+
+/// A named compartment holding some quantity of population.
+///
+/// Buckets are cheap handles to shared state: cloning a bucket yields another
+/// handle to the same compartment, which is how behaviours refer to the
+/// buckets they move population into.
+#[derive(Clone, Default)]
+pub struct Bucket {
+    state: Rc<RefCell<BucketState>>,
+}
+
+impl Bucket {
+    /// Creates an empty bucket with the given name.
+    pub fn new(name: &'_ str) -> Bucket {
+        Bucket::default().with_name(name)
+    }
+    /// Creates an empty sink with the given name.
+    ///
+    /// Sinks collect population that has left the living population, such
+    /// as deaths. They are excluded from the population that behaviours see
+    /// through [`Context::total`].
+    pub fn sink(name: &'_ str) -> Bucket {
+        let bucket = Bucket::new(name);
+        bucket.state.borrow_mut().sink = true;
+        bucket
+    }
+    /// Returns true if the bucket is a sink.
+    pub fn is_sink(&self) -> bool {
+        self.state.borrow().sink
+    }
+    pub(crate) fn update(&self, context: &Context) -> f64 {
+        self.transfers(context)
+            .into_iter()
+            .map(|transfer| transfer.net_change(transfer.apply()))
+            .sum()
+    }
+    pub(crate) fn transfers(&self, context: &Context) -> Vec<Transfer> {
+        let (bs, flows) = {
+            let state = self.state.borrow();
+            (state.behaviours.clone(), state.flows.clone())
+        };
+        bs.iter()
+            .zip(flows)
+            .flat_map(|(bs, flow)| self.traced(bs, flow, context))
+            .collect()
+    }
+    fn traced(
+        &self,
+        behaviour: &SharedBehaviour,
+        flow: Counter,
+        context: &Context,
+    ) -> Vec<Transfer> {
+        let mut behaviour = behaviour.borrow_mut();
+        let transfers = behaviour.update(self.clone(), context);
+        context.trace(behaviour.name(), &transfers);
+        transfers
+            .into_iter()
+            .map(|transfer| transfer.counted(flow.clone()))
+            .collect()
+    }
+    /// Renames the bucket.
+    pub fn set_name(&mut self, name: &'_ str) {
+        self.state.borrow_mut().name = name.to_owned();
+    }
+    /// Renames the bucket, returning it for chaining.
+    pub fn with_name(self, name: &'_ str) -> Self {
+        self.state.borrow_mut().name = name.to_owned();
+        self
+    }
+    /// Returns the current quantity held in the bucket.
+    pub fn get(&self) -> f64 {
+        self.state.borrow().quantity
+    }
+    /// Returns how much population moved into the bucket during the step
+    /// `lag` steps before the most recent one, so a lag of 0 gives the
+    /// incidence of the last step taken.
+    ///
+    /// Only as many steps are kept as the model's
+    /// [incidence history](crate::Model::set_incidence_history), and older
+    /// steps read as zero.
+    pub fn incidence(&self, lag: usize) -> f64 {
+        self.state
+            .borrow()
+            .incidence
+            .get(lag)
+            .cloned()
+            .unwrap_or(0.0)
+    }
+    /// Returns how much population has moved into the bucket since it was
+    /// created or its model was last reset.
+    pub fn cumulative_incidence(&self) -> f64 {
+        self.state.borrow().cumulative
+    }
+    pub(crate) fn set(&self, quantity: f64) {
+        self.state.borrow_mut().quantity = quantity;
+    }
+    pub(crate) fn receive(&self, amount: f64) {
+        let mut state = self.state.borrow_mut();
+        state.quantity += amount;
+        state.inflow += amount;
+        state.cumulative += amount;
+    }
+    pub(crate) fn reset(&self, quantity: f64) {
+        let behaviours = {
+            let mut state = self.state.borrow_mut();
+            state.quantity = quantity;
+            state.inflow = 0.0;
+            state.incidence.clear();
+            state.cumulative = 0.0;
+            state.flows.iter().for_each(Counter::reset);
+            state.behaviours.clone()
+        };
+        behaviours
+            .iter()
+            .for_each(|behaviour| behaviour.borrow_mut().reset());
+    }
+    /// Returns the incidence of every step kept, most recent first, the
+    /// cumulative incidence and the flow through every behaviour, which is
+    /// what a checkpoint keeps of the bucket beyond its quantity.
+    pub(crate) fn totals(&self) -> (Vec<f64>, f64, Vec<f64>) {
+        let state = self.state.borrow();
+        (
+            state.incidence.iter().cloned().collect(),
+            state.cumulative,
+            state.flows.iter().map(Counter::get).collect(),
+        )
+    }
+    /// Restores what [`totals`](Bucket::totals) returned.
+    pub(crate) fn set_totals(&self, incidence: &[f64], cumulative: f64, flows: &[f64]) {
+        let mut state = self.state.borrow_mut();
+        state.incidence = incidence.iter().cloned().collect();
+        state.cumulative = cumulative;
+        state.flows.iter().zip(flows).for_each(|(counter, total)| {
+            counter.reset();
+            counter.add(*total);
+        });
+    }
+    pub(crate) fn record_incidence(&self, history: usize) {
+        let mut state = self.state.borrow_mut();
+        let inflow = state.inflow;
+        state.inflow = 0.0;
+        state.incidence.push_front(inflow);
+        state.incidence.truncate(history);
+    }
+    /// Returns true if both handles refer to the same bucket.
+    pub fn ptr_eq(&self, other: &Bucket) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+    /// Returns the name of the bucket.
+    pub fn name(&self) -> String {
+        self.state.borrow().name.clone()
+    }
+    /// Returns handles to every behaviour attached to the bucket.
+    pub fn behaviours(&self) -> Vec<SharedBehaviour> {
+        self.state.borrow().behaviours.clone()
+    }
+    /// Returns the name of every behaviour attached to the bucket, along
+    /// with how much population it has moved since the bucket was created
+    /// or its model was last reset.
+    ///
+    /// This is the flow through each behaviour, where the
+    /// [quantity](Bucket::get) of a bucket is its stock: the total for an
+    /// infection behaviour on a susceptible bucket is the cumulative number
+    /// of cases, however many have since recovered.
+    pub fn flows(&self) -> Vec<(String, f64)> {
+        let state = self.state.borrow();
+        state
+            .behaviours
+            .iter()
+            .zip(&state.flows)
+            .map(|(behaviour, flow)| (behaviour.borrow().name().to_owned(), flow.get()))
+            .collect()
+    }
+    /// Adds `amount` to the bucket, or returns an error and leaves the bucket
+    /// as it was if that would leave it negative or not finite.
+    pub fn checked_add(&mut self, amount: f64) -> Result<(), QuantityError> {
+        let mut state = self.state.borrow_mut();
+        let quantity = state.quantity + amount;
+        QuantityError::check(&state.name, quantity)?;
+        state.quantity = quantity;
+        Ok(())
+    }
+    /// Removes `amount` from the bucket, or returns an error and leaves the
+    /// bucket as it was if that would leave it negative or not finite.
+    pub fn checked_sub(&mut self, amount: f64) -> Result<(), QuantityError> {
+        self.checked_add(-amount)
+    }
+    /// Removes as much of `amount` as the bucket holds, never leaving it
+    /// negative, and returns how much was removed.
+    pub fn saturating_sub(&mut self, amount: f64) -> f64 {
+        let mut state = self.state.borrow_mut();
+        let removed = amount.min(state.quantity.max(0.0)).max(0.0);
+        state.quantity -= removed;
+        removed
+    }
+    /// Attaches a behaviour, which will be run against this bucket every tick.
+    pub fn add(&mut self, behaviour: Box<dyn Behaviour>) {
+        let mut state = self.state.borrow_mut();
+        state.behaviours.push(Rc::new(RefCell::new(behaviour)));
+        state.flows.push(Counter::new());
+    }
+}
+
+/// Unchecked addition, which can leave the bucket with any quantity at all.
+/// See [`Bucket::checked_add`] for a checked alternative.
+impl<T> AddAssign<T> for Bucket
+where
+    T: Into<f64>,
+{
+    fn add_assign(&mut self, rhs: T) {
+        self.state.borrow_mut().quantity += rhs.into();
+    }
+}
+
+/// Unchecked subtraction, which can leave the bucket negative. See
+/// [`Bucket::checked_sub`] and [`Bucket::saturating_sub`] for checked
+/// alternatives.
+impl<T> SubAssign<T> for Bucket
+where
+    T: Into<f64>,
+{
+    fn sub_assign(&mut self, rhs: T) {
+        self.state.borrow_mut().quantity -= rhs.into();
+    }
+}
@@ -0,0 +1,647 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::equation::Equation;
+use crate::{
+    Behaviour, Branch, Bucket, Diffusion, ForceOfInfection, Incubation, Infection,
+    InfectionFatality, Mixing, Model, ModelError, NaturalDeath, Param, Reaction, Reinfection,
+    Seasonal, Waning,
+};
+
+/// A kind of flow between two compartments, used with
+/// [`ModelBuilder::flow`].
+pub trait FlowKind {
+    /// Creates the behaviour moving population from `from` into `to`. The
+    /// behaviour is attached to `from`.
+    ///
+    /// `compartments` holds every compartment of the model being built, for
+    /// flows that depend on compartments other than their endpoints.
+    fn build(
+        self: Box<Self>,
+        from: &Bucket,
+        to: &Bucket,
+        compartments: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError>;
+}
+
+/// Any behaviour can be used as a flow through a closure creating it from
+/// the destination compartment.
+impl<F> FlowKind for F
+where
+    F: FnOnce(Bucket) -> Box<dyn Behaviour>,
+{
+    fn build(
+        self: Box<Self>,
+        _: &Bucket,
+        to: &Bucket,
+        _: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        Ok(self(to.clone()))
+    }
+}
+
+/// Flows of a kind only known at runtime, such as those read from a
+/// [`Config`](crate::Config).
+impl FlowKind for Box<dyn FlowKind> {
+    fn build(
+        self: Box<Self>,
+        from: &Bucket,
+        to: &Bucket,
+        compartments: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        (*self).build(from, to, compartments)
+    }
+}
+
+/// The compartments of a model being built by a [`ModelBuilder`].
+///
+/// A compartment is usually a single bucket, but a
+/// [staged](ModelBuilder::compartment_staged) compartment is a chain of
+/// buckets, one for each stage.
+pub struct Compartments<'a> {
+    model: &'a Model,
+    stages: &'a [(String, usize)],
+}
+
+impl<'a> Compartments<'a> {
+    /// Returns every bucket making up the compartment `name`, in stage
+    /// order.
+    pub fn get(&self, name: &str) -> Result<Vec<Bucket>, BuildError> {
+        match self.stages.iter().find(|(staged, _)| staged == name) {
+            Some((_, stages)) => (1..=*stages)
+                .map(|stage| self.bucket(&stage_name(name, stage)))
+                .collect(),
+            None => Ok(vec![self.bucket(name)?]),
+        }
+    }
+    /// Returns every bucket making up the compartment `bucket` belongs to,
+    /// which is just `bucket` unless the compartment is staged.
+    pub fn containing(&self, bucket: &Bucket) -> Result<Vec<Bucket>, BuildError> {
+        for (name, _) in self.stages {
+            let stages = self.get(name)?;
+            if stages.iter().any(|stage| stage.ptr_eq(bucket)) {
+                return Ok(stages);
+            }
+        }
+        Ok(vec![bucket.clone()])
+    }
+    /// Returns the bucket population enters the compartment `name` through,
+    /// which is its first stage.
+    pub fn first(&self, name: &str) -> Result<Bucket, BuildError> {
+        Ok(self.get(name)?.remove(0))
+    }
+    /// Returns the bucket population leaves the compartment `name` from,
+    /// which is its last stage.
+    pub fn last(&self, name: &str) -> Result<Bucket, BuildError> {
+        Ok(self.get(name)?.pop().expect("compartments have a bucket"))
+    }
+    fn bucket(&self, name: &str) -> Result<Bucket, BuildError> {
+        self.model
+            .bucket(name)
+            .ok_or_else(|| BuildError::UnknownCompartment(name.to_owned()))
+    }
+}
+
+fn stage_name(name: &str, stage: usize) -> String {
+    format!("{}_{}", name, stage)
+}
+
+/// Mass-action transmission. Unless set otherwise with
+/// [`infectious`](Transmission::infectious), the destination compartment is
+/// the infectious one.
+pub struct Transmission {
+    beta: Param,
+    mixing: Mixing,
+    infectious: Vec<(String, f64)>,
+    seasonality: Option<(f64, f64)>,
+    reduction: Option<Param>,
+}
+
+impl Transmission {
+    /// Creates frequency-dependent transmission with rate `beta`.
+    pub fn new(beta: impl Into<Param>) -> Transmission {
+        Transmission {
+            beta: beta.into(),
+            mixing: Mixing::Frequency,
+            infectious: Vec::new(),
+            seasonality: None,
+            reduction: None,
+        }
+    }
+    /// Creates density-dependent transmission with rate `beta`.
+    pub fn density(beta: impl Into<Param>) -> Transmission {
+        Transmission {
+            beta: beta.into(),
+            mixing: Mixing::Density,
+            infectious: Vec::new(),
+            seasonality: None,
+            reduction: None,
+        }
+    }
+    /// Drives transmission by contact with the compartment `name` instead of
+    /// the destination compartment. Call this once for each compartment that
+    /// contributes to the force of infection.
+    pub fn infectious(mut self, name: &'_ str) -> Self {
+        self.infectious.push((name.to_owned(), 1.0));
+        self
+    }
+    /// Drives transmission by contact with the compartment `name`, whose
+    /// members are `weight` times as infectious as those of compartments
+    /// added with [`infectious`](Transmission::infectious).
+    ///
+    /// Transmission with weighted compartments is built as a
+    /// [`ForceOfInfection`].
+    pub fn weighted(mut self, name: &'_ str, weight: f64) -> Self {
+        self.infectious.push((name.to_owned(), weight));
+        self
+    }
+    /// Forces transmission seasonally with the given relative `amplitude`
+    /// and `period` in ticks, as with [`Seasonal`].
+    pub fn seasonal(mut self, amplitude: f64, period: f64) -> Self {
+        self.seasonality = Some((amplitude, period));
+        self
+    }
+    /// Reduces the susceptibility of the source compartment by the fraction
+    /// `reduction`, for reinfection of a compartment such as the recovered.
+    ///
+    /// Transmission with reduced susceptibility is built as a
+    /// [`Reinfection`].
+    pub fn reduced(mut self, reduction: impl Into<Param>) -> Self {
+        self.reduction = Some(reduction.into());
+        self
+    }
+}
+
+impl FlowKind for Transmission {
+    fn build(
+        self: Box<Self>,
+        _: &Bucket,
+        to: &Bucket,
+        compartments: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        let sources = if self.infectious.is_empty() {
+            // A staged destination is infectious in every stage.
+            compartments
+                .containing(to)?
+                .into_iter()
+                .map(|bucket| (bucket, 1.0))
+                .collect()
+        } else {
+            let mut sources = Vec::new();
+            for (name, weight) in &self.infectious {
+                for bucket in compartments.get(name)? {
+                    sources.push((bucket, *weight));
+                }
+            }
+            sources
+        };
+        let infection = if let Some(reduction) = self.reduction {
+            Reinfection::with_mixing(to.clone(), sources, self.beta, reduction, self.mixing)
+        } else if sources.iter().all(|(_, weight)| *weight == 1.0) {
+            let infectious = sources.into_iter().map(|(source, _)| source).collect();
+            Infection::with_mixing(to.clone(), infectious, self.beta, self.mixing)
+        } else {
+            ForceOfInfection::with_mixing(to.clone(), sources, self.beta, self.mixing)
+        };
+        Ok(match self.seasonality {
+            Some((amplitude, period)) => Seasonal::new(infection, amplitude, period),
+            None => infection,
+        })
+    }
+}
+
+/// A constant per-capita rate of movement.
+pub struct Rate {
+    rate: Param,
+}
+
+impl Rate {
+    /// Creates a flow moving `rate` of the source compartment per tick.
+    pub fn new(rate: impl Into<Param>) -> Rate {
+        Rate { rate: rate.into() }
+    }
+}
+
+impl FlowKind for Rate {
+    fn build(
+        self: Box<Self>,
+        _: &Bucket,
+        to: &Bucket,
+        _: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        Ok(Diffusion::new(to.clone(), self.rate))
+    }
+}
+
+/// Progression out of a latent stage, with exponentially distributed latent
+/// periods.
+pub struct Latency {
+    sigma: Param,
+}
+
+impl Latency {
+    /// Creates progression at rate `sigma` per tick.
+    pub fn new(sigma: impl Into<Param>) -> Latency {
+        Latency {
+            sigma: sigma.into(),
+        }
+    }
+    /// Creates progression with a mean latent period of `period` ticks.
+    pub fn with_period(period: f64) -> Latency {
+        Latency::new(1.0 / period)
+    }
+}
+
+impl FlowKind for Latency {
+    fn build(
+        self: Box<Self>,
+        _: &Bucket,
+        to: &Bucket,
+        _: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        Ok(Incubation::new(to.clone(), self.sigma))
+    }
+}
+
+/// Deaths caused by the disease, usually into a
+/// [sink](ModelBuilder::sink).
+pub struct Fatality {
+    rate: Param,
+}
+
+impl Fatality {
+    /// Creates deaths at per-capita rate `rate` per tick.
+    pub fn new(rate: impl Into<Param>) -> Fatality {
+        Fatality { rate: rate.into() }
+    }
+    /// Creates deaths such that a fraction `ratio` of infections end in
+    /// death, for a compartment that also recovers at rate `recovery` per
+    /// tick.
+    pub fn with_ratio(ratio: f64, recovery: f64) -> Fatality {
+        Fatality::new(ratio * recovery / (1.0 - ratio))
+    }
+}
+
+impl FlowKind for Fatality {
+    fn build(
+        self: Box<Self>,
+        _: &Bucket,
+        to: &Bucket,
+        _: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        Ok(InfectionFatality::new(to.clone(), self.rate))
+    }
+}
+
+/// Loss of immunity, with exponentially distributed durations of immunity.
+pub struct Wane {
+    omega: Param,
+}
+
+impl Wane {
+    /// Creates waning at rate `omega` per tick.
+    pub fn new(omega: impl Into<Param>) -> Wane {
+        Wane {
+            omega: omega.into(),
+        }
+    }
+    /// Creates waning where immunity lasts `duration` ticks on average.
+    pub fn with_duration(duration: f64) -> Wane {
+        Wane::new(1.0 / duration)
+    }
+}
+
+impl FlowKind for Wane {
+    fn build(
+        self: Box<Self>,
+        _: &Bucket,
+        to: &Bucket,
+        _: &Compartments,
+    ) -> Result<Box<dyn Behaviour>, BuildError> {
+        Ok(Waning::new(to.clone(), self.omega))
+    }
+}
+
+/// An error in the definition given to a [`ModelBuilder`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// Two compartments were given the same name.
+    DuplicateCompartment(String),
+    /// A flow referred to a compartment that was never defined.
+    UnknownCompartment(String),
+    /// A staged compartment was given no stages or a mean duration that
+    /// is not positive.
+    InvalidStages(String),
+    /// A completion was added to a compartment that is not staged.
+    NotStaged(String),
+    /// A reaction equation was malformed, for the given reason.
+    Equation(String, String),
+    /// The built model failed [validation](Model::validate).
+    Invalid(ModelError),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::DuplicateCompartment(name) => {
+                write!(f, "compartment `{}` is defined more than once", name)
+            }
+            BuildError::UnknownCompartment(name) => {
+                write!(f, "compartment `{}` is not defined", name)
+            }
+            BuildError::InvalidStages(name) => write!(
+                f,
+                "compartment `{}` needs at least one stage and a positive duration",
+                name
+            ),
+            BuildError::NotStaged(name) => write!(f, "compartment `{}` is not staged", name),
+            BuildError::Equation(equation, reason) => {
+                write!(f, "reaction `{}` is malformed: {}", equation, reason)
+            }
+            BuildError::Invalid(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+struct CompartmentDefinition {
+    name: String,
+    initial: f64,
+    sink: bool,
+    stages: Option<(usize, f64)>,
+}
+
+struct BranchDefinition {
+    from: String,
+    rate: Param,
+    targets: Vec<(String, f64)>,
+}
+
+/// Builds a [`Model`] from named compartments and the flows between them.
+#[derive(Default)]
+pub struct ModelBuilder {
+    compartments: Vec<CompartmentDefinition>,
+    flows: Vec<(String, String, Box<dyn FlowKind>)>,
+    completions: Vec<(String, String)>,
+    branches: Vec<BranchDefinition>,
+    behaviours: Vec<(String, Box<dyn Behaviour>)>,
+    reactions: Vec<String>,
+    natural_death: Option<Param>,
+}
+
+impl ModelBuilder {
+    /// Creates a builder with no compartments.
+    pub fn new() -> ModelBuilder {
+        ModelBuilder::default()
+    }
+    /// Adds a compartment with an initial quantity.
+    pub fn compartment(mut self, name: &'_ str, initial: impl Into<f64>) -> Self {
+        self.compartments.push(CompartmentDefinition {
+            name: name.to_owned(),
+            initial: initial.into(),
+            sink: false,
+            stages: None,
+        });
+        self
+    }
+    /// Adds a [sink](Bucket::sink) compartment, such as one counting deaths.
+    pub fn sink(mut self, name: &'_ str, initial: impl Into<f64>) -> Self {
+        self.compartments.push(CompartmentDefinition {
+            name: name.to_owned(),
+            initial: initial.into(),
+            sink: true,
+            stages: None,
+        });
+        self
+    }
+    /// Adds a compartment made up of `stages` buckets in series, so the time
+    /// spent in it is Erlang distributed with mean `mean_duration` ticks
+    /// rather than exponentially distributed.
+    ///
+    /// The stages are named `name_1` to `name_k` and each is left at rate
+    /// `stages / mean_duration`. The initial quantity starts in the first
+    /// stage. Flows and behaviours into the compartment reach its first
+    /// stage, flows out of it leave from its last stage, and it is
+    /// infectious in all of its stages. Use
+    /// [`completion`](ModelBuilder::completion) to say where individuals go
+    /// once they have passed through every stage.
+    pub fn compartment_staged(
+        mut self,
+        name: &'_ str,
+        initial: impl Into<f64>,
+        stages: usize,
+        mean_duration: f64,
+    ) -> Self {
+        self.compartments.push(CompartmentDefinition {
+            name: name.to_owned(),
+            initial: initial.into(),
+            sink: false,
+            stages: Some((stages, mean_duration)),
+        });
+        self
+    }
+    /// Moves individuals completing the last stage of the staged compartment
+    /// `from` into the compartment `to`.
+    pub fn completion(mut self, from: &'_ str, to: &'_ str) -> Self {
+        self.completions.push((from.to_owned(), to.to_owned()));
+        self
+    }
+    /// Adds a flow from the compartment `from` into the compartment `to`.
+    pub fn flow(mut self, from: &'_ str, to: &'_ str, kind: impl FlowKind + 'static) -> Self {
+        self.flows
+            .push((from.to_owned(), to.to_owned(), Box::new(kind)));
+        self
+    }
+    /// Adds a [`Branch`](crate::Branch) out of the compartment `from` at rate
+    /// `rate`, split between the compartments in `targets` with the given
+    /// probabilities.
+    pub fn branch(
+        mut self,
+        from: &'_ str,
+        rate: impl Into<Param>,
+        targets: &[(&'_ str, f64)],
+    ) -> Self {
+        let targets = targets
+            .iter()
+            .map(|(name, p)| ((*name).to_owned(), *p))
+            .collect();
+        self.branches.push(BranchDefinition {
+            from: from.to_owned(),
+            rate: rate.into(),
+            targets,
+        });
+        self
+    }
+    /// Attaches a behaviour to the compartment `name`, for behaviours such
+    /// as [`Birth`](crate::Birth) that are not flows between compartments.
+    pub fn behaviour(mut self, name: &'_ str, behaviour: Box<dyn Behaviour>) -> Self {
+        self.behaviours.push((name.to_owned(), behaviour));
+        self
+    }
+    /// Adds a [`Reaction`](crate::Reaction) written as an equation, such
+    /// as `S + I -> 2I, rate = 0.3/N`.
+    ///
+    /// Either side of the reaction is a `+`-separated list of compartments,
+    /// each with an optional coefficient, and may be empty for population
+    /// entering or leaving the model, as in `-> S, rate = 5`. The rate is
+    /// an expression of numbers, compartment names, which stand for their
+    /// current quantities, `N`, which stands for the total living
+    /// population, parentheses and the operators `+`, `-`, `*`, `/` and
+    /// `^`. Staged compartments are consumed from their last stage and
+    /// produced into their first, and can't be named in the rate.
+    pub fn reaction(mut self, equation: &'_ str) -> Self {
+        self.reactions.push(equation.to_owned());
+        self
+    }
+    /// Adds natural deaths at per-capita rate `mu` per tick to every
+    /// compartment other than sinks.
+    pub fn natural_death(mut self, mu: impl Into<Param>) -> Self {
+        self.natural_death = Some(mu.into());
+        self
+    }
+    /// Validates the definition and builds the model.
+    pub fn build(self) -> Result<Model, BuildError> {
+        let mut model = Model::new();
+        let mut names: Vec<String> = Vec::new();
+        let mut stages: Vec<(String, usize)> = Vec::new();
+        let mut stage_rates: Vec<(String, f64)> = Vec::new();
+        for definition in self.compartments {
+            let name = definition.name;
+            if names.contains(&name) || model.bucket(&name).is_some() {
+                return Err(BuildError::DuplicateCompartment(name));
+            }
+            let buckets: Vec<Bucket> = match definition.stages {
+                Some((count, mean)) => {
+                    if count == 0 || mean.is_nan() || mean <= 0.0 {
+                        return Err(BuildError::InvalidStages(name));
+                    }
+                    let rate = count as f64 / mean;
+                    let buckets: Vec<Bucket> = (1..=count)
+                        .map(|stage| Bucket::new(&stage_name(&name, stage)))
+                        .collect();
+                    for pair in buckets.windows(2) {
+                        let mut stage = pair[0].clone();
+                        stage.add(Diffusion::new(pair[1].clone(), rate));
+                    }
+                    stages.push((name.clone(), count));
+                    stage_rates.push((name.clone(), rate));
+                    buckets
+                }
+                None if definition.sink => vec![Bucket::sink(&name)],
+                None => vec![Bucket::new(&name)],
+            };
+            for bucket in &buckets {
+                if model.bucket(&bucket.name()).is_some() {
+                    return Err(BuildError::DuplicateCompartment(bucket.name()));
+                }
+            }
+            let mut first = buckets[0].clone();
+            first += definition.initial;
+            buckets.into_iter().for_each(|bucket| model.add(bucket));
+            names.push(name);
+        }
+        let compartments = Compartments {
+            model: &model,
+            stages: &stages,
+        };
+        for (from, to, kind) in self.flows {
+            let mut from = compartments.last(&from)?;
+            let to = compartments.first(&to)?;
+            let behaviour = kind.build(&from, &to, &compartments)?;
+            from.add(behaviour);
+        }
+        for (from, to) in self.completions {
+            let rate = stage_rates
+                .iter()
+                .find(|(staged, _)| *staged == from)
+                .map(|(_, rate)| *rate)
+                .ok_or_else(|| BuildError::NotStaged(from.clone()))?;
+            let mut from = compartments.last(&from)?;
+            from.add(Diffusion::new(compartments.first(&to)?, rate));
+        }
+        for branch in self.branches {
+            let targets = branch
+                .targets
+                .iter()
+                .map(|(name, p)| Ok((compartments.first(name)?, *p)))
+                .collect::<Result<Vec<_>, _>>()?;
+            compartments
+                .last(&branch.from)?
+                .add(Branch::new(branch.rate, targets));
+        }
+        for (name, behaviour) in self.behaviours {
+            compartments.first(&name)?.add(behaviour);
+        }
+        for text in self.reactions {
+            let equation = Equation::parse(&text)
+                .map_err(|reason| BuildError::Equation(text.clone(), reason))?;
+            if let Some(name) = equation
+                .rate
+                .names()
+                .into_iter()
+                .find(|&name| name != "N" && model.bucket(name).is_none())
+            {
+                return Err(BuildError::UnknownCompartment(name.to_owned()));
+            }
+            let reactants = equation
+                .reactants
+                .iter()
+                .map(|(name, count)| Ok((compartments.last(name)?, *count)))
+                .collect::<Result<Vec<_>, BuildError>>()?;
+            let products = equation
+                .products
+                .iter()
+                .map(|(name, count)| Ok((compartments.first(name)?, *count)))
+                .collect::<Result<Vec<_>, BuildError>>()?;
+            let mut bucket = reactants
+                .iter()
+                .chain(&products)
+                .map(|(bucket, _)| bucket.clone())
+                .next()
+                .expect("reactions have a reactant or product");
+            let rate = equation.rate;
+            bucket.add(Reaction::new(reactants, products, move |state| {
+                rate.evaluate(state)
+            }));
+        }
+        if let Some(mu) = self.natural_death {
+            model
+                .buckets()
+                .filter(|bucket| !bucket.is_sink())
+                .cloned()
+                .for_each(|mut bucket| bucket.add(NaturalDeath::new(mu.clone())));
+        }
+        // The solver and step are chosen after building, so rates per tick
+        // are left for Model::validate to bound.
+        model.check(false).map_err(BuildError::Invalid)?;
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Model, Rate, Transmission};
+
+    #[test]
+    fn staged_compartments_transmit_from_every_stage() {
+        let mut model = Model::builder()
+            .compartment("S", 990)
+            .compartment_staged("I", 0, 3, 6.0)
+            .compartment("R", 0)
+            .flow("S", "I", Transmission::new(0.5))
+            .flow("I", "R", Rate::new(0.0))
+            .build()
+            .unwrap();
+        model.bucket("I_3").unwrap().set(10.0);
+        model.step(1);
+        // One step of 0.5 * 990 * 10 / 1000 infections, all caused by the
+        // last stage.
+        let susceptible = model.bucket("S").unwrap().get();
+        assert!(
+            (susceptible - (990.0 - 4.95)).abs() < 1e-9,
+            "{}",
+            susceptible
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+
+use crate::{ParameterError, Rng};
+
+/// An error saving or loading a checkpoint.
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// The checkpoint couldn't be read or written.
+    Io(io::Error),
+    /// A record of the checkpoint couldn't be understood.
+    Malformed(String),
+    /// The checkpoint has no record of the given kind, which every
+    /// checkpoint needs.
+    Missing(String),
+    /// The checkpoint holds a bucket the model doesn't have.
+    UnknownBucket(String),
+    /// The checkpoint holds a parameter the model doesn't have.
+    Parameter(ParameterError),
+    /// The checkpoint holds state for a behaviour, given by its
+    /// `bucket.behaviour` path, that the model doesn't have or that the
+    /// behaviour can't understand.
+    State(String),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "{}", error),
+            CheckpointError::Malformed(record) => {
+                write!(f, "malformed checkpoint record `{}`", record)
+            }
+            CheckpointError::Missing(kind) => write!(f, "checkpoint has no `{}` record", kind),
+            CheckpointError::UnknownBucket(name) => {
+                write!(f, "checkpoint holds unknown bucket `{}`", name)
+            }
+            CheckpointError::Parameter(error) => write!(f, "{}", error),
+            CheckpointError::State(path) => {
+                write!(
+                    f,
+                    "checkpoint holds state `{}` the model can't restore",
+                    path
+                )
+            }
+        }
+    }
+}
+
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckpointError::Io(error) => Some(error),
+            CheckpointError::Parameter(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(error: io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+impl From<csv::Error> for CheckpointError {
+    fn from(error: csv::Error) -> Self {
+        CheckpointError::Io(error.into())
+    }
+}
+
+impl From<ParameterError> for CheckpointError {
+    fn from(error: ParameterError) -> Self {
+        CheckpointError::Parameter(error)
+    }
+}
+
+/// Everything about a running model that a checkpoint holds.
+///
+/// Checkpoints are written as CSV with a `kind,name,value` record per
+/// item, as in
+///
+/// ```text
+/// kind,name,value
+/// time,,120
+/// seed,,42
+/// rng,,1234 5678 9012 3456
+/// step,,1
+/// bucket,S,812.4
+/// incidence,I,3.1 2.9 2.6
+/// cumulative,I,40.2
+/// flows,I,31.5
+/// parameter,S.infection.beta,0.3
+/// state,V.two_dose_vaccination,96 10 97 10
+/// event,,130 0 2 5
+/// ```
+///
+/// `incidence` holds the incidence of every step kept, most recent first,
+/// and `flows` the flow through each behaviour of the bucket. `state`
+/// holds what a behaviour has accumulated, such as queued transfers, as
+/// given by [`Behaviour::state`](crate::Behaviour::state), and `event` a
+/// scheduled transfer as its time, its source, its destination and its
+/// amount, with buckets written as their position in the model.
+///
+/// Numbers are written in full, so a loaded model carries on exactly as
+/// the saved one would have.
+pub(crate) struct Checkpoint {
+    pub(crate) time: f64,
+    pub(crate) seed: u64,
+    pub(crate) rng: Rng,
+    pub(crate) step_size: f64,
+    pub(crate) buckets: Vec<(String, f64)>,
+    pub(crate) incidence: Vec<(String, Vec<f64>)>,
+    pub(crate) cumulative: Vec<(String, f64)>,
+    pub(crate) flows: Vec<(String, Vec<f64>)>,
+    pub(crate) parameters: Vec<(String, f64)>,
+    pub(crate) states: Vec<(String, Vec<f64>)>,
+    pub(crate) events: Vec<Vec<f64>>,
+}
+
+/// Writes `values` separated by spaces.
+fn join(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(f64::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Checkpoint {
+    pub(crate) fn write<W: Write>(&self, writer: W) -> Result<(), CheckpointError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["kind", "name", "value"])?;
+        let state = self.rng.state();
+        let rng = state
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        writer.write_record(["time", "", &self.time.to_string()])?;
+        writer.write_record(["seed", "", &self.seed.to_string()])?;
+        writer.write_record(["rng", "", &rng])?;
+        writer.write_record(["step", "", &self.step_size.to_string()])?;
+        for (name, quantity) in &self.buckets {
+            writer.write_record(["bucket", name, &quantity.to_string()])?;
+        }
+        for (name, incidence) in &self.incidence {
+            writer.write_record(["incidence", name, &join(incidence)])?;
+        }
+        for (name, cumulative) in &self.cumulative {
+            writer.write_record(["cumulative", name, &cumulative.to_string()])?;
+        }
+        for (name, flows) in &self.flows {
+            writer.write_record(["flows", name, &join(flows)])?;
+        }
+        for (path, value) in &self.parameters {
+            writer.write_record(["parameter", path, &value.to_string()])?;
+        }
+        for (path, state) in &self.states {
+            writer.write_record(["state", path, &join(state)])?;
+        }
+        for event in &self.events {
+            writer.write_record(["event", "", &join(event)])?;
+        }
+        Ok(writer.flush()?)
+    }
+    pub(crate) fn read<R: Read>(reader: R) -> Result<Checkpoint, CheckpointError> {
+        let mut time = None;
+        let mut seed = None;
+        let mut rng = None;
+        let mut step_size = None;
+        let mut buckets = Vec::new();
+        let mut incidence = Vec::new();
+        let mut cumulative = Vec::new();
+        let mut flows = Vec::new();
+        let mut parameters = Vec::new();
+        let mut states = Vec::new();
+        let mut events = Vec::new();
+        for record in csv::Reader::from_reader(reader).records() {
+            let record = record?;
+            let malformed =
+                || CheckpointError::Malformed(record.iter().collect::<Vec<_>>().join(","));
+            let (kind, name, value) = match (record.get(0), record.get(1), record.get(2)) {
+                (Some(kind), Some(name), Some(value)) => (kind, name, value),
+                _ => return Err(malformed()),
+            };
+            let number = || value.parse::<f64>().map_err(|_| malformed());
+            let numbers = || {
+                value
+                    .split_whitespace()
+                    .map(str::parse::<f64>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| malformed())
+            };
+            match kind {
+                "time" => time = Some(number()?),
+                "seed" => seed = Some(value.parse::<u64>().map_err(|_| malformed())?),
+                "rng" => {
+                    let words = value
+                        .split_whitespace()
+                        .map(str::parse::<u64>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| malformed())?;
+                    let state = <[u64; 4]>::try_from(words).map_err(|_| malformed())?;
+                    rng = Some(Rng::from_state(state));
+                }
+                "step" => step_size = Some(number()?),
+                "bucket" => buckets.push((name.to_owned(), number()?)),
+                "incidence" => incidence.push((name.to_owned(), numbers()?)),
+                "cumulative" => cumulative.push((name.to_owned(), number()?)),
+                "flows" => flows.push((name.to_owned(), numbers()?)),
+                "parameter" => parameters.push((name.to_owned(), number()?)),
+                "state" => states.push((name.to_owned(), numbers()?)),
+                "event" => events.push(numbers()?),
+                _ => return Err(malformed()),
+            }
+        }
+        let missing = |kind: &str| CheckpointError::Missing(kind.to_owned());
+        Ok(Checkpoint {
+            time: time.ok_or_else(|| missing("time"))?,
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            rng: rng.ok_or_else(|| missing("rng"))?,
+            step_size: step_size.ok_or_else(|| missing("step"))?,
+            buckets,
+            incidence,
+            cumulative,
+            flows,
+            parameters,
+            states,
+            events,
+        })
+    }
+}
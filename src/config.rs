@@ -0,0 +1,637 @@
+use std::env;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use toml::{Table, Value};
+
+use crate::{
+    BuildError, Fatality, FlowKind, Interpolation, Latency, Model, ModelBuilder, Param, Rate,
+    Schedule, Solver, TimeUnit, Transmission, Wane,
+};
+
+/// An error reading or building a model definition file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The file isn't valid TOML.
+    Parse(toml::de::Error),
+    /// A key every definition of its kind needs is missing.
+    Missing(String),
+    /// A key holds a value of the wrong type, or one out of range.
+    Invalid(String),
+    /// A key names a flow kind or solver that doesn't exist.
+    Unknown(String, String),
+    /// A placeholder names a variable that is set nowhere, or is never
+    /// closed.
+    Placeholder(String),
+    /// The compartments and flows don't make a valid model.
+    Build(BuildError),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "{}", error),
+            ConfigError::Parse(error) => write!(f, "{}", error),
+            ConfigError::Missing(key) => write!(f, "missing key `{}`", key),
+            ConfigError::Invalid(key) => write!(f, "key `{}` has an invalid value", key),
+            ConfigError::Unknown(key, value) => {
+                write!(f, "key `{}` has unknown value `{}`", key, value)
+            }
+            ConfigError::Placeholder(name) => {
+                write!(f, "placeholder `{}` has no value", name)
+            }
+            ConfigError::Build(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(error) => Some(error),
+            ConfigError::Parse(error) => Some(error),
+            ConfigError::Build(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        ConfigError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(error: toml::de::Error) -> Self {
+        ConfigError::Parse(error)
+    }
+}
+
+impl From<BuildError> for ConfigError {
+    fn from(error: BuildError) -> Self {
+        ConfigError::Build(error)
+    }
+}
+
+/// A model described by a definition file, as read by
+/// [`Model::from_file`].
+///
+/// Definitions are written in TOML, with a `[[compartment]]` table for
+/// every compartment and a `[[flow]]` table for every flow between them, as
+/// in
+///
+/// ```toml
+/// dt = 0.1
+/// seed = 42
+/// solver = "rk4"
+///
+/// [[compartment]]
+/// name = "S"
+/// initial = 999
+///
+/// [[compartment]]
+/// name = "I"
+/// initial = 1
+///
+/// [[compartment]]
+/// name = "R"
+///
+/// [[flow]]
+/// from = "S"
+/// to = "I"
+/// kind = "transmission"
+/// beta = 0.3
+///
+/// [[flow]]
+/// from = "I"
+/// to = "R"
+/// kind = "rate"
+/// rate = 0.1
+/// ```
+///
+/// The top level can set `dt`, `seed`, `natural_death`, and `solver`,
+/// which is one of `euler`, `rk4`, `dormand-prince` (with `atol` and
+/// `rtol`), `implicit-euler`, `gillespie` or `tau-leaping`.
+///
+/// Compartments have a `name` and an `initial` quantity, which defaults to
+/// zero. A compartment with `sink = true` is a [sink](ModelBuilder::sink),
+/// and one with `stages` and `duration` is
+/// [staged](ModelBuilder::compartment_staged), with a `completion` naming
+/// where individuals go once they have passed through every stage.
+///
+/// Flows have a `from` and `to` compartment and a `kind`, which with its
+/// keys is one of
+///
+/// - `transmission`, with `beta`, an optional `mixing` of `frequency` or
+///   `density`, and an optional list of `infectious` compartments,
+/// - `rate`, with `rate`,
+/// - `latency`, with `sigma` or a mean `period`,
+/// - `fatality`, with `rate`,
+/// - `wane`, with `omega` or a mean `duration`.
+///
+/// Rates can be numbers or [schedules](Schedule), written as a list of
+/// `[time, value]` points holding until the next, or as a table such as
+/// `{ points = [[0, 0.3], [30, 0.1]], interpolation = "linear" }`.
+///
+/// The top level can also declare the `time_unit` a tick stands for, as
+/// in `time_unit = "days"`. Rates, durations, `dt` and the times of
+/// schedules can then be written with units of their own, which are
+/// converted into ticks, as in `beta = "2.1 per week"`, `rate = "0.1/day"`
+/// or `dt = "6 hours"`. See [`TimeUnit::parse`] for the units understood.
+///
+/// Flows can also be written as [reactions](ModelBuilder::reaction), in a
+/// top-level list such as
+///
+/// ```toml
+/// reactions = ["S + I -> 2I, rate = 0.3/N", "I -> R, rate = 0.1"]
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    table: Table,
+}
+
+impl Config {
+    /// Reads the definition in the file at `path`, substituting
+    /// placeholders from the environment as [`parse`](Config::parse) does.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+        Config::from_file_with(path, &[])
+    }
+    /// Reads the definition in the file at `path`, substituting
+    /// placeholders as [`parse_with`](Config::parse_with) does.
+    pub fn from_file_with(
+        path: impl AsRef<Path>,
+        variables: &[(&str, &str)],
+    ) -> Result<Config, ConfigError> {
+        Config::parse_with(&fs::read_to_string(path)?, variables)
+    }
+    /// Reads a definition from TOML text, replacing every `${NAME}`
+    /// placeholder with the value of the environment variable `NAME`.
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        Config::parse_with(text, &[])
+    }
+    /// Reads a definition from TOML text, replacing every `${NAME}`
+    /// placeholder with the value given for `NAME` in `variables`, or if
+    /// there is none, the value of the environment variable `NAME`.
+    ///
+    /// Placeholders are replaced before the text is parsed, so one template
+    /// can be launched with many parameterizations, as in `beta =
+    /// ${BETA}`. A placeholder written `${NAME:-default}` falls back to
+    /// `default` if `NAME` is set nowhere; any other unset placeholder is
+    /// an error. Placeholders in comments are left as they are.
+    pub fn parse_with(text: &str, variables: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        Ok(Config {
+            table: substitute(text, variables)?.parse::<Table>()?,
+        })
+    }
+    /// Layers the scenario `overlay` over the definition, so that it
+    /// overrides whatever the overlay sets and keeps the rest.
+    ///
+    /// A scenario is written like a definition, but needs only the keys it
+    /// changes. Its compartments replace the keys of the compartment with
+    /// the same `name`, and its flows those of the flow with the same
+    /// `from` and `to`, as in
+    ///
+    /// ```toml
+    /// # lockdown.toml
+    /// [[compartment]]
+    /// name = "I"
+    /// initial = 10
+    ///
+    /// [[flow]]
+    /// from = "S"
+    /// to = "I"
+    /// beta = [[0, 0.3], [30, 0.1]]
+    /// ```
+    ///
+    /// Compartments and flows that match none of the definition's are added
+    /// to it. Any other key, including a list of reactions, replaces the
+    /// definition's outright.
+    pub fn overlay(&mut self, overlay: &Config) {
+        merge(&mut self.table, &overlay.table);
+    }
+    /// Returns the definition as TOML, as it would be written to a file.
+    pub fn to_toml(&self) -> String {
+        self.table.to_string()
+    }
+    /// Builds the model the definition describes. The model keeps a copy of
+    /// the definition for its [`Metadata`](crate::Metadata).
+    pub fn build(&self) -> Result<Model, ConfigError> {
+        let unit = match self.table.get("time_unit") {
+            None => None,
+            Some(Value::String(name)) => Some(
+                TimeUnit::parse(name)
+                    .ok_or_else(|| ConfigError::Unknown("time_unit".to_owned(), name.clone()))?,
+            ),
+            Some(_) => return Err(ConfigError::Invalid("time_unit".to_owned())),
+        };
+        let top = Entry::new(&self.table, String::new(), unit);
+        let mut builder = ModelBuilder::new();
+        for compartment in top.tables("compartment")? {
+            let name = compartment.string("name")?;
+            let initial = compartment.optional_number("initial")?.unwrap_or(0.0);
+            builder = if compartment.optional_bool("sink")?.unwrap_or(false) {
+                builder.sink(name, initial)
+            } else if let Some(stages) = compartment.optional_number("stages")? {
+                if stages.fract() != 0.0 || stages < 0.0 {
+                    return Err(compartment.invalid("stages"));
+                }
+                let duration = compartment.duration("duration")?;
+                let builder = builder.compartment_staged(name, initial, stages as usize, duration);
+                match compartment.optional_string("completion")? {
+                    Some(to) => builder.completion(name, to),
+                    None => builder,
+                }
+            } else {
+                builder.compartment(name, initial)
+            };
+        }
+        for flow in top.tables("flow")? {
+            let (from, to) = (flow.string("from")?, flow.string("to")?);
+            builder = builder.flow(from, to, flow.flow_kind()?);
+        }
+        for reaction in top.strings("reactions")? {
+            builder = builder.reaction(reaction);
+        }
+        if top.table.contains_key("natural_death") {
+            builder = builder.natural_death(top.param("natural_death")?);
+        }
+        let mut model = builder.build()?;
+        if let Some(dt) = top.optional_duration("dt")? {
+            if dt.is_nan() || dt <= 0.0 {
+                return Err(top.invalid("dt"));
+            }
+            model.set_dt(dt);
+        }
+        if let Some(seed) = top.optional_integer("seed")? {
+            model.set_seed(seed as u64);
+        }
+        if let Some(solver) = top.solver()? {
+            model.set_solver(solver);
+        }
+        if let Some(unit) = unit {
+            model.set_time_unit(unit);
+        }
+        model.set_definition(self.to_toml());
+        Ok(model)
+    }
+}
+
+/// Replaces the `${NAME}` placeholders in `text`, leaving comments as they
+/// are.
+fn substitute(text: &str, variables: &[(&str, &str)]) -> Result<String, ConfigError> {
+    let mut substituted = String::with_capacity(text.len());
+    // The delimiter of the string being read, if any, so that a `#` in a
+    // string isn't taken for a comment.
+    let mut quote: Option<&str> = None;
+    let mut rest = text;
+    'text: while let Some(c) = rest.chars().next() {
+        if quote.is_none() && c == '#' {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            substituted.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if rest.starts_with("${") {
+            let end = rest
+                .find('}')
+                .ok_or_else(|| ConfigError::Placeholder(rest.to_owned()))?;
+            let placeholder = &rest[2..end];
+            let (name, default) = match placeholder.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (placeholder, None),
+            };
+            let value = variables
+                .iter()
+                .rev()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| (*value).to_owned())
+                .or_else(|| env::var(name).ok())
+                .or_else(|| default.map(str::to_owned))
+                .ok_or_else(|| ConfigError::Placeholder(name.to_owned()))?;
+            substituted.push_str(&value);
+            rest = &rest[end + 1..];
+            continue;
+        }
+        let mut length = c.len_utf8();
+        match quote {
+            None => {
+                for delimiter in ["\"\"\"", "'''", "\"", "'"] {
+                    if rest.starts_with(delimiter) {
+                        quote = Some(delimiter);
+                        substituted.push_str(delimiter);
+                        rest = &rest[delimiter.len()..];
+                        continue 'text;
+                    }
+                }
+            }
+            Some(delimiter) if rest.starts_with(delimiter) => {
+                quote = None;
+                length = delimiter.len();
+            }
+            // Basic strings escape their delimiter with a backslash.
+            Some(delimiter) if delimiter.starts_with('"') && c == '\\' => {
+                length += rest[1..].chars().next().map_or(0, char::len_utf8);
+            }
+            Some(_) => {}
+        }
+        substituted.push_str(&rest[..length]);
+        rest = &rest[length..];
+    }
+    Ok(substituted)
+}
+
+/// Merges `overlay` into `base`, matching up the compartments and flows
+/// of the two.
+fn merge(base: &mut Table, overlay: &Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (Some(Value::Array(base)), Value::Array(overlay))
+                if key == "compartment" || key == "flow" =>
+            {
+                let identity: &[&str] = if key == "compartment" {
+                    &["name"]
+                } else {
+                    &["from", "to"]
+                };
+                for item in overlay {
+                    let matching = base.iter_mut().find(|existing| {
+                        identity.iter().all(|key| {
+                            existing.get(key).is_some() && existing.get(key) == item.get(key)
+                        })
+                    });
+                    match (matching, item) {
+                        (Some(Value::Table(existing)), Value::Table(item)) => merge(existing, item),
+                        _ => base.push(item.clone()),
+                    }
+                }
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// A table of a definition, along with its path from the top for errors.
+struct Entry<'a> {
+    table: &'a Table,
+    path: String,
+    unit: Option<TimeUnit>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(table: &'a Table, path: String, unit: Option<TimeUnit>) -> Entry<'a> {
+        Entry { table, path, unit }
+    }
+    fn key(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+    fn invalid(&self, key: &str) -> ConfigError {
+        ConfigError::Invalid(self.key(key))
+    }
+    fn get(&self, key: &str) -> Result<&'a Value, ConfigError> {
+        self.table
+            .get(key)
+            .ok_or_else(|| ConfigError::Missing(self.key(key)))
+    }
+    fn tables(&self, key: &str) -> Result<Vec<Entry<'a>>, ConfigError> {
+        match self.table.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(tables)) => tables
+                .iter()
+                .enumerate()
+                .map(|(index, table)| match table {
+                    Value::Table(table) => Ok(Entry::new(
+                        table,
+                        format!("{}[{}]", self.key(key), index),
+                        self.unit,
+                    )),
+                    _ => Err(self.invalid(key)),
+                })
+                .collect(),
+            Some(_) => Err(self.invalid(key)),
+        }
+    }
+    fn string(&self, key: &str) -> Result<&'a str, ConfigError> {
+        self.get(key)?.as_str().ok_or_else(|| self.invalid(key))
+    }
+    fn optional_string(&self, key: &str) -> Result<Option<&'a str>, ConfigError> {
+        match self.table.get(key) {
+            Some(_) => self.string(key).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn strings(&self, key: &str) -> Result<Vec<&'a str>, ConfigError> {
+        match self.table.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| value.as_str().ok_or_else(|| self.invalid(key)))
+                .collect(),
+            Some(_) => Err(self.invalid(key)),
+        }
+    }
+    fn optional_bool(&self, key: &str) -> Result<Option<bool>, ConfigError> {
+        match self.table.get(key) {
+            Some(value) => value.as_bool().map(Some).ok_or_else(|| self.invalid(key)),
+            None => Ok(None),
+        }
+    }
+    fn number(&self, key: &str) -> Result<f64, ConfigError> {
+        number(self.get(key)?).ok_or_else(|| self.invalid(key))
+    }
+    fn optional_number(&self, key: &str) -> Result<Option<f64>, ConfigError> {
+        match self.table.get(key) {
+            Some(_) => self.number(key).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn duration(&self, key: &str) -> Result<f64, ConfigError> {
+        duration(self.get(key)?, self.unit).ok_or_else(|| self.invalid(key))
+    }
+    fn optional_duration(&self, key: &str) -> Result<Option<f64>, ConfigError> {
+        match self.table.get(key) {
+            Some(_) => self.duration(key).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn optional_integer(&self, key: &str) -> Result<Option<i64>, ConfigError> {
+        match self.table.get(key) {
+            Some(Value::Integer(value)) if *value >= 0 => Ok(Some(*value)),
+            Some(_) => Err(self.invalid(key)),
+            None => Ok(None),
+        }
+    }
+    /// Reads a rate, which is either a number or a schedule.
+    fn param(&self, key: &str) -> Result<Param, ConfigError> {
+        let invalid = || self.invalid(key);
+        let (points, interpolation) = match self.get(key)? {
+            Value::Array(points) => (points, Interpolation::Step),
+            Value::Table(table) => {
+                let interpolation = match table.get("interpolation").map(Value::as_str) {
+                    None | Some(Some("step")) => Interpolation::Step,
+                    Some(Some("linear")) => Interpolation::Linear,
+                    _ => return Err(invalid()),
+                };
+                match table.get("points") {
+                    Some(Value::Array(points)) => (points, interpolation),
+                    _ => return Err(invalid()),
+                }
+            }
+            value => return rate(value, self.unit).map(Param::from).ok_or_else(invalid),
+        };
+        let points = points
+            .iter()
+            .map(|point| match point.as_array().map(Vec::as_slice) {
+                Some([time, value]) => Some((duration(time, self.unit)?, rate(value, self.unit)?)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|points| !points.is_empty())
+            .ok_or_else(invalid)?;
+        Ok(Schedule::from_points(points, interpolation).into())
+    }
+    fn flow_kind(&self) -> Result<Box<dyn FlowKind>, ConfigError> {
+        let kind = self.string("kind")?;
+        Ok(match kind {
+            "transmission" => {
+                let beta = self.param("beta")?;
+                let mut transmission = match self.optional_string("mixing")? {
+                    None | Some("frequency") => Transmission::new(beta),
+                    Some("density") => Transmission::density(beta),
+                    Some(mixing) => {
+                        return Err(ConfigError::Unknown(self.key("mixing"), mixing.to_owned()))
+                    }
+                };
+                for name in self.strings("infectious")? {
+                    transmission = transmission.infectious(name);
+                }
+                Box::new(transmission)
+            }
+            "rate" => Box::new(Rate::new(self.param("rate")?)),
+            "latency" => match self.optional_duration("period")? {
+                Some(period) => Box::new(Latency::with_period(period)),
+                None => Box::new(Latency::new(self.param("sigma")?)),
+            },
+            "fatality" => Box::new(Fatality::new(self.param("rate")?)),
+            "wane" => match self.optional_duration("duration")? {
+                Some(duration) => Box::new(Wane::with_duration(duration)),
+                None => Box::new(Wane::new(self.param("omega")?)),
+            },
+            _ => return Err(ConfigError::Unknown(self.key("kind"), kind.to_owned())),
+        })
+    }
+    fn solver(&self) -> Result<Option<Solver>, ConfigError> {
+        let solver = match self.optional_string("solver")? {
+            Some(solver) => solver,
+            None => return Ok(None),
+        };
+        Ok(Some(match solver {
+            "euler" => Solver::Euler,
+            "rk4" => Solver::Rk4,
+            "dormand-prince" => Solver::DormandPrince {
+                atol: self.optional_number("atol")?.unwrap_or(1e-6),
+                rtol: self.optional_number("rtol")?.unwrap_or(1e-6),
+            },
+            "implicit-euler" => Solver::ImplicitEuler,
+            "gillespie" => Solver::Gillespie,
+            "tau-leaping" => Solver::TauLeaping,
+            _ => return Err(ConfigError::Unknown(self.key("solver"), solver.to_owned())),
+        }))
+    }
+}
+
+/// Reads a duration, written either as a number of ticks or as a string
+/// with a unit, such as `"5 days"`.
+fn duration(value: &Value, unit: Option<TimeUnit>) -> Option<f64> {
+    match value {
+        Value::String(text) => TimeUnit::parse_duration(text, unit),
+        value => number(value),
+    }
+}
+
+/// Reads a rate, written either as a number per tick or as a string with a
+/// unit, such as `"0.7 per week"`.
+fn rate(value: &Value, unit: Option<TimeUnit>) -> Option<f64> {
+    match value {
+        Value::String(text) => TimeUnit::parse_rate(text, unit),
+        value => number(value),
+    }
+}
+
+/// Reads a number written as either an integer or a float.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{substitute, Config, ConfigError};
+
+    #[test]
+    fn placeholders_take_variables_then_defaults() {
+        let text = "beta = ${EPIDEMIC_TEST_BETA:-0.3}\ngamma = ${EPIDEMIC_TEST_GAMMA:-0.1}";
+        let substituted = substitute(text, &[("EPIDEMIC_TEST_BETA", "0.5")]).unwrap();
+        assert_eq!(substituted, "beta = 0.5\ngamma = 0.1");
+        assert!(matches!(
+            substitute("beta = ${EPIDEMIC_TEST_BETA}", &[]),
+            Err(ConfigError::Placeholder(name)) if name == "EPIDEMIC_TEST_BETA"
+        ));
+    }
+
+    #[test]
+    fn placeholders_in_comments_are_left_alone() {
+        let text = "# set ${EPIDEMIC_TEST_BETA}\nname = \"#${EPIDEMIC_TEST_NAME:-S}\" # ${X}\n";
+        let substituted = substitute(text, &[]).unwrap();
+        assert_eq!(
+            substituted,
+            "# set ${EPIDEMIC_TEST_BETA}\nname = \"#S\" # ${X}\n"
+        );
+    }
+
+    #[test]
+    fn definitions_build_with_defaults() {
+        let text = r#"
+            # beta can be set with ${EPIDEMIC_TEST_BETA}
+            [[compartment]]
+            name = "S"
+            initial = ${EPIDEMIC_TEST_SUSCEPTIBLE:-990}
+
+            [[compartment]]
+            name = "I"
+            initial = 10
+
+            [[flow]]
+            from = "S"
+            to = "I"
+            kind = "transmission"
+            beta = ${EPIDEMIC_TEST_BETA:-0.3}
+        "#;
+        let model = Config::parse_with(text, &[("EPIDEMIC_TEST_BETA", "0.4")])
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(model.bucket("S").unwrap().get(), 990.0);
+        let beta = model
+            .parameters()
+            .into_iter()
+            .find(|parameter| parameter.path() == "S.infection.beta")
+            .map(|parameter| parameter.value);
+        assert_eq!(beta, Some(0.4));
+    }
+}
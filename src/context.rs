@@ -0,0 +1,123 @@
+use std::cell::{RefCell, RefMut};
+
+use crate::event::EventQueue;
+use crate::{Bucket, Rng, State, Transfer};
+
+/// The parts of a model that behaviours share while they are updated.
+#[derive(Clone, Copy)]
+pub(crate) struct Shared<'a> {
+    pub(crate) rng: &'a RefCell<Rng>,
+    pub(crate) events: Option<&'a RefCell<EventQueue>>,
+    pub(crate) trace: bool,
+}
+
+impl<'a> Shared<'a> {
+    /// Returns the same shared state for a trial evaluation, such as an
+    /// intermediate stage of a Runge-Kutta step, in which behaviours can't
+    /// schedule events and transfers aren't traced, so that each happens
+    /// only once per step.
+    pub(crate) fn trial(self) -> Shared<'a> {
+        Shared {
+            events: None,
+            trace: false,
+            ..self
+        }
+    }
+}
+
+/// What a behaviour can see of the model while it is being updated.
+pub struct Context<'a> {
+    time: f64,
+    dt: f64,
+    buckets: &'a [Bucket],
+    shared: Shared<'a>,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(
+        time: f64,
+        dt: f64,
+        buckets: &'a [Bucket],
+        shared: Shared<'a>,
+    ) -> Context<'a> {
+        Context {
+            time,
+            dt,
+            buckets,
+            shared,
+        }
+    }
+    /// Returns the time at the start of the step being taken.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    /// Returns the length of the step being taken, in ticks.
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+    /// Returns every bucket in the model.
+    pub fn buckets(&self) -> &'a [Bucket] {
+        self.buckets
+    }
+    /// Returns the bucket called `name`, if there is one.
+    pub fn bucket(&self, name: &str) -> Option<Bucket> {
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.name() == name)
+            .cloned()
+    }
+    /// Returns the model's random number generator, which every stochastic
+    /// behaviour should draw from so that a run is reproducible from the
+    /// model's seed.
+    ///
+    /// The generator is borrowed until the returned guard is dropped, so it
+    /// shouldn't be held across calls into other behaviours.
+    pub fn rng(&self) -> RefMut<'a, Rng> {
+        self.shared.rng.borrow_mut()
+    }
+    /// Schedules `transfer` to be applied once, `delay` ticks after the
+    /// start of the step being taken, as in moving a cohort on after a
+    /// fixed 14 days.
+    ///
+    /// The transfer is applied at the start of the first step that begins
+    /// at or after that time, limited by what its source holds then.
+    /// Solvers that evaluate behaviours more than once per step only keep
+    /// what is scheduled from the state at the start of the step.
+    pub fn schedule(&self, delay: f64, transfer: Transfer) {
+        if let Some(events) = self.shared.events {
+            events.borrow_mut().push(self.time + delay, transfer);
+        }
+    }
+    /// Prints the transfers a behaviour called `behaviour` computed to
+    /// stderr, one per line, if the model is [tracing](crate::Model::set_trace).
+    pub(crate) fn trace(&self, behaviour: &str, transfers: &[Transfer]) {
+        if !self.shared.trace {
+            return;
+        }
+        let name = |bucket: &Option<Bucket>| bucket.as_ref().map_or("-".to_owned(), Bucket::name);
+        for transfer in transfers {
+            eprintln!(
+                "t={} dt={} behaviour={} source={} target={} amount={}",
+                self.time,
+                self.dt,
+                behaviour,
+                name(&transfer.from),
+                name(&transfer.to),
+                transfer.amount
+            );
+        }
+    }
+    /// Returns a snapshot of every bucket in the model.
+    pub fn state(&self) -> State {
+        State::new(self.time, self.buckets)
+    }
+    /// Returns the total living population, across every bucket in the
+    /// model that is not a [sink](Bucket::sink).
+    pub fn total(&self) -> f64 {
+        self.buckets
+            .iter()
+            .filter(|bucket| !bucket.is_sink())
+            .map(Bucket::get)
+            .sum()
+    }
+}
@@ -0,0 +1,37 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// A running total of the population moved by a behaviour, such as
+/// cumulative infections or vaccinations.
+///
+/// Counters are not compartments, so they keep counting after individuals
+/// leave the compartment they were moved into, which makes them suited to
+/// attack rates. Like buckets, counters are cheap handles to shared state:
+/// wrap a behaviour in [`Counted`](crate::Counted) with a clone of a counter
+/// and read the total from the original.
+#[derive(Clone, Debug, Default)]
+pub struct Counter {
+    total: Rc<Cell<f64>>,
+}
+
+impl Counter {
+    /// Creates a counter at zero.
+    pub fn new() -> Counter {
+        Counter::default()
+    }
+    /// Returns the total counted so far.
+    pub fn get(&self) -> f64 {
+        self.total.get()
+    }
+    /// Sets the total back to zero.
+    pub fn reset(&self) {
+        self.total.set(0.0);
+    }
+    /// Returns true if both handles refer to the same counter.
+    pub fn ptr_eq(&self, other: &Counter) -> bool {
+        Rc::ptr_eq(&self.total, &other.total)
+    }
+    pub(crate) fn add(&self, amount: f64) {
+        self.total.set(self.total.get() + amount);
+    }
+}
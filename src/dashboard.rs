@@ -0,0 +1,197 @@
+use std::io::{self, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread::sleep;
+use std::time::Duration;
+
+use crate::stream::{self, HEADER};
+use crate::{Bucket, Model};
+
+/// The page served at `/`, which charts the events from `/events` and
+/// drives the control endpoints.
+const PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Model</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+button { margin-right: 0.5em; }
+canvas { border: 1px solid #ccc; margin-top: 1em; }
+#legend span { margin-right: 1em; }
+</style>
+</head>
+<body>
+<div>
+<button onclick="control('start')">Start</button>
+<button onclick="control('stop')">Stop</button>
+<button onclick="control('reset')">Reset</button>
+<span id="status"></span>
+</div>
+<canvas id="chart" width="900" height="450"></canvas>
+<div id="legend"></div>
+<script>
+const palette = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf"];
+let times = [], series = {};
+function control(action) { fetch("/" + action, { method: "POST" }); }
+function draw() {
+  const canvas = document.getElementById("chart"), context = canvas.getContext("2d");
+  context.clearRect(0, 0, canvas.width, canvas.height);
+  const names = Object.keys(series);
+  const high = Math.max(1, ...names.flatMap(name => series[name]).filter(Number.isFinite));
+  const start = times[0] || 0, span = Math.max(1e-9, (times[times.length - 1] || 0) - start);
+  const x = t => 40 + (t - start) / span * (canvas.width - 50);
+  const y = v => canvas.height - 20 - v / high * (canvas.height - 30);
+  context.fillStyle = "black";
+  context.fillText(high.toPrecision(4), 2, 14);
+  context.fillText("time " + (times[times.length - 1] || 0), canvas.width - 120, canvas.height - 4);
+  names.forEach((name, index) => {
+    context.strokeStyle = palette[index % palette.length];
+    context.beginPath();
+    series[name].forEach((value, point) => {
+      if (point == 0) context.moveTo(x(times[point]), y(value));
+      else context.lineTo(x(times[point]), y(value));
+    });
+    context.stroke();
+  });
+  document.getElementById("legend").innerHTML = names.map((name, index) =>
+    `<span style="color:${palette[index % palette.length]}">${name} ${series[name][series[name].length - 1].toFixed(2)}</span>`
+  ).join("");
+}
+const events = new EventSource("/events");
+events.onmessage = event => {
+  const state = JSON.parse(event.data);
+  times.push(state.time);
+  for (const [name, value] of Object.entries(state.buckets)) (series[name] = series[name] || []).push(value);
+  draw();
+};
+events.addEventListener("reset", () => { times = []; series = {}; draw(); });
+events.addEventListener("status", event => document.getElementById("status").textContent = event.data);
+</script>
+</body>
+</html>
+"##;
+
+/// The clients listening to a dashboard, and every event since the model
+/// was last reset, which is replayed to clients that connect later.
+struct Clients {
+    streams: Vec<TcpStream>,
+    events: Vec<String>,
+    status: String,
+}
+
+impl Clients {
+    fn send(&mut self, event: String) {
+        self.streams
+            .retain_mut(|stream| stream.write_all(event.as_bytes()).is_ok());
+        self.events.push(event);
+    }
+    fn set_status(&mut self, status: &str) {
+        self.status = format!("event: status\ndata: {}\n\n", status);
+        let status = &self.status;
+        self.streams
+            .retain_mut(|stream| stream.write_all(status.as_bytes()).is_ok());
+    }
+    fn add(&mut self, mut stream: TcpStream) {
+        let replayed = self
+            .events
+            .iter()
+            .chain(Some(&self.status))
+            .all(|event| stream.write_all(event.as_bytes()).is_ok());
+        if replayed {
+            self.streams.push(stream);
+        }
+    }
+}
+
+/// Serves a page charting `model` live on `address`, with endpoints to
+/// start, stop and reset it, as described on [`Model::serve`].
+pub(crate) fn serve(
+    model: &mut Model,
+    address: impl ToSocketAddrs,
+    speed: u64,
+    interval: Duration,
+) -> io::Result<()> {
+    model
+        .validate()
+        .map_err(|error| io::Error::new(ErrorKind::InvalidInput, error))?;
+    let listener = TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+    let time = model.time();
+    let start = model.buckets().map(Bucket::get).collect::<Vec<_>>();
+    let seed = model.metadata().seed;
+
+    let mut clients = Clients {
+        streams: Vec::new(),
+        events: Vec::new(),
+        status: String::new(),
+    };
+    let mut running = false;
+    clients.set_status("stopped");
+    clients.send(stream::state_event(model.time(), &model.state()));
+    loop {
+        loop {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            };
+            let (method, path) = match stream::read_request(&stream) {
+                Ok(request) => request,
+                Err(_) => continue,
+            };
+            let mut stream = stream;
+            let response = match (method.as_str(), path.as_str()) {
+                ("GET", "/") => {
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        PAGE.len(),
+                        PAGE
+                    );
+                    continue;
+                }
+                ("GET", "/events") => {
+                    if stream.write_all(HEADER.as_bytes()).is_ok() {
+                        clients.add(stream);
+                    }
+                    continue;
+                }
+                ("POST", "/start") => {
+                    running = true;
+                    clients.set_status("running");
+                    "204 No Content"
+                }
+                ("POST", "/stop") => {
+                    running = false;
+                    clients.set_status("stopped");
+                    "204 No Content"
+                }
+                ("POST", "/reset") => {
+                    model.restart(time, &start);
+                    model.set_seed(seed);
+                    clients.events.clear();
+                    clients.send("event: reset\ndata: \n\n".to_owned());
+                    clients.send(stream::state_event(model.time(), &model.state()));
+                    "204 No Content"
+                }
+                _ => "404 Not Found",
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                response
+            );
+        }
+        if running {
+            match model.try_step(speed) {
+                Ok(()) => clients.send(stream::state_event(model.time(), &model.state())),
+                Err(error) => {
+                    running = false;
+                    clients.set_status(&format!("stopped: {}", error));
+                }
+            }
+        }
+        sleep(interval);
+    }
+}
@@ -0,0 +1,256 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::{Model, Rng, SimulationResult, TimeSeries};
+
+/// The trajectories of many replicate runs of a stochastic model, all
+/// started from the same state and recorded at the same time points.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ensemble {
+    runs: Vec<SimulationResult>,
+    seeds: Vec<u64>,
+}
+
+impl Ensemble {
+    pub(crate) fn new(runs: Vec<SimulationResult>, seeds: Vec<u64>) -> Ensemble {
+        Ensemble { runs, seeds }
+    }
+    /// Runs `runs` replicates for `ticks` ticks each across `jobs` threads,
+    /// or one per core if `jobs` is 0, building a fresh model for every run
+    /// with `build`.
+    ///
+    /// Models share their buckets through reference counting and cannot
+    /// cross threads, so each thread builds its own replicas instead. Run
+    /// `n` is seeded with the `n`th number drawn from a generator seeded
+    /// with `seed`, so the result is the same whatever the number of jobs.
+    pub fn parallel<F>(build: F, runs: usize, ticks: u64, seed: u64, jobs: usize) -> Ensemble
+    where
+        F: Fn() -> Model + Sync,
+    {
+        let mut rng = Rng::new(seed);
+        let seeds: Vec<u64> = (0..runs).map(|_| rng.next_u64()).collect();
+        let results = parallel(runs, jobs, |run| {
+            build().with_seed(seeds[run]).simulate(ticks)
+        });
+        Ensemble::new(results, seeds)
+    }
+    /// Returns the trajectory of every run.
+    pub fn runs(&self) -> &[SimulationResult] {
+        &self.runs
+    }
+    /// Returns the seed each run was given, in the same order as the runs.
+    pub fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+    /// Iterates over the trajectory of every run.
+    pub fn iter(&self) -> slice::Iter<'_, SimulationResult> {
+        self.runs.iter()
+    }
+    /// Returns the number of runs.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+    /// Returns true if there are no runs.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+    /// Returns the time of every recorded point, which every run shares.
+    pub fn times(&self) -> &[f64] {
+        self.runs.first().map_or(&[], SimulationResult::times)
+    }
+    /// Returns the names of the recorded buckets.
+    pub fn names(&self) -> Vec<&str> {
+        self.runs
+            .first()
+            .map_or_else(Vec::new, SimulationResult::names)
+    }
+    /// Writes every run to the file at `path` as CSV in long format,
+    /// replacing it if it exists. See
+    /// [`write_csv`](Ensemble::write_csv) for the layout.
+    pub fn to_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+    /// Writes every run to `writer` as CSV in long format, with the columns
+    /// `run`, `seed`, `time`, `compartment` and `value` and one row per
+    /// value recorded.
+    ///
+    /// Long format suits large ensembles, since it can be streamed, filtered
+    /// and grouped by run or compartment without knowing the compartments
+    /// in advance. With the `arrow` feature, `Ensemble::write_arrow` writes
+    /// the same columns as an Arrow IPC stream, which is far more compact
+    /// for ensembles of thousands of runs.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["run", "seed", "time", "compartment", "value"])?;
+        for (run, (result, seed)) in self.runs.iter().zip(&self.seeds).enumerate() {
+            let (run, seed) = (run.to_string(), seed.to_string());
+            for series in result.series() {
+                for (time, value) in result.times().iter().zip(series) {
+                    writer.write_record([
+                        run.as_str(),
+                        seed.as_str(),
+                        &time.to_string(),
+                        series.name(),
+                        &value.to_string(),
+                    ])?;
+                }
+            }
+        }
+        writer.flush()
+    }
+    /// Returns the mean across runs of the bucket called `name` at every
+    /// time point.
+    pub fn mean(&self, name: &str) -> Option<TimeSeries> {
+        self.summarise(name, |values| {
+            values.iter().sum::<f64>() / values.len() as f64
+        })
+    }
+    /// Returns the median across runs of the bucket called `name` at every
+    /// time point.
+    pub fn median(&self, name: &str) -> Option<TimeSeries> {
+        self.quantile(name, 0.5)
+    }
+    /// Returns the `q`th quantile across runs of the bucket called `name`
+    /// at every time point, interpolating linearly between runs. `q` is
+    /// clamped to `[0, 1]`.
+    pub fn quantile(&self, name: &str, q: f64) -> Option<TimeSeries> {
+        self.summarise(name, |values| quantile(values, q))
+    }
+    /// Returns the `lower` and `upper` quantiles across runs of the bucket
+    /// called `name`, such as `0.05` and `0.95` for a 90% band.
+    pub fn band(&self, name: &str, lower: f64, upper: f64) -> Option<(TimeSeries, TimeSeries)> {
+        Some((self.quantile(name, lower)?, self.quantile(name, upper)?))
+    }
+    /// Returns the [`difference`](SimulationResult::difference) between
+    /// every run and the run at the same index of `other`, such as
+    /// infections averted by an intervention when this is the baseline,
+    /// whose [`band`](Ensemble::band) carries the uncertainty of the runs
+    /// into the difference. Returns `None` unless both ensembles have the
+    /// same number of runs recorded at the same times.
+    ///
+    /// Ensembles run from the same seed, as by
+    /// [`Ensemble::parallel`], pair runs that share their random numbers, so
+    /// the spread of the differences reflects the intervention more than
+    /// chance.
+    pub fn difference(&self, other: &Ensemble) -> Option<Ensemble> {
+        self.pair(other, SimulationResult::difference)
+    }
+    /// Returns the [`ratio`](SimulationResult::ratio) of every run to the
+    /// run at the same index of `other`, paired as by
+    /// [`difference`](Ensemble::difference).
+    pub fn ratio(&self, other: &Ensemble) -> Option<Ensemble> {
+        self.pair(other, SimulationResult::ratio)
+    }
+    /// Returns the
+    /// [`cumulative_difference`](SimulationResult::cumulative_difference)
+    /// between every run and the run at the same index of `other`, paired
+    /// as by [`difference`](Ensemble::difference).
+    pub fn cumulative_difference(&self, other: &Ensemble) -> Option<Ensemble> {
+        self.pair(other, SimulationResult::cumulative_difference)
+    }
+    /// Combines every run with the run at the same index of `other`,
+    /// keeping the seeds of this ensemble.
+    fn pair<F>(&self, other: &Ensemble, combine: F) -> Option<Ensemble>
+    where
+        F: Fn(&SimulationResult, &SimulationResult) -> Option<SimulationResult>,
+    {
+        if self.len() != other.len() {
+            return None;
+        }
+        let runs = self
+            .runs
+            .iter()
+            .zip(&other.runs)
+            .map(|(run, other)| combine(run, other))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Ensemble::new(runs, self.seeds.clone()))
+    }
+    /// Summarises the values of the bucket called `name` across runs at
+    /// every time point, or returns `None` if there are no runs or no such
+    /// bucket.
+    fn summarise<F>(&self, name: &str, mut summary: F) -> Option<TimeSeries>
+    where
+        F: FnMut(&mut [f64]) -> f64,
+    {
+        let series = self
+            .runs
+            .iter()
+            .map(|run| run.get(name))
+            .collect::<Option<Vec<_>>>()?;
+        if series.is_empty() {
+            return None;
+        }
+        let points = series.iter().map(|series| series.len()).min().unwrap_or(0);
+        let mut result = TimeSeries::new(name.to_owned());
+        let mut values = Vec::with_capacity(series.len());
+        (0..points).for_each(|index| {
+            values.clear();
+            values.extend(series.iter().map(|series| series.values()[index]));
+            result.push(summary(&mut values));
+        });
+        Some(result)
+    }
+}
+
+impl<'a> IntoIterator for &'a Ensemble {
+    type Item = &'a SimulationResult;
+    type IntoIter = slice::Iter<'a, SimulationResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Computes `task` for every index below `count` across `jobs` threads, or
+/// one per core if `jobs` is 0, returning the results in index order.
+pub(crate) fn parallel<T, F>(count: usize, jobs: usize, task: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let jobs = match jobs {
+        0 => thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        jobs => jobs,
+    };
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(count))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            break results;
+                        }
+                        results.push((index, task(index)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("parallel run panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Returns the `q`th quantile of `values`, sorting them in place, or NaN
+/// if there are none.
+fn quantile(values: &mut [f64], q: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    values.sort_by(f64::total_cmp);
+    let position = q.clamp(0.0, 1.0) * (values.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let fraction = position - below as f64;
+    values[below] + (values[above] - values[below]) * fraction
+}
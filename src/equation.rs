@@ -0,0 +1,281 @@
+//! Parsing of reaction equations such as `S + I -> 2I, rate = 0.3/N`.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::State;
+
+/// A reaction read from an equation, with the compartments it consumes and
+/// produces, each with its stoichiometric coefficient.
+pub(crate) struct Equation {
+    pub(crate) reactants: Vec<(String, u32)>,
+    pub(crate) products: Vec<(String, u32)>,
+    pub(crate) rate: Expression,
+}
+
+impl Equation {
+    /// Parses an equation, returning a description of what is wrong with it
+    /// if it is malformed.
+    pub(crate) fn parse(text: &str) -> Result<Equation, String> {
+        let (reaction, rate) = text
+            .split_once(',')
+            .ok_or_else(|| "expected `, rate = ...` after the reaction".to_owned())?;
+        let (reactants, products) = reaction
+            .split_once("->")
+            .ok_or_else(|| "expected `->` between reactants and products".to_owned())?;
+        let rate = match rate.split_once('=') {
+            Some((key, rate)) if key.trim() == "rate" => rate,
+            _ => return Err("expected `rate = ...` after the reaction".to_owned()),
+        };
+        let equation = Equation {
+            reactants: side(reactants)?,
+            products: side(products)?,
+            rate: Expression::parse(rate)?,
+        };
+        if equation.reactants.is_empty() && equation.products.is_empty() {
+            return Err("the reaction has neither reactants nor products".to_owned());
+        }
+        Ok(equation)
+    }
+}
+
+/// Parses one side of a reaction, a `+`-separated list of compartment names
+/// each with an optional coefficient, as in `2I`. An empty side is a source
+/// or sink outside the model.
+fn side(text: &str) -> Result<Vec<(String, u32)>, String> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split('+')
+        .map(|term| {
+            let term = term.trim();
+            let digits = term.len() - term.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let (count, name) = term.split_at(digits);
+            let count = if count.is_empty() {
+                1
+            } else {
+                count
+                    .parse()
+                    .map_err(|_| format!("coefficient `{}` is too large", count))?
+            };
+            let name = name.trim();
+            if !is_name(name) {
+                return Err(format!("expected a compartment, found `{}`", term));
+            }
+            Ok((name.to_owned(), count))
+        })
+        .collect()
+}
+
+fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// An arithmetic expression over numbers and compartment names, where `N`
+/// stands for the total living population unless a compartment is named
+/// `N`.
+pub(crate) enum Expression {
+    Number(f64),
+    Name(String),
+    Negate(Box<Expression>),
+    Binary(char, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Parses an expression of numbers, names, parentheses and the
+    /// operators `+`, `-`, `*`, `/` and `^`, with the usual precedence.
+    pub(crate) fn parse(text: &str) -> Result<Expression, String> {
+        let mut parser = Parser {
+            chars: text.chars().peekable(),
+        };
+        let expression = parser.sum()?;
+        parser.skip_whitespace();
+        match parser.chars.next() {
+            None => Ok(expression),
+            Some(c) => Err(format!("unexpected `{}` in the rate", c)),
+        }
+    }
+    /// Returns every name the expression refers to.
+    pub(crate) fn names(&self) -> Vec<&str> {
+        match self {
+            Expression::Number(_) => Vec::new(),
+            Expression::Name(name) => vec![name],
+            Expression::Negate(operand) => operand.names(),
+            Expression::Binary(_, left, right) => {
+                let mut names = left.names();
+                names.extend(right.names());
+                names
+            }
+        }
+    }
+    /// Evaluates the expression against the quantities in `state`.
+    pub(crate) fn evaluate(&self, state: &State) -> f64 {
+        match self {
+            Expression::Number(value) => *value,
+            Expression::Name(name) => match state.get(name) {
+                Some(quantity) => quantity,
+                None if name == "N" => state.total(),
+                None => f64::NAN,
+            },
+            Expression::Negate(operand) => -operand.evaluate(state),
+            Expression::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(state), right.evaluate(state));
+                match operator {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => left.powf(right),
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+    fn operator(&mut self, operators: &str) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.next_if(|c| operators.contains(*c))
+    }
+    fn sum(&mut self) -> Result<Expression, String> {
+        let mut expression = self.product()?;
+        while let Some(operator) = self.operator("+-") {
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.product()?));
+        }
+        Ok(expression)
+    }
+    fn product(&mut self) -> Result<Expression, String> {
+        let mut expression = self.unary()?;
+        while let Some(operator) = self.operator("*/") {
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+    /// Parses a negation, which binds more loosely than `^`, so `-2^2` is
+    /// -4.
+    fn unary(&mut self) -> Result<Expression, String> {
+        match self.operator("-") {
+            Some(_) => Ok(Expression::Negate(Box::new(self.unary()?))),
+            None => self.power(),
+        }
+    }
+    fn power(&mut self) -> Result<Expression, String> {
+        let base = self.atom()?;
+        match self.operator("^") {
+            Some(operator) => Ok(Expression::Binary(
+                operator,
+                Box::new(base),
+                Box::new(self.unary()?),
+            )),
+            None => Ok(base),
+        }
+    }
+    fn atom(&mut self) -> Result<Expression, String> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('(') => {
+                self.chars.next();
+                let expression = self.sum()?;
+                match self.operator(")") {
+                    Some(_) => Ok(expression),
+                    None => Err("expected `)` in the rate".to_owned()),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                if let Some(e) = self.chars.next_if(|c| *c == 'e' || *c == 'E') {
+                    number.push(e);
+                    if let Some(sign) = self.chars.next_if(|c| *c == '-' || *c == '+') {
+                        number.push(sign);
+                    }
+                    while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+                        number.push(c);
+                    }
+                }
+                number
+                    .parse()
+                    .map(Expression::Number)
+                    .map_err(|_| format!("malformed number `{}` in the rate", number))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                Ok(Expression::Name(name))
+            }
+            Some(c) => Err(format!("unexpected `{}` in the rate", c)),
+            None => Err("the rate ends unexpectedly".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Equation, Expression};
+    use crate::Model;
+
+    fn state() -> crate::State {
+        Model::builder()
+            .compartment("S", 990)
+            .compartment("I", 10)
+            .build()
+            .unwrap()
+            .state()
+    }
+
+    #[test]
+    fn equations_name_their_reactants_and_products() {
+        let equation = Equation::parse("S + I -> 2I, rate = 0.3 * S * I / N").unwrap();
+        assert_eq!(
+            equation.reactants,
+            [("S".to_owned(), 1), ("I".to_owned(), 1)]
+        );
+        assert_eq!(equation.products, [("I".to_owned(), 2)]);
+        assert!((equation.rate.evaluate(&state()) - 0.3 * 990.0 * 10.0 / 1000.0).abs() < 1e-12);
+        let birth = Equation::parse(" -> S, rate = 5").unwrap();
+        assert!(birth.reactants.is_empty());
+        assert_eq!(birth.products, [("S".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn expressions_follow_the_usual_precedence() {
+        let rate = |text| Expression::parse(text).unwrap().evaluate(&state());
+        assert_eq!(rate("1 + 2 * 3"), 7.0);
+        assert_eq!(rate("(1 + 2) * 3"), 9.0);
+        assert_eq!(rate("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(rate("-2 ^ 2"), -4.0);
+        assert_eq!(rate("2 ^ -1"), 0.5);
+        assert_eq!(rate("8 / 4 / 2"), 1.0);
+        assert_eq!(rate("1.5e2 - I"), 140.0);
+    }
+
+    #[test]
+    fn malformed_equations_are_rejected() {
+        for text in [
+            "S + I -> 2I",
+            "S + I 2I, rate = 1",
+            "S + I -> 2I, beta = 1",
+            "S + 2 -> I, rate = 1",
+            " -> , rate = 1",
+            "S -> I, rate = (1 + 2",
+            "S -> I, rate = 1 2",
+        ] {
+            assert!(Equation::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::context::Shared;
+use crate::linalg::{eigenvalues, invert};
+use crate::solver;
+use crate::Bucket;
+
+/// The relative size below which derivatives count as zero, so that a
+/// state counts as an equilibrium.
+const TOLERANCE: f64 = 1e-9;
+
+/// The relative size below which the real part of an eigenvalue counts as
+/// zero.
+const NEUTRAL: f64 = 1e-7;
+
+/// One eigenvalue of a Jacobian.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eigenvalue {
+    /// The real part, the rate at which perturbations along its direction
+    /// grow, or shrink if negative.
+    pub real: f64,
+    /// The imaginary part, the angular frequency at which perturbations
+    /// along its direction oscillate.
+    pub imaginary: f64,
+}
+
+impl Display for Eigenvalue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.imaginary == 0.0 {
+            write!(f, "{:.6}", self.real)
+        } else {
+            write!(f, "{:.6}{:+.6}i", self.real, self.imaginary)
+        }
+    }
+}
+
+/// Whether small perturbations of an equilibrium die away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stability {
+    /// Every eigenvalue has a negative real part, so perturbations die
+    /// away and the model returns to the equilibrium.
+    Stable,
+    /// An eigenvalue has a positive real part, so some perturbations grow
+    /// and the model leaves the equilibrium, as an epidemic leaves the
+    /// disease-free state when R0 is above one.
+    Unstable,
+    /// The largest real part is zero, as at a bifurcation threshold such as
+    /// R0 of exactly one, so stability can't be told from the Jacobian.
+    Neutral,
+}
+
+impl Display for Stability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Stability::Stable => write!(f, "stable"),
+            Stability::Unstable => write!(f, "unstable"),
+            Stability::Neutral => write!(f, "neutral"),
+        }
+    }
+}
+
+/// A state in which a model's flows balance, so that it stays put, with
+/// the eigenvalues of the Jacobian of the flows there that tell whether it
+/// is stable. Displaying it draws the state and eigenvalues as a table.
+///
+/// Sinks are left out, since they keep filling at an endemic equilibrium
+/// with deaths.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equilibrium {
+    names: Vec<String>,
+    values: Vec<f64>,
+    jacobian: Vec<Vec<f64>>,
+    eigenvalues: Vec<Eigenvalue>,
+    conserved: bool,
+}
+
+impl Equilibrium {
+    /// Returns the name of every bucket, other than sinks, in the order
+    /// they were added to the model.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+    /// Returns the quantity of every bucket, other than sinks, at the
+    /// equilibrium.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+    /// Returns the quantity of the bucket called `name` at the equilibrium.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        let index = self.names.iter().position(|n| n == name)?;
+        Some(self.values[index])
+    }
+    /// Returns the Jacobian of the flows at the equilibrium, whose entry
+    /// `(i, j)` is how fast the rate of change of bucket `i` changes with
+    /// the quantity of bucket `j`, in the order of
+    /// [`names`](Equilibrium::names).
+    pub fn jacobian(&self) -> &[Vec<f64>] {
+        &self.jacobian
+    }
+    /// Returns every eigenvalue of the Jacobian, from the largest real part
+    /// to the smallest.
+    pub fn eigenvalues(&self) -> &[Eigenvalue] {
+        &self.eigenvalues
+    }
+    /// Returns true if the flows conserve the total population, in which
+    /// case one eigenvalue is zero whatever the dynamics, along the
+    /// direction that changes the total, and is left out when judging
+    /// stability.
+    pub fn is_conserved(&self) -> bool {
+        self.conserved
+    }
+    /// Returns the eigenvalue that decides stability, the one with the
+    /// largest real part other than the zero from a conserved total. Sweep
+    /// a parameter and watch its real part cross zero to find a bifurcation
+    /// threshold.
+    pub fn dominant(&self) -> Option<Eigenvalue> {
+        let mut eigenvalues = self.eigenvalues.clone();
+        if self.conserved {
+            let zero = (0..eigenvalues.len()).min_by(|a, b| {
+                let magnitude = |e: &Eigenvalue| e.real.hypot(e.imaginary);
+                magnitude(&eigenvalues[*a]).total_cmp(&magnitude(&eigenvalues[*b]))
+            })?;
+            eigenvalues.remove(zero);
+        }
+        eigenvalues.first().copied()
+    }
+    /// Returns whether small perturbations of the equilibrium die away.
+    pub fn stability(&self) -> Stability {
+        let scale = self
+            .eigenvalues
+            .iter()
+            .map(|e| e.real.hypot(e.imaginary))
+            .fold(0.0, f64::max);
+        match self.dominant() {
+            Some(e) if e.real > NEUTRAL * scale => Stability::Unstable,
+            Some(e) if e.real >= -NEUTRAL * scale => Stability::Neutral,
+            _ => Stability::Stable,
+        }
+    }
+}
+
+impl Display for Equilibrium {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self
+            .names
+            .iter()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (name, value) in self.names.iter().zip(&self.values) {
+            writeln!(f, "{:<width$}  {:.6}", name, value, width = width)?;
+        }
+        let eigenvalues: Vec<String> = self.eigenvalues.iter().map(|e| e.to_string()).collect();
+        writeln!(f, "Eigenvalues: {}", eigenvalues.join(", "))?;
+        writeln!(f, "Stability: {}", self.stability())
+    }
+}
+
+/// The disease-free and endemic equilibria of a model, found by
+/// [`Model::equilibria`](crate::Model::equilibria). Either is `None` if
+/// the model has no such equilibrium or it couldn't be found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equilibria {
+    /// The equilibrium with no one infected.
+    pub disease_free: Option<Equilibrium>,
+    /// An equilibrium at which the infection persists.
+    pub endemic: Option<Equilibrium>,
+}
+
+impl Display for Equilibria {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (title, equilibrium) in [
+            ("Disease-free equilibrium", &self.disease_free),
+            ("Endemic equilibrium", &self.endemic),
+        ]
+        .iter()
+        {
+            writeln!(f, "{}:", title)?;
+            match equilibrium {
+                Some(equilibrium) => write!(f, "{}", equilibrium)?,
+                None => writeln!(f, "none found")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The vector field of a model's flows over its living buckets, evaluated
+/// as by a solver without disturbing the model.
+pub(crate) struct Flows<'a> {
+    buckets: &'a [Bucket],
+    shared: Shared<'a>,
+    time: f64,
+    dt: f64,
+    living: Vec<usize>,
+}
+
+impl<'a> Flows<'a> {
+    pub(crate) fn new(buckets: &'a [Bucket], shared: Shared<'a>, time: f64, dt: f64) -> Flows<'a> {
+        let living = (0..buckets.len())
+            .filter(|i| !buckets[*i].is_sink())
+            .collect();
+        Flows {
+            buckets,
+            shared,
+            time,
+            dt,
+            living,
+        }
+    }
+    /// Returns the current quantity of every living bucket.
+    pub(crate) fn current(&self) -> Vec<f64> {
+        self.living.iter().map(|i| self.buckets[*i].get()).collect()
+    }
+    /// Returns the index among the living buckets of the bucket called
+    /// `name`.
+    pub(crate) fn position(&self, name: &str) -> Option<usize> {
+        self.living
+            .iter()
+            .position(|i| self.buckets[*i].name() == name)
+    }
+    /// Returns the rate of change per tick of every living bucket with the
+    /// living buckets holding `quantities`, leaving every bucket as it was.
+    pub(crate) fn rates(&self, quantities: &[f64]) -> Vec<f64> {
+        let start: Vec<f64> = self.buckets.iter().map(Bucket::get).collect();
+        for (i, quantity) in self.living.iter().zip(quantities) {
+            self.buckets[*i].set(*quantity);
+        }
+        let mut rates = vec![0.0; self.living.len()];
+        let position = |bucket: &Option<Bucket>| {
+            bucket.as_ref().and_then(|bucket| {
+                self.living
+                    .iter()
+                    .position(|i| self.buckets[*i].ptr_eq(bucket))
+            })
+        };
+        for transfer in solver::transfers(self.buckets, self.shared.trial(), self.time, self.dt) {
+            let rate = transfer.amount / self.dt;
+            if let Some(from) = position(&transfer.from) {
+                rates[from] -= rate;
+            }
+            if let Some(to) = position(&transfer.to) {
+                rates[to] += rate;
+            }
+        }
+        self.buckets
+            .iter()
+            .zip(&start)
+            .for_each(|(bucket, quantity)| bucket.set(*quantity));
+        rates
+    }
+    /// Returns the Jacobian of the rates at `quantities` by central
+    /// differences, or forward ones where a quantity is too close to zero
+    /// to step below it.
+    pub(crate) fn jacobian(&self, quantities: &[f64]) -> Vec<Vec<f64>> {
+        let n = quantities.len();
+        let mut jacobian = vec![vec![0.0; n]; n];
+        let base = self.rates(quantities);
+        for j in 0..n {
+            let h = 1e-6 * quantities[j].abs().max(1.0);
+            let mut above = quantities.to_vec();
+            above[j] += h;
+            let rates_above = self.rates(&above);
+            let (rates_below, span) = if quantities[j] >= h {
+                let mut below = quantities.to_vec();
+                below[j] -= h;
+                (self.rates(&below), 2.0 * h)
+            } else {
+                (base.clone(), h)
+            };
+            for i in 0..n {
+                jacobian[i][j] = (rates_above[i] - rates_below[i]) / span;
+            }
+        }
+        jacobian
+    }
+    /// Looks for an equilibrium by following the flows from `start` for up
+    /// to `ticks` ticks by the fourth-order Runge-Kutta method, then
+    /// homing in on it with damped Newton steps. Returns `None` if it
+    /// doesn't settle.
+    pub(crate) fn settle(&self, start: &[f64], ticks: u64) -> Option<Vec<f64>> {
+        let scale = start.iter().map(|x| x.abs()).sum::<f64>().max(1.0);
+        let size = |rates: &[f64]| rates.iter().map(|r| r.abs()).fold(0.0, f64::max);
+        let mut x = start.to_vec();
+        let h = self.dt;
+        let steps = (ticks as f64 / h).ceil() as u64;
+        for _ in 0..steps {
+            let k1 = self.rates(&x);
+            if size(&k1) <= TOLERANCE * scale {
+                break;
+            }
+            let shifted = |k: &[f64], by: f64| -> Vec<f64> {
+                x.iter().zip(k).map(|(x, k)| x + by * k).collect()
+            };
+            let k2 = self.rates(&shifted(&k1, h / 2.0));
+            let k3 = self.rates(&shifted(&k2, h / 2.0));
+            let k4 = self.rates(&shifted(&k3, h));
+            for i in 0..x.len() {
+                x[i] = (x[i] + h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i])).max(0.0);
+            }
+        }
+        // Levenberg-Marquardt steps, which stay well defined where the
+        // Jacobian is singular, as it is along a conserved total.
+        let mut lambda = 1e-3;
+        let mut residual = self.rates(&x);
+        for _ in 0..100 {
+            if size(&residual) <= TOLERANCE * scale {
+                return Some(x);
+            }
+            let jacobian = self.jacobian(&x);
+            let n = x.len();
+            let gradient: Vec<f64> = (0..n)
+                .map(|j| (0..n).map(|i| jacobian[i][j] * residual[i]).sum())
+                .collect();
+            let normal: Vec<Vec<f64>> = (0..n)
+                .map(|j| {
+                    (0..n)
+                        .map(|k| (0..n).map(|i| jacobian[i][j] * jacobian[i][k]).sum())
+                        .collect()
+                })
+                .collect();
+            let norm = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>();
+            loop {
+                let mut damped = normal.clone();
+                for (j, row) in damped.iter_mut().enumerate() {
+                    row[j] += lambda * normal[j][j].max(1e-12);
+                }
+                let inverse = invert(damped)?;
+                let candidate: Vec<f64> = (0..n)
+                    .map(|j| {
+                        let step: f64 = (0..n).map(|k| inverse[j][k] * gradient[k]).sum();
+                        (x[j] - step).max(0.0)
+                    })
+                    .collect();
+                let rates = self.rates(&candidate);
+                if norm(&rates) < norm(&residual) {
+                    x = candidate;
+                    residual = rates;
+                    lambda = (lambda / 10.0).max(1e-12);
+                    break;
+                }
+                lambda *= 10.0;
+                if lambda > 1e12 {
+                    return None;
+                }
+            }
+        }
+        if size(&residual) <= TOLERANCE * scale {
+            Some(x)
+        } else {
+            None
+        }
+    }
+    /// Describes the equilibrium at `quantities`.
+    pub(crate) fn equilibrium(&self, quantities: Vec<f64>) -> Equilibrium {
+        let jacobian = self.jacobian(&quantities);
+        let mut eigenvalues: Vec<Eigenvalue> = eigenvalues(&jacobian)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(real, imaginary)| Eigenvalue { real, imaginary })
+            .collect();
+        eigenvalues.sort_by(|a, b| b.real.total_cmp(&a.real));
+        let largest = jacobian
+            .iter()
+            .flatten()
+            .map(|x| x.abs())
+            .fold(0.0, f64::max);
+        let conserved = (0..jacobian.len()).all(|j| {
+            jacobian.iter().map(|row| row[j]).sum::<f64>().abs() <= 1e-6 * largest.max(1e-12)
+        });
+        Equilibrium {
+            names: self
+                .living
+                .iter()
+                .map(|i| self.buckets[*i].name())
+                .collect(),
+            values: quantities,
+            jacobian,
+            eigenvalues,
+            conserved,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::Transfer;
+
+/// A transfer waiting to be applied at a future time.
+struct Event {
+    time: f64,
+    sequence: u64,
+    transfer: Transfer,
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Event) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Event) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    /// Orders events so the earliest is greatest, and so first out of a
+    /// max-heap, with ties going to the one scheduled first.
+    fn cmp(&self, other: &Event) -> Ordering {
+        other
+            .time
+            .total_cmp(&self.time)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// One-off transfers scheduled for future times, in order of when they are
+/// due.
+#[derive(Default)]
+pub(crate) struct EventQueue {
+    events: BinaryHeap<Event>,
+    sequence: u64,
+}
+
+impl EventQueue {
+    /// Schedules `transfer` to be applied at `time`.
+    pub(crate) fn push(&mut self, time: f64, transfer: Transfer) {
+        self.events.push(Event {
+            time,
+            sequence: self.sequence,
+            transfer,
+        });
+        self.sequence += 1;
+    }
+    /// Removes and returns every transfer due at or before `time`, earliest
+    /// first.
+    pub(crate) fn pop_due(&mut self, time: f64) -> Vec<Transfer> {
+        let mut due = Vec::new();
+        while self.events.peek().is_some_and(|event| event.time <= time) {
+            due.extend(self.events.pop().map(|event| event.transfer));
+        }
+        due
+    }
+    /// Moves every event in `other` into this queue, as though they had
+    /// been scheduled here in the same order.
+    pub(crate) fn append(&mut self, other: &mut EventQueue) {
+        let mut events = std::mem::take(&mut other.events).into_vec();
+        events.sort_by_key(|event| event.sequence);
+        events
+            .into_iter()
+            .for_each(|event| self.push(event.time, event.transfer));
+    }
+    /// Returns every waiting event with the time it is due, in the order
+    /// they were scheduled.
+    pub(crate) fn events(&self) -> Vec<(f64, &Transfer)> {
+        let mut events: Vec<&Event> = self.events.iter().collect();
+        events.sort_by_key(|event| event.sequence);
+        events
+            .into_iter()
+            .map(|event| (event.time, &event.transfer))
+            .collect()
+    }
+    /// Returns the number of events waiting.
+    pub(crate) fn len(&self) -> usize {
+        self.events.len()
+    }
+    /// Drops every waiting event.
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+    }
+}
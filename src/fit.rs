@@ -0,0 +1,521 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::optimize::{nelder_mead, Minimum};
+use crate::{Model, ParameterError, ParameterProfile, Profile};
+
+/// What an observed time series measures in a model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// The quantity of the named compartment, such as the number currently
+    /// in hospital.
+    Prevalence(String),
+    /// How much the behaviour at the given `bucket.behaviour` path moved
+    /// during each tick, as in
+    /// [`Model::simulate_flows`](crate::Model::simulate_flows), such as new
+    /// cases per day.
+    Incidence(String),
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Prevalence(name) => write!(f, "{}", name),
+            Target::Incidence(path) => write!(f, "incidence of {}", path),
+        }
+    }
+}
+
+/// An error reading observations or fitting a model to them.
+#[derive(Debug)]
+pub enum FitError {
+    /// The observations couldn't be read.
+    Io(io::Error),
+    /// The observations have no column by the given name.
+    MissingColumn(String),
+    /// A value in the observations isn't a number.
+    Malformed(String),
+    /// The model has no compartment or behaviour to compare against the
+    /// target.
+    UnknownTarget(Target),
+    /// The model has no parameter at the given path.
+    Parameter(ParameterError),
+    /// The given parameter's bounds are empty or not finite.
+    Bounds(String),
+    /// There are no observations to fit to.
+    NoObservations,
+    /// Too few simulations came within the given tolerance of the
+    /// observations before the simulation budget ran out.
+    Tolerance(f64),
+}
+
+impl Display for FitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FitError::Io(error) => write!(f, "{}", error),
+            FitError::MissingColumn(name) => write!(f, "observations have no column `{}`", name),
+            FitError::Malformed(value) => write!(f, "`{}` is not a number", value),
+            FitError::UnknownTarget(target) => write!(f, "the model has no {}", target),
+            FitError::Parameter(error) => write!(f, "{}", error),
+            FitError::Bounds(path) => write!(f, "parameter `{}` has invalid bounds", path),
+            FitError::NoObservations => write!(f, "there are no observations to fit to"),
+            FitError::Tolerance(tolerance) => write!(
+                f,
+                "too few simulations came within {} of the observations",
+                tolerance
+            ),
+        }
+    }
+}
+
+impl Error for FitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FitError::Io(error) => Some(error),
+            FitError::Parameter(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FitError {
+    fn from(error: io::Error) -> Self {
+        FitError::Io(error)
+    }
+}
+
+impl From<csv::Error> for FitError {
+    fn from(error: csv::Error) -> Self {
+        FitError::Io(error.into())
+    }
+}
+
+impl From<ParameterError> for FitError {
+    fn from(error: ParameterError) -> Self {
+        FitError::Parameter(error)
+    }
+}
+
+/// A time series observed from the real epidemic a model describes, such
+/// as daily reported cases, to fit the model to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observations {
+    target: Target,
+    times: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl Observations {
+    /// Creates observations of `target` from `(time, value)` points, with
+    /// times in ticks on the model's clock.
+    pub fn new(target: Target, points: Vec<(f64, f64)>) -> Observations {
+        let (times, values) = points.into_iter().unzip();
+        Observations {
+            target,
+            times,
+            values,
+        }
+    }
+    /// Reads observations of `target` from the CSV file at `path`, taking
+    /// times from its `time` column and values from the column named
+    /// `column`. See [`read_csv`](Observations::read_csv).
+    pub fn from_csv(
+        target: Target,
+        path: impl AsRef<Path>,
+        column: &str,
+    ) -> Result<Observations, FitError> {
+        Observations::read_csv(target, File::open(path)?, column)
+    }
+    /// Reads observations of `target` from CSV with a header row, taking
+    /// times from its `time` column and values from the column named
+    /// `column`. Rows with an empty value, such as days nothing was
+    /// reported, are skipped.
+    pub fn read_csv<R: Read>(
+        target: Target,
+        reader: R,
+        column: &str,
+    ) -> Result<Observations, FitError> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+        let index = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.trim() == name)
+                .ok_or_else(|| FitError::MissingColumn(name.to_owned()))
+        };
+        let (time, value) = (index("time")?, index(column)?);
+        let number = |text: &str| {
+            text.trim()
+                .parse::<f64>()
+                .map_err(|_| FitError::Malformed(text.to_owned()))
+        };
+        let mut points = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let (time, value) = (&record[time], &record[value]);
+            if !value.trim().is_empty() {
+                points.push((number(time)?, number(value)?));
+            }
+        }
+        Ok(Observations::new(target, points))
+    }
+    /// Returns what was observed.
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+    /// Returns the time of every observation.
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+    /// Returns the observed values.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+    /// Returns the number of observations.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+    /// Returns true if there are no observations.
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+    /// Runs `model` from its current state past the last observation and
+    /// returns what it predicts at the time of every observation.
+    pub fn predict(&self, model: &mut Model) -> Result<Vec<f64>, FitError> {
+        let end = self.times.iter().copied().fold(model.time(), f64::max);
+        let ticks = (end - model.time()).ceil() as u64;
+        let unknown = || FitError::UnknownTarget(self.target.clone());
+        let (result, name) = match &self.target {
+            Target::Prevalence(name) => {
+                model.bucket(name).ok_or_else(unknown)?;
+                (model.simulate(ticks), name)
+            }
+            Target::Incidence(path) => {
+                if !model.flows().iter().any(|(flow, _)| flow == path) {
+                    return Err(unknown());
+                }
+                (model.simulate_flows(ticks).1, path)
+            }
+        };
+        Ok(self
+            .times
+            .iter()
+            .map(|time| result.value_at(name, *time).unwrap_or(f64::NAN))
+            .collect())
+    }
+}
+
+/// The best fit of a model's parameters to observations, found by
+/// [`Calibration::fit`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fit {
+    /// The path and fitted value of every free parameter.
+    pub parameters: Vec<(String, f64)>,
+    /// The observed minus the predicted value at every observation, with
+    /// one list for each set of observations.
+    pub residuals: Vec<Vec<f64>>,
+    /// The sum of squared residuals.
+    pub sse: f64,
+    /// How many points in parameter space the search evaluated.
+    pub evaluations: usize,
+    /// Whether the search converged, rather than running out of
+    /// evaluations.
+    pub converged: bool,
+}
+
+impl Fit {
+    /// Returns the fitted value of the parameter at `path`.
+    pub fn get(&self, path: &str) -> Option<f64> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter == path)
+            .map(|(_, value)| *value)
+    }
+    /// Returns the root mean squared residual.
+    pub fn rmse(&self) -> f64 {
+        let count: usize = self.residuals.iter().map(Vec::len).sum();
+        (self.sse / count as f64).sqrt()
+    }
+}
+
+impl Display for Fit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self
+            .parameters
+            .iter()
+            .map(|(path, _)| path.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (path, value) in &self.parameters {
+            writeln!(f, "{:<width$}  {:.6}", path, value, width = width)?;
+        }
+        writeln!(f, "SSE: {:.4e} (RMSE {:.4e})", self.sse, self.rmse())?;
+        write!(f, "Evaluations: {}", self.evaluations)?;
+        if !self.converged {
+            write!(f, " (did not converge)")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// The fitting of a model's parameters to observations by least squares.
+///
+/// Free parameters are addressed by their
+/// [`path`](crate::Parameter::path), each searched for within bounds, and
+/// the sum of squared differences between the observations and the model's
+/// predictions is minimized by the Nelder-Mead simplex method. Every
+/// evaluation runs a fresh model built by `build`, so the search isn't
+/// disturbed by the state of earlier runs.
+///
+/// ```no_run
+/// # use epidemic::{Calibration, Model, Observations, Target};
+/// # fn build() -> Model { unimplemented!() }
+/// let cases = Observations::from_csv(
+///     Target::Incidence("Susceptible.infection".to_owned()),
+///     "cases.csv",
+///     "cases",
+/// )
+/// .unwrap();
+/// let fit = Calibration::new(build, vec![cases])
+///     .parameter("Susceptible.infection.beta", 0.05, 2.0)
+///     .fit()
+///     .unwrap();
+/// println!("{}", fit);
+/// ```
+pub struct Calibration<F> {
+    build: F,
+    observations: Vec<Observations>,
+    parameters: Vec<(String, f64, f64)>,
+    tolerance: f64,
+    max_evaluations: usize,
+}
+
+impl<F: Fn() -> Model> Calibration<F> {
+    /// Creates a calibration of the models `build` makes to every set of
+    /// `observations`, with no free parameters yet.
+    pub fn new(build: F, observations: Vec<Observations>) -> Calibration<F> {
+        Calibration {
+            build,
+            observations,
+            parameters: Vec::new(),
+            tolerance: 1e-8,
+            max_evaluations: 2000,
+        }
+    }
+    /// Frees the parameter at `path` to be fitted between `low` and `high`.
+    /// The search starts from the value the built model gives it, moved
+    /// into the bounds if it lies outside them.
+    pub fn parameter(mut self, path: &str, low: f64, high: f64) -> Calibration<F> {
+        self.parameters.push((path.to_owned(), low, high));
+        self
+    }
+    /// Sets the relative difference in squared error between the best and
+    /// worst points of the search at which it stops. Defaults to `1e-8`.
+    pub fn tolerance(mut self, tolerance: f64) -> Calibration<F> {
+        self.tolerance = tolerance;
+        self
+    }
+    /// Sets the most times the model is run before the search gives up.
+    /// Defaults to 2000.
+    pub fn max_evaluations(mut self, max_evaluations: usize) -> Calibration<F> {
+        self.max_evaluations = max_evaluations;
+        self
+    }
+    /// Returns the path of every free parameter.
+    pub fn paths(&self) -> Vec<&str> {
+        self.parameters
+            .iter()
+            .map(|(path, _, _)| path.as_str())
+            .collect()
+    }
+    /// Returns the observations fitted to.
+    pub fn observations(&self) -> &[Observations] {
+        &self.observations
+    }
+    /// Builds a model with the free parameters set to `values`, in the
+    /// order they were freed.
+    pub fn model(&self, values: &[f64]) -> Result<Model, FitError> {
+        let mut model = (self.build)();
+        for ((path, _, _), value) in self.parameters.iter().zip(values) {
+            model.set_parameter(path, *value)?;
+        }
+        Ok(model)
+    }
+    /// Runs a model with the free parameters set to `values` and returns
+    /// what it predicts for every set of observations.
+    pub fn predict(&self, values: &[f64]) -> Result<Vec<Vec<f64>>, FitError> {
+        self.observations
+            .iter()
+            .map(|observations| observations.predict(&mut self.model(values)?))
+            .collect()
+    }
+    /// Returns the observed minus the predicted value at every observation
+    /// with the free parameters set to `values`.
+    pub fn residuals(&self, values: &[f64]) -> Result<Vec<Vec<f64>>, FitError> {
+        Ok(self
+            .predict(values)?
+            .iter()
+            .zip(&self.observations)
+            .map(|(predicted, observations)| {
+                observations
+                    .values()
+                    .iter()
+                    .zip(predicted)
+                    .map(|(observed, predicted)| observed - predicted)
+                    .collect()
+            })
+            .collect())
+    }
+    /// Checks the free parameters and observations against a built model,
+    /// returning the value it gives each free parameter.
+    pub(crate) fn check(&self) -> Result<Vec<f64>, FitError> {
+        if self.observations.iter().all(Observations::is_empty) {
+            return Err(FitError::NoObservations);
+        }
+        let model = (self.build)();
+        let known = model.parameters();
+        self.parameters
+            .iter()
+            .map(|(path, low, high)| {
+                if !(low.is_finite() && high.is_finite() && low < high) {
+                    return Err(FitError::Bounds(path.clone()));
+                }
+                let parameter = known
+                    .iter()
+                    .find(|parameter| parameter.path() == *path)
+                    .ok_or_else(|| ParameterError::Unknown(path.clone()))?;
+                Ok(parameter.value.clamp(*low, *high))
+            })
+            .collect()
+    }
+    /// Searches for the values of the free parameters that minimize the sum
+    /// of squared residuals.
+    ///
+    /// The search runs in a space where each parameter's bounds are
+    /// stretched out to infinity, so it never leaves them.
+    pub fn fit(&self) -> Result<Fit, FitError> {
+        let start = self.check()?;
+        self.residuals(&start)?;
+        let (values, minimum) = self.minimize(&start, None);
+        let residuals = self.residuals(&values)?;
+        Ok(Fit {
+            parameters: self
+                .parameters
+                .iter()
+                .map(|(path, _, _)| path.clone())
+                .zip(values)
+                .collect(),
+            sse: residuals.iter().flatten().map(|r| r * r).sum(),
+            residuals,
+            evaluations: minimum.evaluations,
+            converged: minimum.converged,
+        })
+    }
+    /// Profiles the likelihood of every free parameter of `fit` at `points`
+    /// values spread evenly across its bounds, re-fitting the others with
+    /// it held at each, to find which parameters the observations actually
+    /// constrain.
+    ///
+    /// Errors are taken to be independent and normally distributed with an
+    /// unknown variance, so the profile of a parameter rises with the
+    /// squared error as `n ln(SSE / SSE_min)` for `n` observations. Values
+    /// where it stays below the `level` quantile of the chi-squared
+    /// distribution with one degree of freedom form the likelihood-based
+    /// confidence interval, such as a 95% interval for `0.95`. Each re-fit
+    /// starts from the last, walking out from the estimate, and is bounded
+    /// by [`max_evaluations`](Calibration::max_evaluations).
+    pub fn profile(&self, fit: &Fit, points: usize, level: f64) -> Result<Profile, FitError> {
+        self.check()?;
+        let estimate: Vec<f64> = self
+            .paths()
+            .iter()
+            .map(|path| {
+                fit.get(path)
+                    .ok_or_else(|| FitError::from(ParameterError::Unknown((*path).to_owned())))
+            })
+            .collect::<Result<_, _>>()?;
+        let observations: usize = self.observations.iter().map(Observations::len).sum();
+        let mut profiles = Vec::with_capacity(self.parameters.len());
+        for (index, (path, low, high)) in self.parameters.iter().enumerate() {
+            let grid: Vec<f64> = (0..points)
+                .map(|point| low + (high - low) * point as f64 / (points.max(2) - 1) as f64)
+                .collect();
+            let mut values = vec![estimate[index]];
+            let mut sse = vec![fit.sse];
+            // Walk outwards from the estimate on each side, so every re-fit
+            // starts close to its optimum.
+            let above = grid.iter().filter(|value| **value > estimate[index]);
+            let below = grid.iter().rev().filter(|value| **value < estimate[index]);
+            for side in [above.collect::<Vec<_>>(), below.collect()] {
+                let mut start = estimate.clone();
+                for value in side {
+                    start[index] = *value;
+                    let (optimum, _) = self.minimize(&start, Some(index));
+                    let residuals = self.residuals(&optimum)?;
+                    values.push(*value);
+                    sse.push(residuals.iter().flatten().map(|r| r * r).sum());
+                    start = optimum;
+                }
+            }
+            let mut order: Vec<usize> = (0..values.len()).collect();
+            order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+            profiles.push(ParameterProfile::new(
+                path.clone(),
+                estimate[index],
+                order.iter().map(|i| values[*i]).collect(),
+                order.iter().map(|i| sse[*i]).collect(),
+            ));
+        }
+        Ok(Profile::new(profiles, observations, level))
+    }
+    /// Minimizes the sum of squared residuals from `start`, holding the
+    /// parameter at index `fixed` at its starting value if there is one,
+    /// and returns the values found.
+    ///
+    /// The search runs in a space where each parameter's bounds are
+    /// stretched out to infinity, so it never leaves them.
+    fn minimize(&self, start: &[f64], fixed: Option<usize>) -> (Vec<f64>, Minimum) {
+        let free: Vec<usize> = (0..start.len()).filter(|i| Some(*i) != fixed).collect();
+        let bounds: Vec<(f64, f64)> = free
+            .iter()
+            .map(|i| (self.parameters[*i].1, self.parameters[*i].2))
+            .collect();
+        let unbounded: Vec<f64> = free
+            .iter()
+            .zip(&bounds)
+            .map(|(i, bounds)| to_unbounded(start[*i], *bounds))
+            .collect();
+        let values = |point: &[f64]| {
+            let mut values = start.to_vec();
+            for (i, value) in free.iter().zip(to_bounded(point, &bounds)) {
+                values[*i] = value;
+            }
+            values
+        };
+        let sse = |point: &[f64]| match self.residuals(&values(point)) {
+            Ok(residuals) => residuals.iter().flatten().map(|r| r * r).sum(),
+            Err(_) => f64::INFINITY,
+        };
+        let minimum = nelder_mead(sse, &unbounded, 1.0, self.tolerance, self.max_evaluations);
+        (values(&minimum.point), minimum)
+    }
+}
+
+/// Maps a value within `(low, high)` onto the whole real line, by the logit
+/// of its position between the bounds.
+fn to_unbounded(value: f64, (low, high): (f64, f64)) -> f64 {
+    let position = ((value - low) / (high - low)).clamp(1e-9, 1.0 - 1e-9);
+    (position / (1.0 - position)).ln()
+}
+
+/// Maps a point on the real line back within the bounds of each parameter.
+fn to_bounded(point: &[f64], bounds: &[(f64, f64)]) -> Vec<f64> {
+    point
+        .iter()
+        .zip(bounds)
+        .map(|(x, (low, high))| low + (high - low) / (1.0 + (-x).exp()))
+        .collect()
+}
@@ -0,0 +1,95 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Add, Neg, Sub};
+
+/// A signed 32.32 fixed-point number, with 32 bits either side of the
+/// binary point.
+///
+/// Conversions from floating point round to the nearest representable
+/// value, with ties going to the even one, and saturate at the ends of the
+/// range, so the same float always gives the same fixed-point value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    /// The number of bits after the binary point.
+    pub const FRACTIONAL_BITS: u32 = 32;
+    /// The smallest representable positive value, `2^-32`.
+    pub const EPSILON: Fixed = Fixed(1);
+    /// The largest representable value, just under `2^31`.
+    pub const MAX: Fixed = Fixed(i64::MAX);
+    /// The smallest representable value, `-2^31`.
+    pub const MIN: Fixed = Fixed(i64::MIN);
+
+    const SCALE: f64 = (1u64 << Fixed::FRACTIONAL_BITS) as f64;
+
+    /// Creates a fixed-point number from its raw bits, which are the value
+    /// multiplied by `2^32`.
+    pub fn from_bits(bits: i64) -> Fixed {
+        Fixed(bits)
+    }
+    /// Returns the raw bits of the number, which are the value multiplied
+    /// by `2^32`.
+    pub fn to_bits(self) -> i64 {
+        self.0
+    }
+    /// Returns the nearest fixed-point number to `value`, rounding ties to
+    /// even, saturating at [`MIN`](Fixed::MIN) and [`MAX`](Fixed::MAX) and
+    /// mapping NaN to zero.
+    pub fn from_f64(value: f64) -> Fixed {
+        // Casts from float to integer saturate and map NaN to zero.
+        Fixed((value * Fixed::SCALE).round_ties_even() as i64)
+    }
+    /// Returns the value as a float, exactly if its magnitude is below
+    /// `2^21` and otherwise rounded to the nearest float.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Fixed::SCALE
+    }
+    /// Rounds `value` onto the fixed-point grid, returning it as a float.
+    ///
+    /// Floats of magnitude `2^21` or more are already coarser than the
+    /// grid, and are returned as they are rather than saturating.
+    pub fn quantize(value: f64) -> f64 {
+        if value.abs() >= (1u64 << 21) as f64 {
+            return value;
+        }
+        Fixed::from_f64(value).to_f64()
+    }
+}
+
+impl From<Fixed> for f64 {
+    fn from(value: Fixed) -> f64 {
+        value.to_f64()
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    /// Adds two numbers, saturating at the ends of the range.
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    /// Subtracts two numbers, saturating at the ends of the range.
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.to_f64(), f)
+    }
+}
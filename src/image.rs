@@ -0,0 +1,378 @@
+use crate::raster::Canvas;
+
+use std::io::{self, Write};
+
+/// Bits written least significant first, as both DEFLATE and GIF's LZW
+/// pack them.
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits {
+    /// Appends the low `length` bits of `value`.
+    fn write(&mut self, value: u32, length: u32) {
+        self.buffer |= value << self.count;
+        self.count += length;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+    /// Appends a Huffman code, which DEFLATE packs most significant bit
+    /// first.
+    fn code(&mut self, code: u32, length: u32) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+    /// Pads the last byte with zeros and returns every byte written.
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// The shortest match of each DEFLATE length code, from 257, and how many
+/// extra bits follow it.
+const LENGTHS: [(u16, u32); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// The shortest distance of each DEFLATE distance code and how many extra
+/// bits follow it.
+const DISTANCES: [(u16, u32); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+/// Writes `symbol`, a literal byte, a length code or the end of the block,
+/// in DEFLATE's fixed Huffman code.
+fn symbol(bits: &mut Bits, symbol: u32) {
+    match symbol {
+        0..=143 => bits.code(0x30 + symbol, 8),
+        144..=255 => bits.code(0x190 + symbol - 144, 9),
+        256..=279 => bits.code(symbol - 256, 7),
+        _ => bits.code(0xC0 + symbol - 280, 8),
+    }
+}
+
+/// Writes the last entry of `table` whose base is at most `value`, as code
+/// `first + index` in `write`'s code, followed by its extra bits.
+fn coded(bits: &mut Bits, table: &[(u16, u32)], value: usize, write: impl Fn(&mut Bits, u32)) {
+    let index = table
+        .iter()
+        .rposition(|(base, _)| *base as usize <= value)
+        .expect("in range");
+    let (base, extra) = table[index];
+    write(bits, index as u32);
+    bits.write((value - base as usize) as u32, extra);
+}
+
+/// Compresses `data` as a zlib stream of one DEFLATE block with the fixed
+/// Huffman code.
+///
+/// Matches are only looked for one byte back, which finds runs, and
+/// `stride` bytes back, which finds whatever repeats the row above. Charts
+/// are mostly both, so this does nearly as well as a full search.
+fn zlib(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut bits = Bits::default();
+    // The block is final and uses the fixed code.
+    bits.write(1, 1);
+    bits.write(1, 2);
+    let mut position = 0;
+    while position < data.len() {
+        let longest = (data.len() - position).min(258);
+        let (length, distance) = [1, stride]
+            .iter()
+            .filter(|&&distance| distance > 0 && distance <= position.min(32768))
+            .map(|&distance| {
+                let length = (0..longest)
+                    .take_while(|&i| data[position + i] == data[position + i - distance])
+                    .count();
+                (length, distance)
+            })
+            .max()
+            .unwrap_or((0, 0));
+        if length >= 3 {
+            coded(&mut bits, &LENGTHS, length, |bits, index| {
+                symbol(bits, 257 + index)
+            });
+            coded(&mut bits, &DISTANCES, distance, |bits, index| {
+                bits.code(index, 5)
+            });
+            position += length;
+        } else {
+            symbol(&mut bits, u32::from(data[position]));
+            position += 1;
+        }
+    }
+    symbol(&mut bits, 256);
+    let mut stream = vec![0x78, 0x01];
+    stream.extend(bits.finish());
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Writes a PNG chunk of type `kind`.
+fn chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut body = kind.to_vec();
+    body.extend(data);
+    writer.write_all(&body)?;
+    writer.write_all(&crc32(&body).to_be_bytes())
+}
+
+/// Writes `canvas` as a PNG whose pixels index into `palette`.
+pub(crate) fn write_png<W: Write>(
+    mut writer: W,
+    canvas: &Canvas,
+    palette: &[[u8; 3]],
+) -> io::Result<()> {
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+    let mut header = Vec::new();
+    header.extend((canvas.width as u32).to_be_bytes());
+    header.extend((canvas.height as u32).to_be_bytes());
+    // Eight bits per pixel, indexed colour, the default compression and
+    // filtering, and no interlacing.
+    header.extend([8, 3, 0, 0, 0]);
+    chunk(&mut writer, b"IHDR", &header)?;
+    chunk(&mut writer, b"PLTE", &palette.concat())?;
+    // Every row starts with the filter type, none.
+    let mut rows = Vec::with_capacity((canvas.width + 1) * canvas.height);
+    for row in canvas.pixels.chunks(canvas.width) {
+        rows.push(0);
+        rows.extend(row);
+    }
+    chunk(&mut writer, b"IDAT", &zlib(&rows, canvas.width + 1))?;
+    chunk(&mut writer, b"IEND", &[])
+}
+
+/// The number of bits in the smallest code of GIF's LZW, enough for a
+/// palette of 16 colours.
+const MIN_CODE_SIZE: u32 = 4;
+/// The most codes GIF's LZW can have.
+const MAX_CODES: usize = 4096;
+
+/// Compresses `pixels`, each less than 16, with GIF's variant of LZW.
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u32 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut bits = Bits::default();
+    // The code for each string followed by each colour, zero for none.
+    let mut table = vec![0u16; MAX_CODES << MIN_CODE_SIZE];
+    let mut size = MIN_CODE_SIZE + 1;
+    let mut next = end + 1;
+    bits.write(clear, size);
+    let mut current = match pixels.first() {
+        Some(&pixel) => u32::from(pixel),
+        None => {
+            bits.write(end, size);
+            return bits.finish();
+        }
+    };
+    for &pixel in &pixels[1..] {
+        let slot = ((current as usize) << MIN_CODE_SIZE) | pixel as usize;
+        if table[slot] != 0 {
+            current = u32::from(table[slot]);
+            continue;
+        }
+        bits.write(current, size);
+        table[slot] = next as u16;
+        if next == 1 << size && size < 12 {
+            size += 1;
+        }
+        next += 1;
+        if next as usize == MAX_CODES {
+            bits.write(clear, size);
+            table.iter_mut().for_each(|code| *code = 0);
+            size = MIN_CODE_SIZE + 1;
+            next = end + 1;
+        }
+        current = u32::from(pixel);
+    }
+    bits.write(current, size);
+    bits.write(end, size);
+    bits.finish()
+}
+
+/// Writes the frames of a GIF animation that plays once, storing only the
+/// part of each frame that differs from the one before.
+pub(crate) struct Gif<W: Write> {
+    writer: W,
+    previous: Option<Canvas>,
+}
+
+impl<W: Write> Gif<W> {
+    /// Starts an animation of frames of `width` by `height` pixels whose
+    /// pixels index into `palette`, of at most 16 colours.
+    pub(crate) fn new(
+        mut writer: W,
+        width: usize,
+        height: usize,
+        palette: &[[u8; 3]],
+    ) -> io::Result<Gif<W>> {
+        writer.write_all(b"GIF89a")?;
+        writer.write_all(&(width as u16).to_le_bytes())?;
+        writer.write_all(&(height as u16).to_le_bytes())?;
+        // A global colour table of 16 colours follows.
+        writer.write_all(&[0xF3, 0, 0])?;
+        for index in 0..1 << MIN_CODE_SIZE {
+            writer.write_all(palette.get(index).unwrap_or(&[0, 0, 0]))?;
+        }
+        Ok(Gif {
+            writer,
+            previous: None,
+        })
+    }
+    /// Adds `canvas` as the next frame, shown for `delay` hundredths of a
+    /// second.
+    pub(crate) fn frame(&mut self, canvas: &Canvas, delay: u16) -> io::Result<()> {
+        let (width, height) = (canvas.width, canvas.height);
+        // The smallest rectangle holding every changed pixel, or a single
+        // pixel if nothing changed, since a frame can't be empty.
+        let (mut left, mut top, mut right, mut bottom) = (0, 0, width, height);
+        if let Some(previous) = &self.previous {
+            let changed =
+                |x: usize, y: usize| canvas.pixels[y * width + x] != previous.pixels[y * width + x];
+            let rows: Vec<usize> = (0..height)
+                .filter(|&y| (0..width).any(|x| changed(x, y)))
+                .collect();
+            let columns: Vec<usize> = (0..width)
+                .filter(|&x| rows.iter().any(|&y| changed(x, y)))
+                .collect();
+            match (rows.first(), rows.last(), columns.first(), columns.last()) {
+                (Some(&first), Some(&last), Some(&leftmost), Some(&rightmost)) => {
+                    top = first;
+                    bottom = last + 1;
+                    left = leftmost;
+                    right = rightmost + 1;
+                }
+                _ => {
+                    right = 1;
+                    bottom = 1;
+                }
+            }
+        }
+        // A graphic control extension leaving the frame in place for the
+        // next to be drawn over.
+        self.writer.write_all(&[0x21, 0xF9, 4, 1 << 2])?;
+        self.writer.write_all(&delay.to_le_bytes())?;
+        self.writer.write_all(&[0, 0])?;
+        self.writer.write_all(&[0x2C])?;
+        for value in [left, top, right - left, bottom - top] {
+            self.writer.write_all(&(value as u16).to_le_bytes())?;
+        }
+        self.writer.write_all(&[0, MIN_CODE_SIZE as u8])?;
+        let pixels: Vec<u8> = (top..bottom)
+            .flat_map(|y| {
+                canvas.pixels[y * width + left..y * width + right]
+                    .iter()
+                    .cloned()
+            })
+            .collect();
+        for block in lzw(&pixels).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+        self.writer.write_all(&[0])?;
+        self.previous = Some(canvas.clone());
+        Ok(())
+    }
+    /// Ends the animation.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.writer.write_all(&[0x3B])?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_their_check_values() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
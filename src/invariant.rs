@@ -0,0 +1,133 @@
+use crate::{Bucket, ModelError, State};
+
+/// The relative error within which a sum invariant is taken to hold.
+const SUM_TOLERANCE: f64 = 1e-9;
+
+/// What to do when an invariant stops holding after a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvariantAction {
+    /// Print a warning to stderr and carry on.
+    Log,
+    /// Stop the model. [`Model::try_step`](crate::Model::try_step) returns
+    /// an error, and [`Model::step`](crate::Model::step) panics.
+    Abort,
+    /// Scale the buckets of a [sum](Invariants::sum) so they add up to
+    /// their total again. Predicates have nothing to scale, so their
+    /// violations are logged instead.
+    Renormalize,
+}
+
+enum Check {
+    Predicate(Box<dyn FnMut(&State) -> bool>),
+    Sum { buckets: Vec<String>, total: f64 },
+}
+
+struct Invariant {
+    name: String,
+    check: Check,
+    action: InvariantAction,
+}
+
+/// Conditions that a model checks after every step, to catch mass balance
+/// bugs as soon as they happen.
+#[derive(Default)]
+pub struct Invariants {
+    invariants: Vec<Invariant>,
+}
+
+impl Invariants {
+    /// Adds an invariant called `name` that holds while `check` returns true
+    /// of the state after every step.
+    pub fn add<F>(&mut self, name: &str, check: F, action: InvariantAction) -> &mut Invariants
+    where
+        F: FnMut(&State) -> bool + 'static,
+    {
+        self.invariants.push(Invariant {
+            name: name.to_owned(),
+            check: Check::Predicate(Box::new(check)),
+            action,
+        });
+        self
+    }
+    /// Adds an invariant called `name` that holds while the buckets named
+    /// in `buckets` add up to `total`, as in `S + I + R == N`.
+    pub fn sum(
+        &mut self,
+        name: &str,
+        buckets: &[&str],
+        total: f64,
+        action: InvariantAction,
+    ) -> &mut Invariants {
+        self.invariants.push(Invariant {
+            name: name.to_owned(),
+            check: Check::Sum {
+                buckets: buckets.iter().map(|&bucket| bucket.to_owned()).collect(),
+                total,
+            },
+            action,
+        });
+        self
+    }
+    /// Returns the number of invariants.
+    pub fn len(&self) -> usize {
+        self.invariants.len()
+    }
+    /// Returns true if there are no invariants.
+    pub fn is_empty(&self) -> bool {
+        self.invariants.is_empty()
+    }
+    /// Removes every invariant.
+    pub fn clear(&mut self) {
+        self.invariants.clear();
+    }
+    /// Checks every invariant against `buckets` at `time`, acting on any
+    /// that don't hold, and returns an error for the first that should
+    /// abort the model.
+    pub(crate) fn check(&mut self, time: f64, buckets: &[Bucket]) -> Result<(), ModelError> {
+        if self.invariants.is_empty() {
+            return Ok(());
+        }
+        let state = State::new(time, buckets);
+        for invariant in &mut self.invariants {
+            let holds = match &mut invariant.check {
+                Check::Predicate(check) => check(&state),
+                Check::Sum { buckets, total } => {
+                    let sum: f64 = buckets.iter().filter_map(|name| state.get(name)).sum();
+                    (sum - *total).abs() <= SUM_TOLERANCE * total.abs().max(1.0)
+                }
+            };
+            if holds {
+                continue;
+            }
+            match (invariant.action, &invariant.check) {
+                (InvariantAction::Abort, _) => {
+                    return Err(ModelError::Invariant(invariant.name.clone(), time));
+                }
+                (
+                    InvariantAction::Renormalize,
+                    Check::Sum {
+                        buckets: names,
+                        total,
+                    },
+                ) => {
+                    let members: Vec<&Bucket> = buckets
+                        .iter()
+                        .filter(|bucket| names.contains(&bucket.name()))
+                        .collect();
+                    let sum: f64 = members.iter().map(|bucket| bucket.get()).sum();
+                    if sum > 0.0 {
+                        let scale = total / sum;
+                        members
+                            .iter()
+                            .for_each(|bucket| bucket.set(bucket.get() * scale));
+                    }
+                }
+                _ => eprintln!(
+                    "warning: invariant `{}` does not hold at tick {}",
+                    invariant.name, time
+                ),
+            }
+        }
+        Ok(())
+    }
+}
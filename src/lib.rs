@@ -0,0 +1,156 @@
+//! Compartmental population models.
+//!
+//! A [`Model`] is a collection of [`Bucket`]s, each holding some quantity of
+//! a population. Buckets carry [`Behaviour`]s which move population between
+//! buckets every tick. The [`templates`] module builds the classic models,
+//! such as SIR and SEIR, in a single call, and the [`model!`] macro
+//! defines a model in reaction notation, as
+//! [`ModelBuilder::reaction`] does from equations read at runtime.
+//!
+//! With the `serde` feature, results and [`Metadata`] describing a model
+//! implement `serde::Serialize`, so runs can be archived as JSON or any
+//! other format serde supports.
+//!
+//! Models can also be defined without writing Rust, in a TOML file read by
+//! [`Model::from_file`]; see [`Config`] for the format. The
+//! `compartmentalmodel` binary runs such files from the command line.
+//!
+//! [`Model::reproduction_number`] computes R0, or Rt later in a run, from
+//! the next-generation matrix of a model's own flows, [`Model::final_size`]
+//! the size of the epidemic it implies and [`Model::equilibria`] the
+//! disease-free and endemic states it settles into and their stability,
+//! while [`SimulationResult::summary`]
+//! reports the peaks, attack rate, duration and doubling time of a run.
+//! [`Model::compare`] runs a model under several [`Scenario`]s against its
+//! baseline and tabulates outcomes such as the deaths each averts.
+//!
+//! A [`Sweep`] runs a model in parallel over a grid of parameter values, or
+//! over points a [`ParameterSpace`] draws by Latin hypercube or Sobol
+//! sampling, and [`Sensitivity`] ranks the parameters of a sweep by their
+//! influence on an [`Output`] such as the size of the peak.
+//!
+//! A [`Calibration`] fits a model's parameters to [`Observations`], such as
+//! daily reported cases read from CSV, by least squares, and
+//! [`Calibration::profile`] shows which of them the data actually constrain.
+//! A [`Posterior`] instead samples them by adaptive Metropolis under priors
+//! and a Poisson or negative binomial [`Likelihood`], giving credible
+//! intervals for the parameters and the trajectories they imply. For
+//! stochastic models with no tractable likelihood, [`Abc`] accepts
+//! parameters whose simulations land near the data, by rejection or
+//! sequential Monte Carlo.
+//!
+//! With the `plot` feature, a [`SimulationResult`] can be rendered as an SVG
+//! or PNG chart with `SimulationResult::plot`, or an animated SVG or GIF
+//! with `SimulationResult::animate`, and with the `stream` feature an
+//! `SseReporter` serves a run live to a browser. The `dashboard` feature
+//! adds `Model::serve`, which runs a model from a web page charting it.
+//! The `arrow` feature adds `Ensemble::write_arrow`, which writes the runs
+//! of an ensemble as an Arrow IPC stream for columnar analysis tools.
+
+// Behaviour constructors hand back ready-to-attach `Box<dyn Behaviour>`s.
+#![allow(clippy::new_ret_no_self)]
+
+mod abc;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod behaviour;
+mod bucket;
+mod builder;
+mod checkpoint;
+mod config;
+mod context;
+mod counter;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+mod ensemble;
+mod equation;
+mod equilibrium;
+mod event;
+mod fit;
+mod fixed;
+#[cfg(feature = "plot")]
+mod image;
+mod invariant;
+mod linalg;
+mod macros;
+mod metadata;
+mod model;
+mod optimize;
+mod outbreak;
+mod parameter;
+#[cfg(feature = "plot")]
+mod plot;
+mod posterior;
+mod profile;
+mod progress;
+mod random;
+#[cfg(feature = "plot")]
+mod raster;
+mod reporter;
+mod reproduction;
+mod result;
+mod sampling;
+mod scenario;
+mod schedule;
+mod sensitivity;
+#[cfg(feature = "serde")]
+mod serialize;
+mod solver;
+mod state;
+#[cfg(feature = "stream")]
+mod stream;
+mod summary;
+mod sweep;
+pub mod templates;
+mod terminal;
+mod transfer;
+mod unit;
+mod view;
+
+pub use abc::{Abc, Particles};
+pub use behaviour::{
+    AdaptiveContact, Behaviour, Birth, Branch, Conditional, ContactTracing, Counted, Delayed,
+    Delivery, Diffusion, ForceOfInfection, HospitalOutcome, Hospitalization, Importation,
+    Incubation, Infection, InfectionFatality, Interaction, Isolation, LogisticGrowth,
+    MaternalImmunity, Mixing, NaturalDeath, RateFlow, Reaction, ReedFrost, Reinfection, Renewal,
+    Response, Saturating, Scaled, Seasonal, Spillover, Testing, Treatment, TwoDoseVaccination,
+    Vaccination, VectorBirth, VectorTransmission, Waning,
+};
+pub use bucket::{Bucket, BucketState, QuantityError, SharedBehaviour};
+pub use builder::{
+    BuildError, Compartments, Fatality, FlowKind, Latency, ModelBuilder, Rate, Transmission, Wane,
+};
+pub use checkpoint::CheckpointError;
+pub use config::{Config, ConfigError};
+pub use context::Context;
+pub use counter::Counter;
+pub use ensemble::Ensemble;
+pub use equilibrium::{Eigenvalue, Equilibria, Equilibrium, Stability};
+pub use fit::{Calibration, Fit, FitError, Observations, Target};
+pub use fixed::Fixed;
+pub use invariant::{InvariantAction, Invariants};
+pub use metadata::Metadata;
+pub use model::{Bounds, Conservation, Model, ModelError, Precision, Report, UpdateMode};
+pub use outbreak::Outbreak;
+pub use parameter::{Parameter, ParameterError};
+#[cfg(feature = "plot")]
+pub use plot::Scale;
+pub use posterior::{Chain, Likelihood, Posterior};
+pub use profile::{Identifiability, ParameterProfile, Profile};
+pub use random::Rng;
+pub use reporter::{CsvReporter, NullReporter, Reporter, TableReporter};
+pub use reproduction::ReproductionError;
+pub use result::{SimulationResult, TimeSeries};
+pub use sampling::{Distribution, ParameterSpace, Sobol};
+pub use scenario::{Comparison, Scenario};
+pub use schedule::{Interpolate, Interpolation, Param, Schedule};
+pub use sensitivity::{Output, Sensitivity, SensitivityIndex};
+pub use solver::Solver;
+pub use state::State;
+#[cfg(feature = "stream")]
+pub use stream::SseReporter;
+pub use summary::{CompartmentSummary, Summary};
+pub use sweep::Sweep;
+pub use transfer::Transfer;
+pub use unit::TimeUnit;
+pub use view::{Threshold, View};
@@ -0,0 +1,264 @@
+//! Small dense linear algebra for the analysis routines.
+
+/// Inverts a square matrix by Gauss-Jordan elimination with partial
+/// pivoting, returning `None` if it is singular.
+pub(crate) fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|row| (0..n).map(|column| (row == column) as u8 as f64).collect())
+        .collect();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| {
+            matrix[*a][column]
+                .abs()
+                .total_cmp(&matrix[*b][column].abs())
+        })?;
+        if matrix[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let scale = matrix[column][column];
+        for value in matrix[column].iter_mut().chain(inverse[column].iter_mut()) {
+            *value /= scale;
+        }
+        for row in (0..n).filter(|row| *row != column) {
+            let factor = matrix[row][column];
+            if factor == 0.0 {
+                continue;
+            }
+            for k in 0..n {
+                matrix[row][k] -= factor * matrix[column][k];
+                inverse[row][k] -= factor * inverse[column][k];
+            }
+        }
+    }
+    Some(inverse)
+}
+
+/// Returns the lower triangular Cholesky factor `L` of a symmetric positive
+/// definite matrix, with `L L^T` equal to it, or `None` if it isn't
+/// positive definite.
+pub(crate) fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut lower = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            if i == j {
+                let diagonal = matrix[i][i] - sum;
+                if diagonal.is_nan() || diagonal <= 0.0 {
+                    return None;
+                }
+                lower[i][i] = diagonal.sqrt();
+            } else {
+                lower[i][j] = (matrix[i][j] - sum) / lower[j][j];
+            }
+        }
+    }
+    Some(lower)
+}
+
+/// Returns the eigenvalues of a real square matrix as `(real, imaginary)`
+/// pairs, by reduction to upper Hessenberg form and the shifted QR
+/// algorithm, or `None` if the iteration doesn't converge. Complex
+/// eigenvalues come in conjugate pairs.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn eigenvalues(matrix: &[Vec<f64>]) -> Option<Vec<(f64, f64)>> {
+    let n = matrix.len();
+    // Indexed from one, as in the classic formulation, so that the many
+    // `k - 1`s and `k + 2`s stay readable.
+    let mut a = vec![vec![0.0; n + 1]; n + 1];
+    for (i, row) in matrix.iter().enumerate() {
+        a[i + 1][1..=n].copy_from_slice(row);
+    }
+    hessenberg(&mut a, n);
+    let sign = |a: f64, b: f64| if b >= 0.0 { a.abs() } else { -a.abs() };
+    let mut values = vec![(0.0, 0.0); n + 1];
+    let mut norm = 0.0;
+    for i in 1..=n {
+        for j in (i - 1).max(1)..=n {
+            norm += a[i][j].abs();
+        }
+    }
+    let (mut nn, mut t) = (n, 0.0);
+    let (mut p, mut q, mut r, mut x, mut y, mut z, mut w): (f64, f64, f64, f64, f64, f64, f64);
+    while nn >= 1 {
+        let mut its = 0;
+        loop {
+            // Look for a single small subdiagonal element to split at.
+            let mut l = nn;
+            while l >= 2 {
+                let mut s = a[l - 1][l - 1].abs() + a[l][l].abs();
+                if s == 0.0 {
+                    s = norm;
+                }
+                if a[l][l - 1].abs() + s == s {
+                    a[l][l - 1] = 0.0;
+                    break;
+                }
+                l -= 1;
+            }
+            x = a[nn][nn];
+            if l == nn {
+                // One root found.
+                values[nn] = (x + t, 0.0);
+                nn -= 1;
+            } else {
+                y = a[nn - 1][nn - 1];
+                w = a[nn][nn - 1] * a[nn - 1][nn];
+                if l == nn - 1 {
+                    // Two roots found.
+                    p = 0.5 * (y - x);
+                    q = p * p + w;
+                    z = q.abs().sqrt();
+                    x += t;
+                    if q >= 0.0 {
+                        z = p + sign(z, p);
+                        values[nn - 1] = (x + z, 0.0);
+                        values[nn] = (if z != 0.0 { x - w / z } else { x + z }, 0.0);
+                    } else {
+                        values[nn - 1] = (x + p, -z);
+                        values[nn] = (x + p, z);
+                    }
+                    nn -= 2;
+                } else {
+                    if its == 60 {
+                        return None;
+                    }
+                    if its == 10 || its == 20 {
+                        // An exceptional shift, to break cycles.
+                        t += x;
+                        for i in 1..=nn {
+                            a[i][i] -= x;
+                        }
+                        let s = a[nn][nn - 1].abs() + a[nn - 1][nn - 2].abs();
+                        x = 0.75 * s;
+                        y = x;
+                        w = -0.4375 * s * s;
+                    }
+                    its += 1;
+                    // Form the shift and look for two consecutive small
+                    // subdiagonal elements.
+                    let mut m = nn - 2;
+                    loop {
+                        z = a[m][m];
+                        r = x - z;
+                        let s = y - z;
+                        p = (r * s - w) / a[m + 1][m] + a[m][m + 1];
+                        q = a[m + 1][m + 1] - z - r - s;
+                        r = a[m + 2][m + 1];
+                        let s = p.abs() + q.abs() + r.abs();
+                        p /= s;
+                        q /= s;
+                        r /= s;
+                        if m == l {
+                            break;
+                        }
+                        let u = a[m][m - 1].abs() * (q.abs() + r.abs());
+                        let v = p.abs() * (a[m - 1][m - 1].abs() + z.abs() + a[m + 1][m + 1].abs());
+                        if u + v == v {
+                            break;
+                        }
+                        m -= 1;
+                    }
+                    for i in m + 2..=nn {
+                        a[i][i - 2] = 0.0;
+                        if i != m + 2 {
+                            a[i][i - 3] = 0.0;
+                        }
+                    }
+                    // A double QR step on rows l to nn and columns m to nn.
+                    for k in m..nn {
+                        if k != m {
+                            p = a[k][k - 1];
+                            q = a[k + 1][k - 1];
+                            r = if k != nn - 1 { a[k + 2][k - 1] } else { 0.0 };
+                            x = p.abs() + q.abs() + r.abs();
+                            if x != 0.0 {
+                                p /= x;
+                                q /= x;
+                                r /= x;
+                            }
+                        }
+                        let s = sign((p * p + q * q + r * r).sqrt(), p);
+                        if s == 0.0 {
+                            continue;
+                        }
+                        if k == m {
+                            if l != m {
+                                a[k][k - 1] = -a[k][k - 1];
+                            }
+                        } else {
+                            a[k][k - 1] = -s * x;
+                        }
+                        p += s;
+                        x = p / s;
+                        y = q / s;
+                        z = r / s;
+                        q /= p;
+                        r /= p;
+                        for j in k..=nn {
+                            p = a[k][j] + q * a[k + 1][j];
+                            if k != nn - 1 {
+                                p += r * a[k + 2][j];
+                                a[k + 2][j] -= p * z;
+                            }
+                            a[k + 1][j] -= p * y;
+                            a[k][j] -= p * x;
+                        }
+                        for i in l..=nn.min(k + 3) {
+                            p = x * a[i][k] + y * a[i][k + 1];
+                            if k != nn - 1 {
+                                p += z * a[i][k + 2];
+                                a[i][k + 2] -= p * r;
+                            }
+                            a[i][k + 1] -= p * q;
+                            a[i][k] -= p;
+                        }
+                    }
+                }
+            }
+            if l + 1 >= nn {
+                break;
+            }
+        }
+    }
+    Some(values.split_off(1))
+}
+
+/// Reduces the matrix held in rows and columns `1..=n` of `a` to upper
+/// Hessenberg form with the same eigenvalues, by elimination with
+/// pivoting, zeroing everything below the subdiagonal.
+#[allow(clippy::needless_range_loop)]
+fn hessenberg(a: &mut [Vec<f64>], n: usize) {
+    for m in 2..n {
+        let (mut x, mut i) = (0.0, m);
+        for j in m..=n {
+            if a[j][m - 1].abs() > f64::abs(x) {
+                x = a[j][m - 1];
+                i = j;
+            }
+        }
+        if i != m {
+            a.swap(i, m);
+            for row in a.iter_mut() {
+                row.swap(i, m);
+            }
+        }
+        if x != 0.0 {
+            for i in m + 1..=n {
+                let y = a[i][m - 1] / x;
+                if y != 0.0 {
+                    a[i][m - 1] = 0.0;
+                    for j in m..=n {
+                        a[i][j] -= y * a[m][j];
+                    }
+                    for row in a.iter_mut() {
+                        row[m] += y * row[i];
+                    }
+                }
+            }
+        }
+    }
+}
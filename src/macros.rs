@@ -0,0 +1,64 @@
+/// Defines a model in reaction notation, building it with a
+/// [`ModelBuilder`](crate::ModelBuilder).
+///
+/// The first line names every compartment with its initial quantity,
+/// optionally followed by `=> N` to name the total living population. Each
+/// line after it is a flow from one compartment into another at the rate,
+/// per tick, given after `@`, as in
+///
+/// ```
+/// use epidemic::model;
+///
+/// let (beta, gamma) = (0.3, 0.1);
+/// let model = model! {
+///     S = 999.0, I = 1.0, R = 0.0 => N;
+///     S -> I @ beta * S * I / N;
+///     I -> R @ gamma * I;
+/// }
+/// .unwrap();
+/// ```
+///
+/// Within a rate, every compartment's name stands for its current quantity,
+/// and other names are captured from the surrounding code. Each flow is a
+/// [`RateFlow`](crate::RateFlow), so captured parameters are not visible
+/// to [`Model::parameters`](crate::Model::parameters). The macro evaluates
+/// to the `Result` of [`ModelBuilder::build`](crate::ModelBuilder::build).
+#[macro_export]
+macro_rules! model {
+    (
+        $($name:ident = $initial:expr),+ => $total:ident;
+        $($from:ident -> $to:ident @ $rate:expr);* $(;)?
+    ) => {
+        $crate::model!(@build [$($name = $initial),+] $total; $($from -> $to @ $rate);*)
+    };
+    (
+        $($name:ident = $initial:expr),+;
+        $($from:ident -> $to:ident @ $rate:expr);* $(;)?
+    ) => {
+        $crate::model!(@build [$($name = $initial),+] total; $($from -> $to @ $rate);*)
+    };
+    (
+        @build $compartments:tt $total:ident;
+        $($from:ident -> $to:ident @ $rate:expr);*
+    ) => {
+        $crate::model!(@compartments $compartments)
+            $(.flow(stringify!($from), stringify!($to), move |to: $crate::Bucket| {
+                $crate::RateFlow::new(to, $crate::model!(@rate $compartments $total; $rate))
+            }))*
+            .build()
+    };
+    (@compartments [$($name:ident = $initial:expr),+]) => {
+        $crate::ModelBuilder::new()$(.compartment(stringify!($name), $initial))+
+    };
+    (@rate [$($name:ident = $initial:expr),+] $total:ident; $rate:expr) => {
+        move |state: &$crate::State| {
+            $(
+                #[allow(non_snake_case, unused_variables)]
+                let $name = state[stringify!($name)];
+            )+
+            #[allow(non_snake_case, unused_variables)]
+            let $total = state.total();
+            $rate
+        }
+    };
+}
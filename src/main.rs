@@ -1,187 +1,387 @@
-use prettytable::{Cell, Row, Table};
+//! Runs models defined in TOML files from the command line. See
+//! `epidemic::Config` for the format.
 
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
 
-use std::collections::VecDeque;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use epidemic::{
+    Calibration, Config, Distribution, Ensemble, Model, Observations, ParameterSpace, Report, Rng,
+    Sobol, Sweep, Target, TimeUnit,
+};
 
-use std::ops::{AddAssign, SubAssign};
-
-use std::thread::sleep;
-use std::time::Duration;
+#[derive(Parser)]
+#[command(name = "compartmentalmodel", about = "Runs compartmental models")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-pub trait Behaviour {
-    fn update(&mut self, bucket: Bucket, delta: u64);
+#[derive(Subcommand)]
+enum Command {
+    /// Runs a model once, drawing it in the terminal or writing it as CSV.
+    Run {
+        #[command(flatten)]
+        options: Options,
+        /// Writes the trajectory as CSV instead of drawing it.
+        #[arg(long)]
+        headless: bool,
+        /// Writes the state every this many ticks in headless runs, or
+        /// every such duration, as in `1 week`.
+        #[arg(long, default_value = "1")]
+        every: String,
+        /// Advances this many ticks per frame in terminal runs.
+        #[arg(long, default_value_t = 1)]
+        speed: u64,
+    },
+    /// Runs replicates of a model and writes every run as CSV.
+    Ensemble {
+        #[command(flatten)]
+        options: Options,
+        /// The number of replicates.
+        #[arg(long, default_value_t = 100)]
+        runs: usize,
+        /// The number of threads, or 0 for one per core.
+        #[arg(long, default_value_t = 0)]
+        jobs: usize,
+        /// Writes the runs as an Arrow IPC stream rather than CSV.
+        #[cfg(feature = "arrow")]
+        #[arg(long)]
+        arrow: bool,
+    },
+    /// Runs a model over every combination of parameter values, or over
+    /// points sampled from parameter ranges, and writes the results as
+    /// long-format CSV.
+    Sweep {
+        #[command(flatten)]
+        options: Options,
+        /// A parameter and the values to try, as in
+        /// `S.infection.beta=0.2,0.3,0.4`, or with `--sample` the range to
+        /// sample uniformly, as in `S.infection.beta=0.2..0.4`.
+        #[arg(long = "param", required = true)]
+        parameters: Vec<String>,
+        /// Samples points from the parameter ranges rather than running a
+        /// grid. Latin hypercube sampling is seeded by the model's seed.
+        #[arg(long, value_enum)]
+        sample: Option<Sampling>,
+        /// The number of points to sample.
+        #[arg(long, default_value_t = 100)]
+        samples: usize,
+        /// The number of threads, or 0 for one per core.
+        #[arg(long, default_value_t = 0)]
+        jobs: usize,
+    },
+    /// Fits parameters of a model to observations by least squares and
+    /// prints the fitted values.
+    Fit {
+        #[command(flatten)]
+        options: Options,
+        /// A CSV file of observations, with a `time` column.
+        #[arg(long)]
+        data: PathBuf,
+        /// The column of the observations to fit to.
+        #[arg(long)]
+        column: String,
+        /// The compartment the observations count, as in `Infected`.
+        #[arg(
+            long,
+            conflicts_with = "incidence",
+            required_unless_present = "incidence"
+        )]
+        compartment: Option<String>,
+        /// The behaviour whose flow per tick the observations count, as a
+        /// `bucket.behaviour` path, as in `Susceptible.infection` for new
+        /// cases.
+        #[arg(long)]
+        incidence: Option<String>,
+        /// A parameter to fit and the range to search, as in
+        /// `S.infection.beta=0.05..2`.
+        #[arg(long = "param", required = true)]
+        parameters: Vec<String>,
+        /// Profiles the likelihood of every fitted parameter at this many
+        /// values across its range, reporting 95% confidence intervals and
+        /// which parameters the data leave unconstrained.
+        #[arg(long, value_name = "POINTS")]
+        profile: Option<usize>,
+    },
+    /// Runs a model once and draws its trajectory as a chart, a PNG image if
+    /// the output ends in `.png` and SVG otherwise.
+    #[cfg(feature = "plot")]
+    Plot {
+        #[command(flatten)]
+        options: Options,
+        /// Draws values on a log scale.
+        #[arg(long)]
+        log: bool,
+    },
 }
 
-#[derive(Default)]
-pub struct BucketState {
-    name: String,
-    quantity: u64,
-    behaviours: Vec<Rc<RefCell<Box<dyn Behaviour>>>>,
+/// A way of sampling points from parameter ranges.
+#[derive(Clone, Copy, ValueEnum)]
+enum Sampling {
+    /// Latin hypercube sampling.
+    Lhs,
+    /// A Sobol sequence.
+    Sobol,
 }
 
-#[derive(Clone, Default)]
-pub struct Bucket {
-    state: Rc<RefCell<BucketState>>,
+/// Options every subcommand takes.
+#[derive(Args)]
+struct Options {
+    /// The model definition file.
+    model: PathBuf,
+    /// A scenario file to layer over the definition. Scenarios are layered
+    /// in the order given.
+    #[arg(long = "scenario")]
+    scenarios: Vec<PathBuf>,
+    /// A value for the `${NAME}` placeholders of the definition and
+    /// scenarios, as in `BETA=0.3`, taking precedence over the environment.
+    #[arg(long = "var", value_parser = variable)]
+    variables: Vec<(String, String)>,
+    /// The number of ticks to run for, or a duration to run for in the
+    /// model's time unit, as in `52 weeks`.
+    #[arg(long, default_value = "100")]
+    ticks: String,
+    /// The length of each step in ticks, or as a duration, as in
+    /// `6 hours`, overriding the definition.
+    #[arg(long)]
+    dt: Option<String>,
+    /// The seed of the random number generator, overriding the definition.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// The file to write to, rather than stdout.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
 }
 
-impl Bucket {
-    fn new(name: &'_ str) -> Bucket {
-        Bucket::default().with_name(name)
-    }
-    fn update(&mut self, ticks: u64) {
-        let bs = { self.state.borrow_mut().behaviours.clone() };
-        bs.iter()
-            .for_each(|bs| bs.borrow_mut().update(self.clone(), ticks));
-    }
-    fn set_name(&mut self, name: &'_ str) {
-        self.state.borrow_mut().name = name.to_owned();
-    }
-    fn with_name(self, name: &'_ str) -> Self {
-        self.state.borrow_mut().name = name.to_owned();
-        self
-    }
-    fn get(&self) -> u64 {
-        self.state.borrow().quantity
+impl Options {
+    fn model(&self) -> Result<Model, Box<dyn Error>> {
+        let variables: Vec<(&str, &str)> = self
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        let mut config = Config::from_file_with(&self.model, &variables)?;
+        for scenario in &self.scenarios {
+            config.overlay(&Config::from_file_with(scenario, &variables)?);
+        }
+        let mut model = config.build()?;
+        if let Some(dt) = &self.dt {
+            model.set_dt(duration(dt, &model)?);
+        }
+        if let Some(seed) = self.seed {
+            model.set_seed(seed);
+        }
+        Ok(model)
     }
-    fn name(&self) -> String {
-        self.state.borrow().name.clone()
+    /// Returns the number of ticks to run the model for.
+    fn ticks(&self, model: &Model) -> Result<u64, String> {
+        Ok(duration(&self.ticks, model)?.round() as u64)
     }
-    fn add(&mut self, behaviour: Box<dyn Behaviour>) {
-        self.state
-            .borrow_mut()
-            .behaviours
-            .push(Rc::new(RefCell::new(behaviour)));
+    fn output(&self) -> io::Result<Box<dyn Write>> {
+        Ok(match &self.output {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout()),
+        })
     }
 }
 
-impl<T> AddAssign<T> for Bucket
-where
-    T: Into<i64>,
-{
-    fn add_assign(&mut self, rhs: T) {
-        self.state.borrow_mut().quantity += rhs.into() as u64;
-    }
+/// Parses a number of ticks, or a duration in any unit for a model that
+/// declares its time unit.
+fn duration(text: &str, model: &Model) -> Result<f64, String> {
+    TimeUnit::parse_duration(text, model.time_unit())
+        .filter(|ticks| *ticks > 0.0)
+        .ok_or_else(|| match model.time_unit() {
+            Some(_) => format!("`{}` is not a positive duration", text),
+            None => format!(
+                "`{}` is not a positive number of ticks, and the model declares no time unit",
+                text
+            ),
+        })
 }
 
-impl<T> SubAssign<T> for Bucket
-where
-    T: Into<i64>,
-{
-    fn sub_assign(&mut self, rhs: T) {
-        self.state.borrow_mut().quantity -= rhs.into() as u64;
-    }
+/// Parses a `NAME=value` variable.
+fn variable(text: &str) -> Result<(String, String), String> {
+    text.split_once('=')
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .ok_or_else(|| format!("expected `NAME=value`, found `{}`", text))
 }
 
-pub struct Diffusion {
-    target: Bucket,
-    probability: f32,
+fn main() {
+    if let Err(error) = run(Cli::parse().command) {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
 }
 
-impl Behaviour for Diffusion {
-    fn update(&mut self, bucket: Bucket, delta: u64) {
-        let c = bucket.get();
-        let to_move = ((self.probability * c as f32).round() as u64 * delta) as i32;
-        if c as i32 - to_move > 0 {
-            self.target += to_move;
-            let mut bucket = bucket;
-            bucket -= to_move;
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Run {
+            options,
+            headless,
+            every,
+            speed,
+        } => {
+            let mut model = options.model()?;
+            if !headless {
+                return Ok(model.run(speed)?);
+            }
+            let (ticks, every) = (options.ticks(&model)?, duration(&every, &model)?);
+            match &options.output {
+                Some(path) => {
+                    model.validate()?;
+                    model.simulate(ticks).uniform(every).to_csv(path)?
+                }
+                None => {
+                    let every = (every.round() as u64).max(1);
+                    model.run_headless(ticks, Report::Every(every))?
+                }
+            }
+        }
+        Command::Ensemble {
+            options,
+            runs,
+            jobs,
+            #[cfg(feature = "arrow")]
+            arrow,
+        } => {
+            let model = options.model()?;
+            model.validate()?;
+            let ticks = options.ticks(&model)?;
+            let build = || options.model().expect("model definition was read before");
+            let ensemble = Ensemble::parallel(build, runs, ticks, model.seed(), jobs);
+            #[cfg(feature = "arrow")]
+            if arrow {
+                ensemble.write_arrow(options.output()?)?;
+                return Ok(());
+            }
+            ensemble.write_csv(options.output()?)?;
+        }
+        Command::Sweep {
+            options,
+            parameters,
+            sample,
+            samples,
+            jobs,
+        } => match sample {
+            Some(sampling) => sample_sweep(&options, &parameters, sampling, samples, jobs)?,
+            None => sweep(&options, &parameters, jobs)?,
+        },
+        Command::Fit {
+            options,
+            data,
+            column,
+            compartment,
+            incidence,
+            parameters,
+            profile,
+        } => {
+            let target = match (compartment, incidence) {
+                (Some(compartment), _) => Target::Prevalence(compartment),
+                (None, Some(incidence)) => Target::Incidence(incidence),
+                (None, None) => unreachable!("clap requires a target"),
+            };
+            let observations = Observations::from_csv(target, data, &column)?;
+            options.model()?.validate()?;
+            let build = || options.model().expect("model definition was read before");
+            let mut calibration = Calibration::new(build, vec![observations]);
+            for parameter in &parameters {
+                let (path, bounds) = split_parameter(parameter)?;
+                let (low, high) = range(bounds)?;
+                calibration = calibration.parameter(path, low, high);
+            }
+            let fit = calibration.fit()?;
+            let mut output = options.output()?;
+            write!(output, "{}", fit)?;
+            if let Some(points) = profile {
+                writeln!(output)?;
+                write!(output, "{}", calibration.profile(&fit, points, 0.95)?)?;
+            }
+        }
+        #[cfg(feature = "plot")]
+        Command::Plot { options, log } => {
+            let mut model = options.model()?;
+            model.validate()?;
+            let scale = if log {
+                epidemic::Scale::Log
+            } else {
+                epidemic::Scale::Linear
+            };
+            let result = model.simulate(options.ticks(&model)?);
+            let path = options.output.unwrap_or_else(|| PathBuf::from("plot.svg"));
+            result.plot(path, scale)?;
         }
     }
+    Ok(())
 }
 
-impl Diffusion {
-    fn new(target: Bucket, probability: f32) -> Box<dyn Behaviour> {
-        Box::new(Diffusion {
-            target,
-            probability,
-        })
+/// Runs the model once for every combination of the values given to each
+/// `path=value,value` parameter, writing the runs as long-format CSV.
+fn sweep(options: &Options, parameters: &[String], jobs: usize) -> Result<(), Box<dyn Error>> {
+    let mut grid: Vec<(&str, Vec<f64>)> = Vec::new();
+    for parameter in parameters {
+        let (path, values) = split_parameter(parameter)?;
+        let values = values
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        grid.push((path, values));
     }
+    let model = options.model()?;
+    model.validate()?;
+    let ticks = options.ticks(&model)?;
+    let build = || options.model().expect("model definition was read before");
+    Sweep::grid(build, &grid, ticks, jobs)?.write_csv(options.output()?)?;
+    Ok(())
 }
 
-pub struct Infection {
-    target: Bucket,
-    probability: f32,
-}
-
-impl Behaviour for Infection {
-    fn update(&mut self, bucket: Bucket, delta: u64) {
-        let to_move = ((self.probability * self.target.get() as f32).round() as u64 * delta) as i32;
-        if self.target.get() as i32 - to_move > 0 {
-            self.target += to_move;
-            let mut bucket = bucket;
-            bucket -= to_move;
-        }
+/// Runs the model at `samples` points drawn from the `path=low..high`
+/// range of each parameter, writing the runs as long-format CSV.
+fn sample_sweep(
+    options: &Options,
+    parameters: &[String],
+    sampling: Sampling,
+    samples: usize,
+    jobs: usize,
+) -> Result<(), Box<dyn Error>> {
+    let mut space = ParameterSpace::new();
+    for parameter in parameters {
+        let (path, range) = split_parameter(parameter)?;
+        let (low, high) = self::range(range)?;
+        space = space.parameter(path, Distribution::Uniform(low, high));
     }
-}
-
-impl Infection {
-    fn new(target: Bucket, probability: f32) -> Box<dyn Behaviour> {
-        Box::new(Diffusion {
-            target,
-            probability,
-        })
+    if space.len() > Sobol::MAX_DIMENSIONS {
+        return Err(format!(
+            "at most {} parameters can be sampled",
+            Sobol::MAX_DIMENSIONS
+        )
+        .into());
     }
+    let model = options.model()?;
+    model.validate()?;
+    let points = match sampling {
+        Sampling::Lhs => space.latin_hypercube(samples, &mut Rng::new(model.seed())),
+        Sampling::Sobol => space.sobol(samples),
+    };
+    let ticks = options.ticks(&model)?;
+    let build = || options.model().expect("model definition was read before");
+    Sweep::list(build, &space.paths(), points, ticks, jobs)?.write_csv(options.output()?)?;
+    Ok(())
 }
 
-#[derive(Default)]
-pub struct Model {
-    buckets: Vec<Bucket>,
-}
-
-impl Model {
-    fn new() -> Model {
-        Model::default()
-    }
-    fn run(&mut self, speed: u64) {
-        let names = self
-            .buckets
-            .iter()
-            .map(|bucket| Cell::new(&bucket.name()))
-            .collect::<Vec<Cell>>();
-
-        let mut simulated: VecDeque<Vec<Cell>> = VecDeque::new();
-
-        loop {
-            let mut table = Table::new();
-            table.add_row(Row::new(names.clone()));
-            simulated.push_front(
-                self.buckets
-                    .iter()
-                    .map(|bucket| Cell::new(&format!("{}", bucket.get())))
-                    .collect(),
-            );
-            simulated.truncate(10);
-            simulated.iter().for_each(|row| {
-                table.add_row(Row::new(row.clone()));
-            });
-            table.printstd();
-            print!("{}[2J", 27 as char);
-            self.buckets
-                .iter_mut()
-                .for_each(|bucket| bucket.update(speed));
-            sleep(Duration::from_millis(100));
-        }
-    }
-    fn add(&mut self, bucket: Bucket) {
-        self.buckets.push(bucket);
-    }
+/// Splits a `path=values` parameter argument.
+fn split_parameter(parameter: &str) -> Result<(&str, &str), String> {
+    parameter
+        .split_once('=')
+        .ok_or_else(|| format!("expected `path=values`, found `{}`", parameter))
 }
 
-fn main() {
-    let mut model = Model::new();
-    let mut s = Bucket::new("Susceptible");
-    let mut i = Bucket::new("Infected");
-    let mut r = Bucket::new("Recovered");
-    let infection = Infection::new(i.clone(), 0.01);
-    let recovery = Diffusion::new(r.clone(), 0.2);
-    s.add(infection);
-    i.add(recovery);
-    s += 1000;
-    i += 1;
-    model.add(s);
-    model.add(i);
-    model.add(r);
-    model.run(1);
+/// Parses a `low..high` range.
+fn range(text: &str) -> Result<(f64, f64), Box<dyn Error>> {
+    let (low, high) = text
+        .split_once("..")
+        .ok_or_else(|| format!("expected a range `low..high`, found `{}`", text))?;
+    Ok((low.trim().parse()?, high.trim().parse()?))
 }
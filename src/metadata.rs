@@ -0,0 +1,24 @@
+use crate::{Parameter, Solver, TimeUnit};
+
+/// A description of a model and how it is being run, for archiving
+/// alongside its results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    /// The seed the model's random number generator was last given.
+    pub seed: u64,
+    /// The length of the model's time step, in ticks.
+    pub dt: f64,
+    /// The unit of time a tick stands for, if the model declares one.
+    pub time_unit: Option<TimeUnit>,
+    /// How the model integrates its flows.
+    pub solver: Solver,
+    /// The number of ticks the model has been advanced by.
+    pub time: f64,
+    /// The name and current quantity of every bucket, in order.
+    pub buckets: Vec<(String, f64)>,
+    /// Every tunable parameter of every behaviour.
+    pub parameters: Vec<Parameter>,
+    /// The TOML definition the model was built from, with any scenarios
+    /// layered over it, if it was built from a [`Config`](crate::Config).
+    pub definition: Option<String>,
+}
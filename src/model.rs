@@ -0,0 +1,1748 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+#[cfg(feature = "dashboard")]
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::slice;
+
+use std::time::{Duration, Instant};
+
+use crate::checkpoint::Checkpoint;
+use crate::context::Shared;
+#[cfg(feature = "dashboard")]
+use crate::dashboard;
+use crate::equilibrium::Flows;
+use crate::event::EventQueue;
+use crate::progress::Progress;
+use crate::reproduction;
+use crate::solver;
+use crate::templates;
+use crate::terminal::{Key, Screen};
+use crate::view::{self, Frame};
+use crate::{
+    Bucket, CheckpointError, Comparison, Config, ConfigError, Context, CsvReporter, Ensemble,
+    Equilibria, Fixed, Invariants, Metadata, ModelBuilder, Outbreak, Param, Parameter,
+    ParameterError, QuantityError, Reporter, ReproductionError, Rng, Scenario, SharedBehaviour,
+    SimulationResult, Solver, State, Threshold, TimeSeries, TimeUnit, Transfer, View,
+};
+
+/// The relative change in total population tolerated by conservation
+/// checks, to allow for floating point error.
+const CONSERVATION_TOLERANCE: f64 = 1e-9;
+
+/// How long [`Model::run`] waits between frames by default.
+const DEFAULT_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many times at most [`Model::run_headless`] stops to update its
+/// progress bar.
+const PROGRESS_UPDATES: u64 = 1000;
+
+/// The shortest and longest frame intervals [`Model::run`] can be sped up
+/// or slowed down to from the keyboard.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(1);
+const MAX_FRAME_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The number of steps of incidence each bucket keeps by default.
+const DEFAULT_INCIDENCE_HISTORY: usize = 365;
+
+/// Parameters that are probabilities, and so must lie in `[0, 1]`.
+const PROBABILITIES: [&str; 8] = [
+    "probability",
+    "p",
+    "coverage",
+    "efficacy",
+    "sensitivity",
+    "fatality",
+    "strained_fatality",
+    "reduction",
+];
+
+/// Parameters that may meaningfully be negative.
+const SIGNED: [&str; 1] = ["phase"];
+
+/// A problem with a model that would make its dynamics meaningless.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModelError {
+    /// A bucket has an empty name.
+    EmptyName,
+    /// A bucket holds a negative or non-finite quantity.
+    Quantity(QuantityError),
+    /// The parameter at the given path is infinite or NaN.
+    NotFinite(String, f64),
+    /// The parameter at the given path is negative.
+    Negative(String, f64),
+    /// The invariant with the given name stopped holding at the given time.
+    Invariant(String, f64),
+    /// The parameter at the given path is a probability above 1, or a rate
+    /// that gives a probability above 1 over a single step.
+    NotProbability(String, f64),
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::EmptyName => write!(f, "a bucket has an empty name"),
+            ModelError::Quantity(error) => write!(f, "{}", error),
+            ModelError::NotFinite(path, value) => {
+                write!(f, "parameter `{}` is {}", path, value)
+            }
+            ModelError::Negative(path, value) => {
+                write!(f, "parameter `{}` is negative ({})", path, value)
+            }
+            ModelError::Invariant(name, time) => {
+                write!(f, "invariant `{}` does not hold at tick {}", name, time)
+            }
+            ModelError::NotProbability(path, value) => {
+                write!(
+                    f,
+                    "parameter `{}` is a probability above 1 ({})",
+                    path, value
+                )
+            }
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+/// What to do when a step changes the total population of a model by more
+/// than the births and deaths within it account for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Conservation {
+    /// Don't check the total population.
+    #[default]
+    Ignore,
+    /// Print a warning to stderr.
+    Log,
+    /// Panic.
+    Panic,
+}
+
+/// What to do when a step leaves a bucket holding a negative or non-finite
+/// quantity.
+///
+/// Every policy but `Ignore` records the violation in the model's
+/// [diagnostics](Model::diagnostics).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bounds {
+    /// Don't check quantities.
+    #[default]
+    Ignore,
+    /// Clamp negative and NaN quantities to zero.
+    Clamp,
+    /// Leave the quantity as it is.
+    Report,
+    /// Panic.
+    Panic,
+}
+
+/// How bucket quantities are represented between steps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// Quantities are kept as they are computed, to full floating point
+    /// precision.
+    #[default]
+    Float,
+    /// Quantities are rounded onto the 32.32 [`Fixed`] grid after every
+    /// step, ties to even.
+    ///
+    /// Floating point arithmetic is exactly specified, but functions such
+    /// as `exp` and `powf` can differ in their last bit between platforms
+    /// and compilers. Rounding every step absorbs those differences before
+    /// they can compound, so runs agree bit for bit unless a difference
+    /// happens to straddle a rounding boundary. Quantities of `2^21` or
+    /// more are coarser than the grid already, and are left as they are.
+    Fixed,
+}
+
+/// How the behaviours of a model see each other's effects within a step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UpdateMode {
+    /// Every behaviour is computed from the state at the start of the step,
+    /// and all transfers are applied together at the end of it.
+    #[default]
+    Synchronous,
+    /// Behaviours are run one after another in bucket order, each applying
+    /// its transfers before the next is computed.
+    Sequential,
+}
+
+/// How often [`Model::run_headless`] prints the state of the model, or
+/// [`Model::run_with`] reports it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Report {
+    /// Print the state at the start, every `n` ticks, and at the end.
+    Every(u64),
+    /// Print the state at the end only.
+    End,
+}
+
+/// A collection of buckets that are updated together.
+pub struct Model {
+    buckets: Vec<Bucket>,
+    time: f64,
+    dt: f64,
+    conservation: Conservation,
+    bounds: Bounds,
+    precision: Precision,
+    diagnostics: Vec<(f64, QuantityError)>,
+    update_mode: UpdateMode,
+    solver: Solver,
+    rng: RefCell<Rng>,
+    seed: u64,
+    events: RefCell<EventQueue>,
+    scheduled: RefCell<EventQueue>,
+    step_size: f64,
+    incidence_history: usize,
+    frame_interval: Duration,
+    view: View,
+    thresholds: Vec<(String, Threshold)>,
+    normalized: bool,
+    trace: bool,
+    history: VecDeque<(f64, Vec<f64>)>,
+    history_limit: Option<usize>,
+    invariants: Invariants,
+    aborted: Option<ModelError>,
+    definition: Option<String>,
+    time_unit: Option<TimeUnit>,
+}
+
+impl Default for Model {
+    fn default() -> Model {
+        Model {
+            buckets: Vec::new(),
+            time: 0.0,
+            dt: 1.0,
+            conservation: Conservation::default(),
+            bounds: Bounds::default(),
+            precision: Precision::default(),
+            diagnostics: Vec::new(),
+            update_mode: UpdateMode::default(),
+            solver: Solver::default(),
+            rng: RefCell::new(Rng::new(0)),
+            seed: 0,
+            events: RefCell::default(),
+            scheduled: RefCell::default(),
+            step_size: 1.0,
+            incidence_history: DEFAULT_INCIDENCE_HISTORY,
+            frame_interval: DEFAULT_FRAME_INTERVAL,
+            view: View::default(),
+            thresholds: Vec::new(),
+            normalized: false,
+            trace: false,
+            history: VecDeque::new(),
+            history_limit: None,
+            invariants: Invariants::default(),
+            aborted: None,
+            definition: None,
+            time_unit: None,
+        }
+    }
+}
+
+impl Model {
+    /// Creates an empty model.
+    pub fn new() -> Model {
+        Model::default()
+    }
+    /// Starts building a model from named compartments and flows.
+    pub fn builder() -> ModelBuilder {
+        ModelBuilder::new()
+    }
+    /// Builds a model from the definition file at `path`, so models can be
+    /// defined without recompiling. See [`Config`] for the format.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Model, ConfigError> {
+        Config::from_file(path)?.build()
+    }
+    /// Builds a model from the definition file at `path` with each of the
+    /// scenario files in `scenarios` layered over it in turn, as by
+    /// [`Config::overlay`]. The effective definition is recorded in the
+    /// model's [`metadata`](Model::metadata).
+    pub fn from_scenarios<P: AsRef<Path>>(
+        path: impl AsRef<Path>,
+        scenarios: &[P],
+    ) -> Result<Model, ConfigError> {
+        let mut config = Config::from_file(path)?;
+        for scenario in scenarios {
+            config.overlay(&Config::from_file(scenario)?);
+        }
+        config.build()
+    }
+    /// Creates an SEIR model of a population of `n`, `i0` of whom are
+    /// initially infectious.
+    ///
+    /// `beta` is the transmission rate, `sigma` the rate at which exposed
+    /// individuals become infectious and `gamma` the recovery rate, all per
+    /// tick. The compartments are named `S`, `E`, `I` and `R`. See
+    /// [`templates`](crate::templates) for other classic models.
+    pub fn seir(beta: f64, sigma: f64, gamma: f64, n: f64, i0: f64) -> Model {
+        templates::seir(beta, sigma, gamma, n, i0)
+    }
+    /// Runs the model until stopped, drawing the most recent ticks in the
+    /// [view](Model::set_view) chosen.
+    ///
+    /// Every frame advances each bucket by `speed` ticks, and frames are
+    /// drawn once every [frame interval](Model::set_frame_interval). Within
+    /// a frame the model takes as many steps of [`dt`](Model::set_dt) as
+    /// the ticks need, so `speed` only sets how fast the display moves, and
+    /// accuracy is set by `dt` alone. The model is
+    /// [validated](Model::validate) first, and nothing is run if that
+    /// fails.
+    ///
+    /// The run stops with an error if an [invariant](Model::invariants) set
+    /// to abort stops holding. Frames are drawn on the terminal's alternate
+    /// screen, and the terminal is restored however the run ends.
+    ///
+    /// When run in a terminal the run can be controlled from the keyboard:
+    ///
+    /// - space pauses and resumes,
+    /// - `n` advances a single frame, paused or not,
+    /// - `+` and `-` halve and double the frame interval,
+    /// - the up and down arrows select a [parameter](Model::parameters),
+    /// - the right and left arrows raise and lower the selected parameter
+    ///   by a tenth of its size, taking effect from the next step, unless
+    ///   that would make the model fail validation,
+    /// - `q` or Ctrl-C stops the run and returns.
+    pub fn run(&mut self, speed: u64) -> Result<(), ModelError> {
+        self.validate()?;
+        let names = self.buckets.iter().map(Bucket::name).collect::<Vec<_>>();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let mut screen = Screen::enter().expect("failed to set up the terminal");
+        let mut interval = self.frame_interval;
+        let mut paused = false;
+        let mut selected = 0;
+        let mut due = Instant::now() + interval;
+        self.record_history();
+        loop {
+            let parameters = self.parameters();
+            selected = selected.min(parameters.len().saturating_sub(1));
+            let thresholds = names
+                .iter()
+                .map(|name| {
+                    self.thresholds
+                        .iter()
+                        .find(|(bucket, _)| bucket == name)
+                        .map(|(_, threshold)| *threshold)
+                })
+                .collect::<Vec<_>>();
+            let mut frame = self.view.render(&Frame {
+                names: &names,
+                history: &self.history,
+                parameters: &parameters,
+                selected,
+                thresholds: &thresholds,
+                normalized: self.normalized,
+            });
+            if let Some(parameter) = parameters.get(selected) {
+                frame.push_str(&format!(
+                    "\n{} = {}  [up/down] select  [left/right] adjust",
+                    parameter.path(),
+                    view::round(parameter.value)
+                ));
+            }
+            frame.push_str(&format!(
+                "\n{} every {:?}  [space] {}  [n] step  [+/-] speed  [q] quit",
+                if paused { "paused" } else { "running" },
+                interval,
+                if paused { "resume" } else { "pause" },
+            ));
+            screen.draw(&frame).expect("failed to draw to the terminal");
+            let key = screen.key(due.saturating_duration_since(Instant::now()));
+            if screen.interrupted() {
+                break;
+            }
+            match key {
+                Some(Key::Char('q')) => break,
+                Some(Key::Char(' ')) => paused = !paused,
+                Some(Key::Char('n')) => self.try_step(speed)?,
+                Some(Key::Char('+')) => interval = (interval / 2).max(MIN_FRAME_INTERVAL),
+                Some(Key::Char('-')) => interval = (interval * 2).min(MAX_FRAME_INTERVAL),
+                Some(Key::Up) => selected = selected.saturating_sub(1),
+                Some(Key::Down) => selected += 1,
+                Some(key @ Key::Left) | Some(key @ Key::Right) => {
+                    if let Some(parameter) = parameters.get(selected) {
+                        let nudge = if parameter.value == 0.0 {
+                            0.01
+                        } else {
+                            parameter.value.abs() / 10.0
+                        };
+                        let value = if key == Key::Right {
+                            parameter.value + nudge
+                        } else {
+                            parameter.value - nudge
+                        };
+                        self.set_parameter(&parameter.path(), value)
+                            .expect("parameter listed by the model exists");
+                        if self.validate().is_err() {
+                            self.set_parameter(&parameter.path(), parameter.value)
+                                .expect("parameter listed by the model exists");
+                        }
+                    }
+                }
+                Some(Key::Char(_)) => {}
+                None if Instant::now() >= due => {
+                    if !paused {
+                        self.try_step(speed)?;
+                    }
+                    due = Instant::now() + interval;
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+    /// Serves a web page charting the model live on `address`, such as
+    /// `"127.0.0.1:8080"`, and runs it from there. This doesn't return
+    /// unless serving fails.
+    ///
+    /// The page has buttons to start, stop and reset the model, which post
+    /// to the `/start`, `/stop` and `/reset` endpoints, and follows the
+    /// model through Server-Sent Events from `/events` in the format of
+    /// [`SseReporter`](crate::SseReporter). The model starts stopped. While
+    /// it's running, every [frame interval](Model::set_frame_interval) it
+    /// advances by `speed` ticks. Resetting puts every bucket back to how
+    /// it was when serving began, and reseeds the model with the same
+    /// seed, but keeps any parameters that have been changed.
+    ///
+    /// The model is [validated](Model::validate) first, failing with an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) error if it isn't
+    /// valid. If an [invariant](Model::invariants) set to abort stops
+    /// holding, the model stops and the page shows why.
+    #[cfg(feature = "dashboard")]
+    pub fn serve(&mut self, address: impl ToSocketAddrs, speed: u64) -> io::Result<()> {
+        let interval = self.frame_interval;
+        dashboard::serve(self, address, speed, interval)
+    }
+    /// Runs the model for `ticks` ticks without a display, printing the
+    /// state to stdout as CSV as often as `report` asks, and returns once
+    /// it's done.
+    ///
+    /// The output starts with a header row of `time` followed by the name
+    /// of every bucket, then has a row per report. Nothing waits between
+    /// reports, so this is meant for scripts and batch jobs rather than
+    /// watching a model evolve. See [`run_with`](Model::run_with) for
+    /// other destinations.
+    pub fn run_headless(&mut self, ticks: u64, report: Report) -> Result<(), ModelError> {
+        self.run_with(ticks, report, &mut CsvReporter::new(io::stdout()))
+    }
+    /// Runs the model for `ticks` ticks without a display, passing the state
+    /// to `reporter` as often as `report` asks, then every state reported
+    /// once it's done.
+    ///
+    /// The model is [validated](Model::validate) first, and the run stops
+    /// with an error if an [invariant](Model::invariants) set to abort
+    /// stops holding, without finishing the reporter. When stderr is a
+    /// terminal, a progress bar with the estimated time remaining is drawn
+    /// there while the model runs.
+    pub fn run_with(
+        &mut self,
+        ticks: u64,
+        report: Report,
+        reporter: &mut dyn Reporter,
+    ) -> Result<(), ModelError> {
+        self.validate()?;
+        let mut result = SimulationResult::new(self.buckets.iter().map(Bucket::name).collect());
+        let mut write = |model: &Model| {
+            let state = model.state();
+            reporter.on_step(model.time, &state);
+            result.record(model.time, state.iter().map(|(_, quantity)| quantity));
+        };
+        let every = match report {
+            Report::Every(every) => {
+                write(self);
+                every.max(1)
+            }
+            Report::End => ticks.max(1),
+        };
+
+        let mut progress = Progress::new(ticks, "ticks");
+        let chunk = (ticks / PROGRESS_UPDATES).max(1);
+        let mut done = 0;
+        while done < ticks {
+            let report = (done / every + 1) * every;
+            let delta = chunk.min(report - done).min(ticks - done);
+            self.try_step(delta)?;
+            done += delta;
+            progress.advance(delta);
+            if done == report || done == ticks {
+                progress.clear();
+                write(self);
+            }
+        }
+        if ticks == 0 && report == Report::End {
+            write(self);
+        }
+        drop(progress);
+        reporter.on_finish(&result);
+        Ok(())
+    }
+    /// Advances every bucket by `delta` ticks, in as many steps of at most
+    /// [`dt`](Model::set_dt) as that takes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an [invariant](Model::invariants) set to abort stops
+    /// holding.
+    pub fn step(&mut self, delta: u64) {
+        if let Err(error) = self.try_step(delta) {
+            panic!("{}", error);
+        }
+    }
+    /// Advances every bucket by `delta` ticks like [`step`](Model::step),
+    /// but stops and returns an error if an [invariant](Model::invariants)
+    /// set to abort stops holding, leaving the model at the end of the step
+    /// that broke it.
+    pub fn try_step(&mut self, delta: u64) -> Result<(), ModelError> {
+        if self.history.is_empty() {
+            self.record_history();
+        }
+        self.step_observed(delta, |_| {});
+        self.record_history();
+        match self.aborted.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+    /// Advances the model like [`step`](Model::step), calling `observe`
+    /// after every accepted step within the ticks.
+    fn step_observed<F>(&mut self, delta: u64, mut observe: F)
+    where
+        F: FnMut(&Model),
+    {
+        let end = self.time + delta as f64;
+        while end - self.time > solver::MIN_STEP {
+            let dt = match self.solver {
+                Solver::DormandPrince { .. } => self.step_size,
+                Solver::Gillespie => end - self.time,
+                _ => self.dt,
+            };
+            let time = self.time;
+            self.advance(dt.min(end - self.time));
+            if self.aborted.is_some() {
+                return;
+            }
+            if self.time > time && end - self.time > solver::MIN_STEP {
+                observe(self);
+            }
+        }
+        self.time = end;
+        observe(self);
+    }
+    fn advance(&mut self, dt: f64) {
+        let before = self.total();
+        let due = self.events.get_mut().pop_due(self.time + solver::MIN_STEP);
+        let net_change = Transfer::apply_all(&due);
+        let net_change =
+            net_change
+                + match (self.solver, self.update_mode) {
+                    (Solver::Gillespie, _) => {
+                        solver::gillespie(&self.buckets, self.shared(), self.time, self.time + dt)
+                    }
+                    (Solver::TauLeaping, _) => Transfer::apply_all(&solver::tau_leap(
+                        &self.buckets,
+                        self.shared(),
+                        self.time,
+                        dt,
+                    )),
+                    (Solver::DormandPrince { atol, rtol }, _) => {
+                        let (transfers, error) = solver::dormand_prince(
+                            &self.buckets,
+                            self.shared(),
+                            self.time,
+                            dt,
+                            atol,
+                            rtol,
+                        );
+                        let accepted = error <= 1.0 || dt <= solver::MIN_STEP;
+                        let next = (dt * solver::step_factor(error)).max(solver::MIN_STEP);
+                        self.step_size = if accepted && dt < self.step_size {
+                            next.max(self.step_size)
+                        } else {
+                            next
+                        };
+                        if !accepted {
+                            self.scheduled.get_mut().clear();
+                            return;
+                        }
+                        Transfer::apply_integrated(&transfers)
+                    }
+                    (Solver::ImplicitEuler, _) => Transfer::apply_integrated(
+                        &solver::implicit_euler(&self.buckets, self.shared(), self.time, dt),
+                    ),
+                    (Solver::Rk4, _) => Transfer::apply_integrated(&solver::rk4(
+                        &self.buckets,
+                        self.shared(),
+                        self.time,
+                        dt,
+                    )),
+                    (Solver::Euler, UpdateMode::Synchronous) => Transfer::apply_all(
+                        &solver::transfers(&self.buckets, self.shared(), self.time, dt),
+                    ),
+                    (Solver::Euler, UpdateMode::Sequential) => {
+                        let context = Context::new(self.time, dt, &self.buckets, self.shared());
+                        self.buckets
+                            .iter()
+                            .map(|bucket| bucket.update(&context))
+                            .sum()
+                    }
+                };
+        self.events.get_mut().append(self.scheduled.get_mut());
+        let history = self.incidence_history;
+        self.buckets
+            .iter()
+            .for_each(|bucket| bucket.record_incidence(history));
+        self.time += dt;
+        self.check_conservation(before + net_change);
+        if self.precision == Precision::Fixed {
+            self.buckets
+                .iter()
+                .for_each(|bucket| bucket.set(Fixed::quantize(bucket.get())));
+        }
+        self.check_bounds();
+        if let Err(error) = self.invariants.check(self.time, &self.buckets) {
+            self.aborted = Some(error);
+        }
+    }
+    fn check_conservation(&self, expected: f64) {
+        if self.conservation == Conservation::Ignore {
+            return;
+        }
+        let after = self.total();
+        if (after - expected).abs() <= CONSERVATION_TOLERANCE * expected.abs().max(1.0) {
+            return;
+        }
+        let message = format!(
+            "total population is {} rather than {} at tick {}",
+            after, expected, self.time
+        );
+        match self.conservation {
+            Conservation::Log => eprintln!("warning: {}", message),
+            Conservation::Panic => panic!("{}", message),
+            Conservation::Ignore => {}
+        }
+    }
+    fn check_bounds(&mut self) {
+        if self.bounds == Bounds::Ignore {
+            return;
+        }
+        for bucket in &self.buckets {
+            let quantity = bucket.get();
+            let error = match QuantityError::check(&bucket.name(), quantity) {
+                Ok(()) => continue,
+                Err(error) => error,
+            };
+            match self.bounds {
+                Bounds::Clamp => bucket.set(quantity.max(0.0)),
+                Bounds::Panic => panic!("{} at tick {}", error, self.time),
+                Bounds::Report | Bounds::Ignore => {}
+            }
+            self.diagnostics.push((self.time, error));
+        }
+    }
+    /// Returns the total population across every bucket.
+    pub fn total(&self) -> f64 {
+        self.buckets.iter().map(Bucket::get).sum()
+    }
+    /// Sets the length of the time step behaviours are advanced by, in
+    /// ticks. The default is 1, and smaller steps integrate the model more
+    /// accurately at the cost of more updates per tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dt` is not positive and finite.
+    pub fn set_dt(&mut self, dt: f64) {
+        assert!(
+            dt.is_finite() && dt > 0.0,
+            "time step must be positive and finite"
+        );
+        self.dt = dt;
+        self.step_size = dt;
+    }
+    /// Returns the length of the time step, in ticks.
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+    /// Declares the unit of time a tick stands for, so that durations and
+    /// rates in other units can be converted with
+    /// [`ticks`](Model::ticks) and [`rate`](Model::rate). Models declare no
+    /// unit by default.
+    pub fn set_time_unit(&mut self, unit: TimeUnit) {
+        self.time_unit = Some(unit);
+    }
+    /// Returns the unit of time a tick stands for, if one was declared.
+    pub fn time_unit(&self) -> Option<TimeUnit> {
+        self.time_unit
+    }
+    /// Converts a duration of `amount` in `unit` into ticks, as for
+    /// [`set_dt`](Model::set_dt) or [`SimulationResult::uniform`], or
+    /// returns `None` if the model declares no
+    /// [time unit](Model::set_time_unit).
+    pub fn ticks(&self, amount: f64, unit: TimeUnit) -> Option<f64> {
+        Some(unit.duration_in(amount, self.time_unit?))
+    }
+    /// Converts a rate of `rate` per `unit` into a rate per tick, or
+    /// returns `None` if the model declares no
+    /// [time unit](Model::set_time_unit).
+    pub fn rate(&self, rate: f64, unit: TimeUnit) -> Option<f64> {
+        Some(unit.rate_in(rate, self.time_unit?))
+    }
+    /// Reseeds the random number generator shared by the stochastic solvers
+    /// and behaviours, so that runs from the same seed are identical.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = RefCell::new(Rng::new(seed));
+        self.seed = seed;
+    }
+    /// Returns the seed the random number generator was last seeded with,
+    /// 0 unless one has been set.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Reseeds the random number generator, returning the model for
+    /// chaining.
+    pub fn with_seed(mut self, seed: u64) -> Model {
+        self.set_seed(seed);
+        self
+    }
+    /// Sets how flows are integrated over each time step.
+    pub fn set_solver(&mut self, solver: Solver) {
+        self.solver = solver;
+    }
+    /// Sets how flows are integrated over each time step, returning the
+    /// model for chaining.
+    pub fn with_solver(mut self, solver: Solver) -> Model {
+        self.solver = solver;
+        self
+    }
+    /// Sets how behaviours see each other's effects within a step.
+    pub fn set_update_mode(&mut self, update_mode: UpdateMode) {
+        self.update_mode = update_mode;
+    }
+    /// Sets how many time steps of [incidence](Bucket::incidence) every bucket
+    /// keeps, which must cover the longest generation interval of any
+    /// [`Renewal`](crate::Renewal) in the model. The default is 365.
+    pub fn set_incidence_history(&mut self, steps: usize) {
+        self.incidence_history = steps;
+    }
+    /// Sets what happens when a step fails to conserve the total
+    /// population, other than through inflows and outflows.
+    pub fn set_conservation(&mut self, conservation: Conservation) {
+        self.conservation = conservation;
+    }
+    /// Checks that every bucket is named and holds a valid quantity, and
+    /// that every parameter is finite, non-negative unless it is a phase,
+    /// and at most 1 if it is a probability.
+    ///
+    /// The probability of a [`Diffusion`](crate::Diffusion) is a rate per
+    /// tick, so it is only bounded under the solvers that move each step's
+    /// flow at once, [`Euler`](Solver::Euler) and
+    /// [`TauLeaping`](Solver::TauLeaping), and then only at most 1 over a
+    /// step of [`dt`](Model::dt).
+    pub fn validate(&self) -> Result<(), ModelError> {
+        self.check(matches!(self.solver, Solver::Euler | Solver::TauLeaping))
+    }
+    /// Validates the model, bounding the rates of diffusions over a single
+    /// step only if `per_step` is set.
+    pub(crate) fn check(&self, per_step: bool) -> Result<(), ModelError> {
+        for bucket in &self.buckets {
+            let name = bucket.name();
+            if name.is_empty() {
+                return Err(ModelError::EmptyName);
+            }
+            QuantityError::check(&name, bucket.get()).map_err(ModelError::Quantity)?;
+        }
+        for parameter in self.parameters() {
+            let value = parameter.value;
+            if !value.is_finite() {
+                return Err(ModelError::NotFinite(parameter.path(), value));
+            }
+            let key = parameter.key.as_str();
+            if value < 0.0 && !SIGNED.contains(&key) {
+                return Err(ModelError::Negative(parameter.path(), value));
+            }
+            let probability = if parameter.behaviour == "diffusion" {
+                if !per_step {
+                    continue;
+                }
+                value * self.dt
+            } else {
+                value
+            };
+            if probability > 1.0 && PROBABILITIES.contains(&key) {
+                return Err(ModelError::NotProbability(parameter.path(), probability));
+            }
+        }
+        Ok(())
+    }
+    /// Sets how bucket quantities are represented between steps.
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+    /// Sets how bucket quantities are represented between steps, returning
+    /// the model for chaining.
+    pub fn with_precision(mut self, precision: Precision) -> Model {
+        self.precision = precision;
+        self
+    }
+    /// Sets how long [`run`](Model::run) waits between frames. The default
+    /// is 100 milliseconds.
+    pub fn set_frame_interval(&mut self, interval: Duration) {
+        self.frame_interval = interval;
+    }
+    /// Sets what [`run`](Model::run) draws every frame. The default is a
+    /// [dashboard](View::Dashboard).
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
+    /// Sets whether [`run`](Model::run) shows every bucket as a percentage
+    /// of the total across every bucket, sinks included, rather than as a
+    /// quantity. The total is recomputed every frame, so percentages stay
+    /// true as births and deaths change it. Quantities are shown by
+    /// default.
+    ///
+    /// [`SimulationResult::normalized`] does the same for results.
+    pub fn set_normalized(&mut self, normalized: bool) {
+        self.normalized = normalized;
+    }
+    /// Sets the quantities at which the bucket called `bucket` is
+    /// highlighted in the [table](View::Table) drawn by
+    /// [`run`](Model::run), replacing any threshold it had.
+    pub fn set_threshold(&mut self, bucket: &str, threshold: Threshold) {
+        match self.thresholds.iter_mut().find(|(name, _)| name == bucket) {
+            Some((_, existing)) => *existing = threshold,
+            None => self.thresholds.push((bucket.to_owned(), threshold)),
+        }
+    }
+    /// Returns the value of every bucket at the start and after every call
+    /// to [`step`](Model::step) so far, which includes every frame of
+    /// [`run`](Model::run) and every tick of a simulation.
+    ///
+    /// Only the most recent points are kept if a
+    /// [limit](Model::set_history_limit) is set.
+    pub fn history(&self) -> SimulationResult {
+        let mut result = SimulationResult::new(self.buckets.iter().map(Bucket::name).collect());
+        self.history
+            .iter()
+            .for_each(|(time, row)| result.record(*time, row.iter().cloned()));
+        result
+    }
+    /// Sets whether every transfer each behaviour computes is printed to
+    /// stderr, to help find out why a bucket fills or drains unexpectedly.
+    /// Tracing is off by default.
+    ///
+    /// Each transfer is printed on a line of its own, in the form
+    ///
+    /// ```text
+    /// t=12 dt=1 behaviour=infection source=S target=E amount=3.2
+    /// ```
+    ///
+    /// with a source or target of `-` for transfers into or out of the
+    /// model. Transfers are printed as behaviours compute them from the
+    /// state at the start of each step, before any are scaled down for
+    /// want of population, and only once a step under solvers that evaluate
+    /// behaviours more than once. Nothing is printed under
+    /// [`Solver::Gillespie`], which evaluates behaviours only for their
+    /// rates.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+    /// Limits the history to the most recent `limit` points, or keeps every
+    /// point if `limit` is `None`, which is the default.
+    pub fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.history_limit = limit;
+        self.truncate_history();
+    }
+    /// Forgets the history recorded so far.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+    fn record_history(&mut self) {
+        if self
+            .history
+            .back()
+            .is_some_and(|(time, _)| *time == self.time)
+        {
+            self.history.pop_back();
+        }
+        let row = self.buckets.iter().map(Bucket::get).collect();
+        self.history.push_back((self.time, row));
+        self.truncate_history();
+    }
+    fn truncate_history(&mut self) {
+        if let Some(limit) = self.history_limit {
+            while self.history.len() > limit {
+                self.history.pop_front();
+            }
+        }
+    }
+    /// Schedules `transfer` to be applied once, at the start of the first
+    /// step that begins at or after `time`.
+    ///
+    /// Behaviours schedule their own events through
+    /// [`Context::schedule`].
+    pub fn schedule(&mut self, time: f64, transfer: Transfer) {
+        self.events.get_mut().push(time, transfer);
+    }
+    /// Returns the number of scheduled events still waiting to be applied.
+    pub fn pending_events(&self) -> usize {
+        self.events.borrow().len()
+    }
+    fn shared(&self) -> Shared<'_> {
+        Shared {
+            rng: &self.rng,
+            events: Some(&self.scheduled),
+            trace: self.trace,
+        }
+    }
+    /// Returns a description of the model and how it is being run, with its
+    /// seed, step, solver, buckets and parameters.
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            seed: self.seed,
+            dt: self.dt,
+            time_unit: self.time_unit,
+            solver: self.solver,
+            time: self.time,
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| (bucket.name(), bucket.get()))
+                .collect(),
+            parameters: self.parameters(),
+            definition: self.definition.clone(),
+        }
+    }
+    pub(crate) fn set_definition(&mut self, definition: String) {
+        self.definition = Some(definition);
+    }
+    /// Saves the time, the quantity, incidence and flows of every bucket,
+    /// every parameter, what every behaviour has queued, the scheduled
+    /// events and the state of the random number generator to the file at
+    /// `path`, replacing it if it exists, so that the run can be resumed
+    /// later with [`load_checkpoint`](Model::load_checkpoint).
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_checkpoint(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+    /// Writes a checkpoint, as saved by
+    /// [`save_checkpoint`](Model::save_checkpoint), to `writer`.
+    pub fn write_checkpoint<W: Write>(&self, writer: W) -> Result<(), CheckpointError> {
+        self.checkpoint().write(writer)
+    }
+    fn checkpoint(&self) -> Checkpoint {
+        let totals: Vec<_> = self.buckets.iter().map(Bucket::totals).collect();
+        Checkpoint {
+            time: self.time,
+            seed: self.seed,
+            rng: self.rng.borrow().clone(),
+            step_size: self.step_size,
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| (bucket.name(), bucket.get()))
+                .collect(),
+            incidence: self
+                .buckets
+                .iter()
+                .zip(&totals)
+                .map(|(bucket, (incidence, _, _))| (bucket.name(), incidence.clone()))
+                .collect(),
+            cumulative: self
+                .buckets
+                .iter()
+                .zip(&totals)
+                .map(|(bucket, (_, cumulative, _))| (bucket.name(), *cumulative))
+                .collect(),
+            flows: self
+                .buckets
+                .iter()
+                .zip(&totals)
+                .map(|(bucket, (_, _, flows))| (bucket.name(), flows.clone()))
+                .collect(),
+            parameters: self
+                .parameters()
+                .iter()
+                .map(|parameter| (parameter.path(), parameter.value))
+                .collect(),
+            states: self
+                .behaviours()
+                .into_iter()
+                .filter_map(|(bucket, behaviour)| {
+                    let behaviour = behaviour.borrow();
+                    let state = behaviour.state(&self.buckets);
+                    let path = format!("{}.{}", bucket.name(), behaviour.name());
+                    Some((path, state)).filter(|(_, state)| !state.is_empty())
+                })
+                .collect(),
+            events: self
+                .events
+                .borrow()
+                .events()
+                .into_iter()
+                .map(|(time, transfer)| {
+                    let mut event = vec![time];
+                    event.extend(transfer.encode(&self.buckets));
+                    event
+                })
+                .collect(),
+        }
+    }
+    /// Resumes from the checkpoint saved to the file at `path` by
+    /// [`save_checkpoint`](Model::save_checkpoint), so that the model
+    /// carries on exactly as the saved one would have. Loading the same
+    /// checkpoint into several models branches scenarios from a common
+    /// point.
+    ///
+    /// Only the numbers are saved, not the structure of the model, so it
+    /// must be loaded into a model with the same buckets and behaviours,
+    /// such as one built by the same code. Parameters that follow a
+    /// [`Schedule`](crate::Schedule) keep following the model's own
+    /// schedule rather than taking the saved value, so that the resumed run
+    /// matches the uninterrupted one. Counters outside the model, such as
+    /// those of [`Counted`](crate::Counted), are not saved, and neither is
+    /// the history, which starts afresh from the checkpoint's time. On
+    /// failure the model is left as it was.
+    pub fn load_checkpoint(&mut self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        self.read_checkpoint(BufReader::new(File::open(path)?))
+    }
+    /// Resumes from a checkpoint read from `reader`, as
+    /// [`load_checkpoint`](Model::load_checkpoint) does.
+    pub fn read_checkpoint<R: Read>(&mut self, reader: R) -> Result<(), CheckpointError> {
+        let checkpoint = Checkpoint::read(reader)?;
+        let backup = (self.checkpoint(), self.history.clone());
+        self.resume(&checkpoint).inspect_err(|_| {
+            self.resume(&backup.0)
+                .expect("a model can resume from its own checkpoint");
+            self.history = backup.1;
+        })
+    }
+    fn resume(&mut self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        let position = |name: &String| {
+            self.buckets
+                .iter()
+                .position(|bucket| bucket.name() == *name)
+                .ok_or_else(|| CheckpointError::UnknownBucket(name.clone()))
+        };
+        let mut quantities: Vec<f64> = self.buckets.iter().map(Bucket::get).collect();
+        for (name, quantity) in &checkpoint.buckets {
+            quantities[position(name)?] = *quantity;
+        }
+        let mut totals: Vec<_> = self
+            .buckets
+            .iter()
+            .map(|_| (&[][..], 0.0, &[][..]))
+            .collect();
+        for (name, incidence) in &checkpoint.incidence {
+            totals[position(name)?].0 = incidence;
+        }
+        for (name, cumulative) in &checkpoint.cumulative {
+            totals[position(name)?].1 = *cumulative;
+        }
+        for (name, flows) in &checkpoint.flows {
+            totals[position(name)?].2 = flows;
+        }
+        let paths: Vec<String> = self.parameters().iter().map(Parameter::path).collect();
+        if let Some((path, _)) = checkpoint
+            .parameters
+            .iter()
+            .find(|(path, _)| !paths.contains(path))
+        {
+            return Err(ParameterError::Unknown(path.clone()).into());
+        }
+        let events = checkpoint
+            .events
+            .iter()
+            .map(|event| match event.split_first() {
+                Some((time, transfer)) => Transfer::decode(transfer, &self.buckets)
+                    .map(|transfer| (*time, transfer))
+                    .ok_or_else(|| CheckpointError::Malformed(format!("event,,{:?}", event))),
+                None => Err(CheckpointError::Malformed("event,,".to_owned())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.restart(checkpoint.time, &quantities);
+        self.buckets
+            .iter()
+            .zip(totals)
+            .for_each(|(bucket, (incidence, cumulative, flows))| {
+                bucket.set_totals(incidence, cumulative, flows)
+            });
+        events
+            .into_iter()
+            .for_each(|(time, transfer)| self.events.get_mut().push(time, transfer));
+        // Parameters and states are restored behaviour by behaviour, in the
+        // order they were saved, since a path may address several
+        // behaviours. Scheduled parameters are left to follow their
+        // schedules from the checkpoint's time.
+        let mut saved: Vec<Option<&(String, f64)>> =
+            checkpoint.parameters.iter().map(Some).collect();
+        let mut states: Vec<Option<&(String, Vec<f64>)>> =
+            checkpoint.states.iter().map(Some).collect();
+        for (bucket, behaviour) in self.behaviours() {
+            let mut behaviour = behaviour.borrow_mut();
+            let name = behaviour.name().to_owned();
+            let scheduled: Vec<&'static str> = behaviour
+                .params()
+                .into_iter()
+                .filter(|(_, param)| param.is_scheduled())
+                .map(|(key, _)| key)
+                .collect();
+            for (key, _) in behaviour.parameters() {
+                let path = format!("{}.{}.{}", bucket.name(), name, key);
+                let value = saved
+                    .iter_mut()
+                    .find(|entry| entry.is_some_and(|(saved, _)| *saved == path))
+                    .and_then(Option::take);
+                if let Some((_, value)) = value {
+                    if !scheduled.contains(&key) {
+                        behaviour.set_parameter(key, *value)?;
+                    }
+                }
+            }
+            let path = format!("{}.{}", bucket.name(), name);
+            let state = states
+                .iter_mut()
+                .find(|entry| entry.is_some_and(|(saved, _)| *saved == path))
+                .and_then(Option::take);
+            if let Some((_, state)) = state {
+                if !behaviour.set_state(state, &self.buckets) {
+                    return Err(CheckpointError::State(path));
+                }
+            }
+        }
+        if let Some((path, _)) = states.into_iter().flatten().next() {
+            return Err(CheckpointError::State(path.clone()));
+        }
+        self.rng = RefCell::new(checkpoint.rng.clone());
+        self.seed = checkpoint.seed;
+        self.step_size = checkpoint.step_size;
+        Ok(())
+    }
+    /// Returns the invariants checked after every step, to which more can be
+    /// added.
+    pub fn invariants(&mut self) -> &mut Invariants {
+        &mut self.invariants
+    }
+    /// Sets what to do when a step leaves a bucket negative or not finite.
+    pub fn set_bounds(&mut self, bounds: Bounds) {
+        self.bounds = bounds;
+    }
+    /// Returns every bucket left negative or not finite by a step so far,
+    /// along with the time at the end of that step.
+    pub fn diagnostics(&self) -> &[(f64, QuantityError)] {
+        &self.diagnostics
+    }
+    /// Returns and clears the diagnostics recorded so far.
+    pub fn take_diagnostics(&mut self) -> Vec<(f64, QuantityError)> {
+        std::mem::take(&mut self.diagnostics)
+    }
+    /// Runs the model for `ticks` single-tick steps without any output.
+    pub fn run_for(&mut self, ticks: u64) {
+        (0..ticks).for_each(|_| self.step(1));
+    }
+    /// Runs the model one tick at a time until `predicate` returns true,
+    /// returning the number of ticks that were run.
+    ///
+    /// The predicate is checked before every tick, so no ticks are run if it
+    /// already holds.
+    pub fn run_until<F>(&mut self, mut predicate: F) -> u64
+    where
+        F: FnMut(&Model) -> bool,
+    {
+        let mut ticks = 0;
+        while !predicate(self) {
+            self.step(1);
+            ticks += 1;
+        }
+        ticks
+    }
+    /// Runs the model one tick at a time until it settles into an
+    /// equilibrium, returning the state it settled in, or `None` if it was
+    /// still changing after `max_ticks` ticks.
+    ///
+    /// The model is taken to have settled once no bucket has changed by
+    /// more than `tolerance` in a tick for `window` ticks in a row. Sinks
+    /// are ignored, since they keep filling at an endemic equilibrium with
+    /// births and deaths.
+    pub fn run_to_equilibrium(
+        &mut self,
+        tolerance: f64,
+        window: u64,
+        max_ticks: u64,
+    ) -> Option<State> {
+        let living = |model: &Model| -> Vec<f64> {
+            model
+                .buckets
+                .iter()
+                .filter(|bucket| !bucket.is_sink())
+                .map(Bucket::get)
+                .collect()
+        };
+        let mut previous = living(self);
+        let mut settled = 0;
+        for _ in 0..max_ticks {
+            self.step(1);
+            let current = living(self);
+            let change = previous
+                .iter()
+                .zip(&current)
+                .map(|(before, after)| (after - before).abs())
+                .fold(0.0, f64::max);
+            settled = if change <= tolerance { settled + 1 } else { 0 };
+            if settled >= window.max(1) {
+                return Some(self.state());
+            }
+            previous = current;
+        }
+        None
+    }
+    /// Runs the model one tick at a time until the infection dies out,
+    /// returning how the outbreak played out, or `None` if it was still
+    /// going after `max_ticks` ticks.
+    ///
+    /// The infection is taken to have died out once the buckets named in
+    /// `active`, such as `E` and `I`, hold no more than `threshold` between
+    /// them. A threshold of zero suits stochastic models, while something
+    /// below one individual suits deterministic ones, which only approach
+    /// zero. The final size counts everyone who moved into the first of
+    /// `active`, so that should be the bucket infections enter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no bucket named in `active`.
+    pub fn run_to_extinction(
+        &mut self,
+        active: &[&str],
+        threshold: f64,
+        max_ticks: u64,
+    ) -> Option<Outbreak> {
+        let active: Vec<Bucket> = active
+            .iter()
+            .map(|name| {
+                self.bucket(name)
+                    .unwrap_or_else(|| panic!("no bucket named `{}`", name))
+            })
+            .collect();
+        let prevalence = |active: &[Bucket]| active.iter().map(Bucket::get).sum::<f64>();
+        let start = self.time;
+        let initial = prevalence(&active);
+        let entered = active.first().map_or(0.0, Bucket::cumulative_incidence);
+        let mut ticks = 0;
+        while prevalence(&active) > threshold {
+            if ticks == max_ticks {
+                return None;
+            }
+            self.step(1);
+            ticks += 1;
+        }
+        let final_size =
+            initial + active.first().map_or(0.0, Bucket::cumulative_incidence) - entered;
+        Some(Outbreak::new(self.time - start, final_size, self.state()))
+    }
+    /// Runs the model for `ticks` single-tick steps, recording the value of
+    /// every bucket before the first step and after each one.
+    pub fn simulate(&mut self, ticks: u64) -> SimulationResult {
+        let mut result = SimulationResult::new(self.buckets.iter().map(Bucket::name).collect());
+        result.record(self.time, self.buckets.iter().map(Bucket::get));
+        (0..ticks).for_each(|_| {
+            self.step(1);
+            result.record(self.time, self.buckets.iter().map(Bucket::get));
+        });
+        result
+    }
+    /// Runs the model for `ticks` single-tick steps like
+    /// [`simulate`](Model::simulate), returning both the value of every
+    /// bucket and how much every behaviour moved during each tick.
+    ///
+    /// The first result holds the prevalence, the stock of each bucket, and
+    /// the second the incidence, such as new cases per day, with a column
+    /// per behaviour named by its `bucket.behaviour` path as in
+    /// [`flows`](Model::flows). Both are recorded at the same times, and
+    /// the first row of the incidence, before any tick is run, is zero.
+    pub fn simulate_flows(&mut self, ticks: u64) -> (SimulationResult, SimulationResult) {
+        let (names, mut last): (Vec<_>, Vec<_>) = self.flows().into_iter().unzip();
+        let mut prevalence = SimulationResult::new(self.buckets.iter().map(Bucket::name).collect());
+        let mut incidence = SimulationResult::new(names);
+        prevalence.record(self.time, self.buckets.iter().map(Bucket::get));
+        incidence.record(self.time, last.iter().map(|_| 0.0));
+        (0..ticks).for_each(|_| {
+            self.step(1);
+            prevalence.record(self.time, self.buckets.iter().map(Bucket::get));
+            let totals: Vec<f64> = self.flows().into_iter().map(|(_, total)| total).collect();
+            incidence.record(
+                self.time,
+                totals.iter().zip(&last).map(|(total, last)| total - last),
+            );
+            last = totals;
+        });
+        (prevalence, incidence)
+    }
+    /// Runs the model for `ticks` ticks like [`simulate`](Model::simulate),
+    /// but records every bucket after every internal step as well as at
+    /// every tick.
+    ///
+    /// With a small [`dt`](Model::set_dt) or an adaptive solver the time
+    /// points are uneven, and [`SimulationResult::resample`] gives values
+    /// on any grid of times from them. Gillespie runs are still only
+    /// recorded at every tick.
+    pub fn simulate_dense(&mut self, ticks: u64) -> SimulationResult {
+        let mut result = SimulationResult::new(self.buckets.iter().map(Bucket::name).collect());
+        result.record(self.time, self.buckets.iter().map(Bucket::get));
+        (0..ticks).for_each(|_| {
+            self.step_observed(1, |model| {
+                result.record(model.time, model.buckets.iter().map(Bucket::get));
+            });
+        });
+        result
+    }
+    /// Runs `runs` replicates of the model for `ticks` ticks each, every one
+    /// from the current state with a fresh seed drawn from the model's
+    /// generator, and returns their trajectories.
+    ///
+    /// Every bucket and behaviour is [reset](crate::Behaviour::reset) before each
+    /// run and again afterwards, so the model is left as it was found.
+    /// [`Counter`](crate::Counter)s are not reset, and accumulate over every
+    /// run.
+    pub fn ensemble(&mut self, runs: usize, ticks: u64) -> Ensemble {
+        let time = self.time;
+        let start: Vec<f64> = self.buckets.iter().map(Bucket::get).collect();
+        let seeds: Vec<u64> = (0..runs).map(|_| self.rng.get_mut().next_u64()).collect();
+        let (rng, seed) = (self.rng.get_mut().clone(), self.seed);
+        let results = seeds
+            .iter()
+            .map(|&seed| {
+                self.restart(time, &start);
+                self.set_seed(seed);
+                self.simulate(ticks)
+            })
+            .collect();
+        self.restart(time, &start);
+        self.rng = RefCell::new(rng);
+        self.seed = seed;
+        Ensemble::new(results, seeds)
+    }
+    /// Runs the model for `ticks` ticks as it stands, the baseline, and
+    /// again under each of `scenarios`, every run from the current state,
+    /// and returns their trajectories side by side.
+    ///
+    /// Every run starts from the same seed, so stochastic scenarios share
+    /// their random numbers with the baseline as far as they can and
+    /// differences between them reflect the scenarios rather than chance.
+    /// The model is reset between runs as by [`ensemble`](Model::ensemble),
+    /// and left afterwards as it was found, with every parameter back at
+    /// its baseline value and scheduled ones back on their schedules.
+    /// Returns an error if a scenario sets a parameter the model doesn't
+    /// have.
+    pub fn compare(
+        &mut self,
+        scenarios: Vec<Scenario>,
+        ticks: u64,
+    ) -> Result<Comparison, ParameterError> {
+        let time = self.time;
+        let start: Vec<f64> = self.buckets.iter().map(Bucket::get).collect();
+        // Parameters are restored behaviour by behaviour, since a path may
+        // address several behaviours that started with different values, and
+        // `Param`s are restored whole so that schedules survive.
+        type Saved = (Vec<(&'static str, f64)>, Vec<(&'static str, Param)>);
+        let parameters: Vec<(SharedBehaviour, Saved)> = self
+            .behaviours()
+            .into_iter()
+            .map(|(_, behaviour)| {
+                let saved = {
+                    let mut behaviour = behaviour.borrow_mut();
+                    let params = behaviour
+                        .params()
+                        .into_iter()
+                        .map(|(key, param)| (key, param.clone()))
+                        .collect();
+                    (behaviour.parameters(), params)
+                };
+                (behaviour, saved)
+            })
+            .collect();
+        let (rng, seed) = (self.rng.get_mut().clone(), self.seed);
+        let reset = |model: &mut Model| {
+            model.restart(time, &start);
+            for (behaviour, (values, params)) in &parameters {
+                let mut behaviour = behaviour.borrow_mut();
+                for (key, value) in values {
+                    if params.iter().all(|(param, _)| param != key) {
+                        behaviour
+                            .set_parameter(key, *value)
+                            .expect("a behaviour's own parameters can be set");
+                    }
+                }
+                for (key, param) in behaviour.params() {
+                    if let Some((_, saved)) = params.iter().find(|(saved, _)| *saved == key) {
+                        *param = saved.clone();
+                    }
+                }
+            }
+            model.rng = RefCell::new(rng.clone());
+            model.seed = seed;
+        };
+        reset(self);
+        let baseline = self.simulate(ticks);
+        let results = scenarios
+            .iter()
+            .map(|scenario| {
+                reset(self);
+                scenario.apply(self)?;
+                Ok((scenario.name().to_owned(), self.simulate(ticks)))
+            })
+            .collect::<Result<Vec<_>, ParameterError>>();
+        reset(self);
+        Ok(Comparison::new(baseline, results?))
+    }
+    /// Returns the next-generation matrix of the compartments named in
+    /// `infected`, whose entry `(i, j)` is the expected number of new
+    /// infections entering compartment `i` caused by one individual who
+    /// enters compartment `j`, over that individual's whole time infected.
+    ///
+    /// The matrix is worked out from the model's own flows in the current
+    /// state: flows into an infected compartment from outside them are new
+    /// infections, and every other flow out of an infected compartment is a
+    /// transition. Behaviours are evaluated as by a solver, so the same
+    /// caveats about stateful or random behaviours apply as for
+    /// [`Solver::Rk4`].
+    pub fn next_generation_matrix(
+        &self,
+        infected: &[&str],
+    ) -> Result<Vec<Vec<f64>>, ReproductionError> {
+        let infected = infected
+            .iter()
+            .map(|name| {
+                self.buckets
+                    .iter()
+                    .position(|bucket| bucket.name() == *name)
+                    .ok_or_else(|| ReproductionError::Unknown((*name).to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        reproduction::next_generation(&self.buckets, self.shared(), self.time, self.dt, &infected)
+    }
+    /// Returns the reproduction number, the expected number of secondary
+    /// infections caused by one infected individual, as the dominant
+    /// eigenvalue of the [next-generation
+    /// matrix](Model::next_generation_matrix) of the compartments named in
+    /// `infected`.
+    ///
+    /// Before an outbreak takes hold, while the population is still wholly
+    /// susceptible, this is the basic reproduction number R0. Later on it
+    /// is the effective reproduction number Rt, which falls as susceptibles
+    /// are depleted and with interventions; see
+    /// [`simulate_rt`](Model::simulate_rt) to track it over a run.
+    pub fn reproduction_number(&self, infected: &[&str]) -> Result<f64, ReproductionError> {
+        Ok(reproduction::spectral_radius(
+            &self.next_generation_matrix(infected)?,
+        ))
+    }
+    /// Returns how many of the compartment called `susceptible` the
+    /// final-size relation predicts will be infected from now until the
+    /// epidemic dies out, with the compartments named in `infected`
+    /// spreading it.
+    ///
+    /// The relation is `ln(S0 / S) = R (S0 + I0 - S) / S0`, for `S0`
+    /// susceptibles and `I0` infected individuals now, `S` left susceptible
+    /// at the end and the [reproduction number](Model::reproduction_number)
+    /// `R` now. It holds exactly for SIR-type models, with mass-action or
+    /// frequency-dependent transmission, lasting immunity and a closed
+    /// population, however many stages infection passes through, so a
+    /// simulation run to the end should reach it, and a gap between them
+    /// measures the error of the solver. Divided by the population, it is
+    /// the [`attack_rate`](crate::Summary::attack_rate) of such a run.
+    ///
+    /// ```
+    /// use epidemic::templates;
+    ///
+    /// let mut model = templates::sir(0.5, 0.2, 1000.0, 1.0);
+    /// let predicted = model.final_size("S", &["I"]).unwrap();
+    /// model.set_dt(0.01);
+    /// let result = model.simulate(300);
+    /// let infected = 999.0 - result.get("S").unwrap().last().unwrap();
+    /// assert!((infected - predicted).abs() < 0.01 * predicted);
+    /// ```
+    pub fn final_size(
+        &self,
+        susceptible: &str,
+        infected: &[&str],
+    ) -> Result<f64, ReproductionError> {
+        let reproduction = self.reproduction_number(infected)?;
+        let quantity = |name: &str| {
+            self.bucket(name)
+                .map(|bucket| bucket.get())
+                .ok_or_else(|| ReproductionError::Unknown(name.to_owned()))
+        };
+        let s0 = quantity(susceptible)?;
+        let i0 = infected
+            .iter()
+            .map(|name| quantity(name))
+            .sum::<Result<f64, _>>()?;
+        Ok(s0 - reproduction::final_susceptible(reproduction, s0, i0))
+    }
+    /// Finds the disease-free and endemic equilibria of the model, the
+    /// states at which its flows balance with no one infected and with the
+    /// infection persisting, and the eigenvalues of the Jacobian of the
+    /// flows at each, which tell whether it is stable.
+    ///
+    /// The endemic equilibrium is sought by following the flows from the
+    /// current state for up to `max_ticks` ticks and then homing in on
+    /// where they balance, and the disease-free one in the same way with
+    /// the compartments named in `infected` emptied first. An equilibrium
+    /// the flows lead away from, such as the disease-free one when R0 is
+    /// above one, is still found since emptying the infected compartments
+    /// leaves nothing to lead away. The endemic equilibrium is `None` if the
+    /// infection dies out instead, as it does without births or waning
+    /// immunity to replenish susceptibles. The dominant eigenvalue of the
+    /// disease-free equilibrium crosses zero where R0 crosses one, so
+    /// sweeping a parameter and watching it locates such thresholds.
+    ///
+    /// Behaviours are evaluated as by a solver at the current time, so the
+    /// same caveats about stateful or random behaviours apply as for
+    /// [`Solver::Rk4`], and schedules and time-varying parameters are held
+    /// as they are now.
+    ///
+    /// ```
+    /// use epidemic::{templates, Stability};
+    ///
+    /// // R0 is 2.5, so the infection persists with 1 - 1 / R0 infected.
+    /// let model = templates::sis(0.5, 0.2, 1000.0, 10.0);
+    /// let equilibria = model.equilibria(&["I"], 1000).unwrap();
+    /// let endemic = equilibria.endemic.unwrap();
+    /// assert!((endemic.get("I").unwrap() - 600.0).abs() < 1e-3);
+    /// assert_eq!(endemic.stability(), Stability::Stable);
+    /// let disease_free = equilibria.disease_free.unwrap();
+    /// assert_eq!(disease_free.stability(), Stability::Unstable);
+    /// ```
+    pub fn equilibria(
+        &self,
+        infected: &[&str],
+        max_ticks: u64,
+    ) -> Result<Equilibria, ReproductionError> {
+        let flows = Flows::new(&self.buckets, self.shared(), self.time, self.dt);
+        let infected = infected
+            .iter()
+            .map(|name| {
+                flows
+                    .position(name)
+                    .ok_or_else(|| ReproductionError::Unknown((*name).to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let start = flows.current();
+        let scale = start.iter().map(|x| x.abs()).sum::<f64>().max(1.0);
+        let endemic = flows
+            .settle(&start, max_ticks)
+            .filter(|state| infected.iter().map(|i| state[*i]).sum::<f64>() > 1e-6 * scale)
+            .map(|state| flows.equilibrium(state));
+        let mut start = start;
+        infected.iter().for_each(|i| start[*i] = 0.0);
+        let disease_free = flows
+            .settle(&start, max_ticks)
+            .map(|state| flows.equilibrium(state));
+        Ok(Equilibria {
+            disease_free,
+            endemic,
+        })
+    }
+    /// Runs the model for `ticks` single-tick steps like
+    /// [`simulate`](Model::simulate), also recording the
+    /// [reproduction number](Model::reproduction_number) of the
+    /// compartments named in `infected` at every time point, as a series
+    /// named `Rt`.
+    pub fn simulate_rt(
+        &mut self,
+        ticks: u64,
+        infected: &[&str],
+    ) -> Result<(SimulationResult, TimeSeries), ReproductionError> {
+        let mut result = SimulationResult::new(self.buckets.iter().map(Bucket::name).collect());
+        let mut rt = TimeSeries::new("Rt".to_owned());
+        result.record(self.time, self.buckets.iter().map(Bucket::get));
+        rt.push(self.reproduction_number(infected)?);
+        for _ in 0..ticks {
+            self.step(1);
+            result.record(self.time, self.buckets.iter().map(Bucket::get));
+            rt.push(self.reproduction_number(infected)?);
+        }
+        Ok((result, rt))
+    }
+    pub(crate) fn restart(&mut self, time: f64, quantities: &[f64]) {
+        self.time = time;
+        self.history.clear();
+        self.events.get_mut().clear();
+        self.scheduled.get_mut().clear();
+        self.step_size = self.dt;
+        self.buckets
+            .iter()
+            .zip(quantities)
+            .for_each(|(bucket, quantity)| bucket.reset(*quantity));
+    }
+    /// Returns the number of ticks the model has been advanced by.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    /// Returns a snapshot of every bucket in the model.
+    pub fn state(&self) -> State {
+        State::new(self.time, &self.buckets)
+    }
+    /// Adds a bucket to the model.
+    pub fn add(&mut self, bucket: Bucket) {
+        self.buckets.push(bucket);
+    }
+    /// Returns a handle to the bucket called `name`.
+    pub fn bucket(&self, name: &str) -> Option<Bucket> {
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.name() == name)
+            .cloned()
+    }
+    /// Iterates over every bucket, in the order they were added.
+    pub fn buckets(&self) -> slice::Iter<'_, Bucket> {
+        self.buckets.iter()
+    }
+    /// Returns every behaviour in the model along with the bucket it is
+    /// attached to.
+    pub fn behaviours(&self) -> Vec<(Bucket, SharedBehaviour)> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| {
+                bucket
+                    .behaviours()
+                    .into_iter()
+                    .map(move |behaviour| (bucket.clone(), behaviour))
+            })
+            .collect()
+    }
+    /// Returns how much population every behaviour in the model has moved
+    /// since it was built or last reset, keyed by `bucket.behaviour` paths
+    /// as parameters are addressed by [`Parameter::path`]. See
+    /// [`Bucket::flows`].
+    pub fn flows(&self) -> Vec<(String, f64)> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| {
+                let name = bucket.name();
+                bucket
+                    .flows()
+                    .into_iter()
+                    .map(move |(behaviour, total)| (format!("{}.{}", name, behaviour), total))
+            })
+            .collect()
+    }
+    /// Returns every tunable parameter of every behaviour in the model.
+    pub fn parameters(&self) -> Vec<Parameter> {
+        self.behaviours()
+            .into_iter()
+            .flat_map(|(bucket, behaviour)| {
+                let behaviour = behaviour.borrow();
+                let name = behaviour.name().to_owned();
+                behaviour
+                    .parameters()
+                    .into_iter()
+                    .map(|(key, value)| Parameter {
+                        bucket: bucket.name(),
+                        behaviour: name.clone(),
+                        key: key.to_owned(),
+                        value,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+    /// Sets a parameter addressed by a `bucket.behaviour.key` path, as
+    /// returned by [`Parameter::path`].
+    ///
+    /// Every behaviour of that name on the bucket is updated.
+    pub fn set_parameter(&mut self, path: &str, value: f64) -> Result<(), ParameterError> {
+        let unknown = || ParameterError::Unknown(path.to_owned());
+        let mut parts = path.rsplitn(3, '.');
+        let key = parts.next().ok_or_else(unknown)?;
+        let name = parts.next().ok_or_else(unknown)?;
+        let bucket = parts.next().ok_or_else(unknown)?;
+        let mut found = false;
+        for behaviour in self.bucket(bucket).ok_or_else(unknown)?.behaviours() {
+            let mut behaviour = behaviour.borrow_mut();
+            if behaviour.name() == name {
+                behaviour.set_parameter(key, value)?;
+                found = true;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(unknown())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ContactTracing, Delayed, Model, RateFlow, Renewal, Transfer, TwoDoseVaccination};
+
+    /// A renewal SIR with a lagged recovery, a two-dose campaign, contact
+    /// tracing, an imported seed and an event due after the checkpoint, so
+    /// that everything a checkpoint holds is in play.
+    fn model() -> Model {
+        let mut model = Model::builder()
+            .compartment("S", 990)
+            .compartment("I", 0)
+            .compartment("R", 0)
+            .compartment("Q", 0)
+            .compartment("V1", 0)
+            .compartment("V2", 0)
+            .build()
+            .unwrap();
+        let bucket = |name| model.bucket(name).unwrap();
+        let (mut s, mut i, r) = (bucket("S"), bucket("I"), bucket("R"));
+        s.add(Renewal::new(i.clone(), 2.5, vec![0.2, 0.5, 0.3]));
+        s.add(TwoDoseVaccination::new(
+            bucket("V1"),
+            bucket("V2"),
+            5.0,
+            3.0,
+        ));
+        s.add(ContactTracing::new(bucket("Q"), r.clone(), 2.0, 0.5, 2.0));
+        i.add(Delayed::new(
+            RateFlow::new(r, |state| 0.2 * state.get("I").unwrap_or(0.0)),
+            4.0,
+        ));
+        model.schedule(0.0, Transfer::inflow(i.clone(), 10.0));
+        model.schedule(8.0, Transfer::inflow(i, 5.0));
+        model
+    }
+
+    #[test]
+    fn resumed_runs_match_uninterrupted_ones() {
+        let mut uninterrupted = model();
+        uninterrupted.step(20);
+        let mut saved = model();
+        saved.step(5);
+        let mut checkpoint = Vec::new();
+        saved.write_checkpoint(&mut checkpoint).unwrap();
+        let mut resumed = model();
+        resumed.read_checkpoint(&checkpoint[..]).unwrap();
+        resumed.step(15);
+        assert_eq!(resumed.time(), uninterrupted.time());
+        for (resumed, uninterrupted) in resumed.buckets().zip(uninterrupted.buckets()) {
+            assert_eq!(resumed.get(), uninterrupted.get(), "{}", resumed.name());
+            assert_eq!(resumed.incidence(0), uninterrupted.incidence(0));
+        }
+        assert_eq!(resumed.flows(), uninterrupted.flows());
+    }
+
+    #[test]
+    fn failed_loads_leave_the_model_as_it_was() {
+        let mut model = model();
+        model.step(5);
+        let mut checkpoint = Vec::new();
+        model.write_checkpoint(&mut checkpoint).unwrap();
+        let before = String::from_utf8(checkpoint).unwrap();
+        let broken = format!("{}state,S.renewal,1 2 3\n", before);
+        assert!(model.read_checkpoint(broken.as_bytes()).is_err());
+        let mut after = Vec::new();
+        model.write_checkpoint(&mut after).unwrap();
+        assert_eq!(String::from_utf8(after).unwrap(), before);
+    }
+}
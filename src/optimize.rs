@@ -0,0 +1,102 @@
+//! Derivative-free minimization for the fitting routines.
+
+use std::cell::Cell;
+
+/// The lowest point a minimization found.
+pub(crate) struct Minimum {
+    pub(crate) point: Vec<f64>,
+    pub(crate) evaluations: usize,
+    pub(crate) converged: bool,
+}
+
+/// Minimizes `f` by the Nelder-Mead simplex method, from a simplex around
+/// `start` with sides of `step` along each axis.
+///
+/// Stops once the values at the vertices of the simplex are within
+/// `tolerance` of each other, or after `max_evaluations` evaluations of
+/// `f`. NaN values are treated as infinitely bad.
+pub(crate) fn nelder_mead<F>(
+    mut f: F,
+    start: &[f64],
+    step: f64,
+    tolerance: f64,
+    max_evaluations: usize,
+) -> Minimum
+where
+    F: FnMut(&[f64]) -> f64,
+{
+    let n = start.len();
+    let evaluations = Cell::new(0);
+    let mut evaluate = |point: &[f64]| {
+        evaluations.set(evaluations.get() + 1);
+        let value = f(point);
+        if value.is_nan() {
+            f64::INFINITY
+        } else {
+            value
+        }
+    };
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((start.to_vec(), evaluate(start)));
+    for axis in 0..n {
+        let mut vertex = start.to_vec();
+        vertex[axis] += step;
+        let value = evaluate(&vertex);
+        simplex.push((vertex, value));
+    }
+    let mut converged = false;
+    while evaluations.get() < max_evaluations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (best, worst) = (simplex[0].1, simplex[n].1);
+        if (worst - best).abs() <= tolerance * (best.abs() + tolerance) {
+            converged = true;
+            break;
+        }
+        let centroid: Vec<f64> = (0..n)
+            .map(|i| simplex[..n].iter().map(|(v, _)| v[i]).sum::<f64>() / n as f64)
+            .collect();
+        let towards = |coefficient: f64| -> Vec<f64> {
+            (0..n)
+                .map(|i| centroid[i] + coefficient * (simplex[n].0[i] - centroid[i]))
+                .collect()
+        };
+        let reflected = towards(-1.0);
+        let reflected_value = evaluate(&reflected);
+        if reflected_value < simplex[0].1 {
+            let expanded = towards(-2.0);
+            let expanded_value = evaluate(&expanded);
+            simplex[n] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_value);
+        } else {
+            let coefficient = if reflected_value < simplex[n].1 {
+                -0.5
+            } else {
+                0.5
+            };
+            let contracted = towards(coefficient);
+            let contracted_value = evaluate(&contracted);
+            if contracted_value < reflected_value.min(simplex[n].1) {
+                simplex[n] = (contracted, contracted_value);
+            } else {
+                let best = simplex[0].0.clone();
+                for (vertex, value) in simplex.iter_mut().skip(1) {
+                    for (x, b) in vertex.iter_mut().zip(&best) {
+                        *x = b + 0.5 * (*x - b);
+                    }
+                    *value = evaluate(vertex);
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    Minimum {
+        point: simplex.swap_remove(0).0,
+        evaluations: evaluations.get(),
+        converged,
+    }
+}
@@ -0,0 +1,34 @@
+use crate::State;
+
+/// How an outbreak run to extinction with
+/// [`Model::run_to_extinction`](crate::Model::run_to_extinction) played out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outbreak {
+    duration: f64,
+    final_size: f64,
+    state: State,
+}
+
+impl Outbreak {
+    pub(crate) fn new(duration: f64, final_size: f64, state: State) -> Outbreak {
+        Outbreak {
+            duration,
+            final_size,
+            state,
+        }
+    }
+    /// Returns the number of ticks from the start of the run until the
+    /// infection died out.
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+    /// Returns how many individuals were ever infected, counting those
+    /// infected at the start of the run.
+    pub fn final_size(&self) -> f64 {
+        self.final_size
+    }
+    /// Returns the state of the model once the infection died out.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+}
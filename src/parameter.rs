@@ -0,0 +1,40 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// A named parameter of a behaviour in a model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    /// The bucket the behaviour is attached to.
+    pub bucket: String,
+    /// The name of the behaviour.
+    pub behaviour: String,
+    /// The name of the parameter within the behaviour.
+    pub key: String,
+    /// The current value of the parameter.
+    pub value: f64,
+}
+
+impl Parameter {
+    /// Returns the `bucket.behaviour.key` path used to address this
+    /// parameter with [`Model::set_parameter`](crate::Model::set_parameter).
+    pub fn path(&self) -> String {
+        format!("{}.{}.{}", self.bucket, self.behaviour, self.key)
+    }
+}
+
+/// An error setting a parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParameterError {
+    /// No behaviour has a parameter with the given key or path.
+    Unknown(String),
+}
+
+impl Display for ParameterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::Unknown(key) => write!(f, "unknown parameter `{}`", key),
+        }
+    }
+}
+
+impl Error for ParameterError {}
@@ -0,0 +1,471 @@
+use crate::image;
+use crate::raster::{Anchor, Canvas};
+use crate::SimulationResult;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 480.0;
+const LEFT: f64 = 72.0;
+const RIGHT: f64 = 144.0;
+const TOP: f64 = 24.0;
+const BOTTOM: f64 = 48.0;
+const TICKS: usize = 6;
+/// The most orders of magnitude a log axis spans below its largest value.
+const DECADES: f64 = 8.0;
+
+/// Line colours, cycled through when there are more series than colours.
+const PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// How values are mapped onto the vertical axis of a chart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scale {
+    /// Values are spaced evenly, from zero up to the largest value.
+    #[default]
+    Linear,
+    /// Values are spaced by order of magnitude. The axis spans at most
+    /// eight orders of magnitude, and values below it, including zero, are
+    /// drawn along its bottom.
+    Log,
+}
+
+impl Scale {
+    /// Returns the range of the axis and the values at which it is labelled.
+    fn axis(self, low: f64, high: f64) -> (f64, f64, Vec<f64>) {
+        match self {
+            Scale::Linear => {
+                let step = tick_step(high.max(f64::MIN_POSITIVE) / TICKS as f64);
+                let top = (high / step).ceil().max(1.0) * step;
+                let ticks = (0..)
+                    .map(|n| n as f64 * step)
+                    .take_while(|&tick| tick <= top + step / 2.0)
+                    .collect();
+                (0.0, top, ticks)
+            }
+            Scale::Log => {
+                let high = high.log10().ceil();
+                let low = low.log10().floor().max(high - DECADES);
+                let high = high.max(low + 1.0);
+                let ticks = (low as i32..=high as i32)
+                    .map(|exponent| 10f64.powi(exponent))
+                    .collect();
+                (low, high, ticks)
+            }
+        }
+    }
+    fn map(self, value: f64, min: f64) -> f64 {
+        match self {
+            Scale::Linear => value,
+            Scale::Log => value.max(min).log10(),
+        }
+    }
+}
+
+/// Returns a round number, one, two or five times a power of ten, at least
+/// as large as `rough`.
+fn tick_step(rough: f64) -> f64 {
+    let magnitude = 10f64.powf(rough.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|&step| step >= rough)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Formats an axis label compactly, switching to exponent notation for very
+/// large or small values.
+fn label(value: f64) -> String {
+    if value != 0.0 && (value.abs() >= 1e6 || value.abs() < 1e-3) {
+        format!("{:e}", value)
+    } else {
+        format!("{}", (value * 1e6).round() / 1e6)
+    }
+}
+
+/// Where everything on a chart of a result goes, shared by the SVG and
+/// raster renderers.
+struct Layout {
+    scale: Scale,
+    bottom: f64,
+    top: f64,
+    ticks: Vec<f64>,
+    start: f64,
+    end: f64,
+    span: f64,
+}
+
+impl Layout {
+    fn new(result: &SimulationResult, scale: Scale) -> Layout {
+        let values = || result.series().flat_map(|series| series.iter().cloned());
+        let high = values().filter(|v| v.is_finite()).fold(0.0, f64::max);
+        let low = values()
+            .filter(|&v| v.is_finite() && v > 0.0)
+            .fold(f64::INFINITY, f64::min);
+        let (low, high) = match scale {
+            Scale::Log if low.is_finite() => (low, high),
+            Scale::Log => (1.0, 10.0),
+            Scale::Linear => (0.0, high),
+        };
+        let (bottom, top, ticks) = scale.axis(low, high);
+        let start = result.times().first().cloned().unwrap_or(0.0);
+        let end = result.times().last().cloned().unwrap_or(0.0);
+        let span = if end > start { end - start } else { 1.0 };
+        Layout {
+            scale,
+            bottom,
+            top,
+            ticks,
+            start,
+            end,
+            span,
+        }
+    }
+    fn x(&self, time: f64) -> f64 {
+        LEFT + (time - self.start) / self.span * (WIDTH - LEFT - RIGHT)
+    }
+    fn y(&self, value: f64) -> f64 {
+        let value = self.scale.map(value, 10f64.powf(self.bottom));
+        let fraction = (value - self.bottom) / (self.top - self.bottom);
+        TOP + (1.0 - fraction) * (HEIGHT - TOP - BOTTOM)
+    }
+    /// Returns the times at which the time axis is labelled.
+    fn time_ticks(&self) -> Vec<f64> {
+        let step = tick_step(self.span / TICKS as f64);
+        let mut ticks = Vec::new();
+        let mut tick = (self.start / step).ceil() * step;
+        while tick <= self.end {
+            ticks.push(tick);
+            tick += step;
+        }
+        ticks
+    }
+    /// Returns the height at which the `index`th series is named in the
+    /// legend.
+    fn legend(index: usize) -> f64 {
+        TOP + 12.0 + index as f64 * 18.0
+    }
+}
+
+/// The colours of raster charts, by index: the background, the frame and
+/// text, the grid lines, then those of [`PALETTE`].
+const WHITE: u8 = 0;
+const BLACK: u8 = 1;
+const GREY: u8 = 2;
+
+fn colours() -> Vec<[u8; 3]> {
+    let hex = |colour: &str| {
+        let channel = |at: usize| u8::from_str_radix(&colour[at..at + 2], 16).expect("hex colour");
+        [channel(1), channel(3), channel(5)]
+    };
+    let mut colours = vec![[255, 255, 255], [0, 0, 0], hex("#e0e0e0")];
+    colours.extend(PALETTE.iter().map(|colour| hex(colour)));
+    colours
+}
+
+/// Returns the raster colour of the `index`th series.
+fn colour(index: usize) -> u8 {
+    GREY + 1 + (index % PALETTE.len()) as u8
+}
+
+/// Whether the file at `path` is named as being of the format `extension`.
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|found| found.eq_ignore_ascii_case(extension))
+}
+
+impl SimulationResult {
+    /// Renders the trajectory of every bucket as a line chart and writes it
+    /// to the file at `path`, replacing it if it exists. The chart is a PNG
+    /// image if `path` ends in `.png`, and SVG otherwise.
+    pub fn plot(&self, path: impl AsRef<Path>, scale: Scale) -> io::Result<()> {
+        let png = has_extension(path.as_ref(), "png");
+        let mut writer = BufWriter::new(File::create(path)?);
+        if png {
+            self.write_png(&mut writer, scale)?;
+        } else {
+            self.write_svg(&mut writer, scale)?;
+        }
+        writer.flush()
+    }
+    /// Renders the trajectory of every bucket as an SVG line chart of value
+    /// against time, with a legend naming each bucket.
+    pub fn write_svg<W: Write>(&self, writer: W, scale: Scale) -> io::Result<()> {
+        self.write_chart(writer, scale, None)
+    }
+    /// Renders the chart of [`write_svg`](SimulationResult::write_svg) as
+    /// an 800 by 480 pixel PNG image, for documents and tools that don't
+    /// take SVG. Text is drawn in a small bitmap font, and characters
+    /// outside printable ASCII as `?`.
+    pub fn write_png<W: Write>(&self, writer: W, scale: Scale) -> io::Result<()> {
+        let layout = Layout::new(self, scale);
+        let mut canvas = self.draw_axes(&layout);
+        let mut last = vec![None; self.series().count()];
+        self.draw_lines(&mut canvas, &layout, 0..self.times().len(), &mut last);
+        image::write_png(writer, &canvas, &colours())
+    }
+    /// Renders the trajectory of every bucket as an animated chart and
+    /// writes it to the file at `path`, replacing it if it exists. The
+    /// animation is a GIF if `path` ends in `.gif`, as
+    /// [`write_gif`](SimulationResult::write_gif) writes, and an SVG
+    /// otherwise, as
+    /// [`write_animated_svg`](SimulationResult::write_animated_svg) writes.
+    pub fn animate(
+        &self,
+        path: impl AsRef<Path>,
+        scale: Scale,
+        every: usize,
+        frame: Duration,
+    ) -> io::Result<()> {
+        let gif = has_extension(path.as_ref(), "gif");
+        let mut writer = BufWriter::new(File::create(path)?);
+        if gif {
+            self.write_gif(&mut writer, scale, every, frame)?;
+        } else {
+            self.write_animated_svg(&mut writer, scale, every, frame)?;
+        }
+        writer.flush()
+    }
+    /// Renders the trajectory of every bucket as a chart like
+    /// [`write_svg`](SimulationResult::write_svg) whose lines are drawn
+    /// over time, as the epidemic unfolded, for presentations and teaching.
+    ///
+    /// The animation has a frame for every `every`th recorded point, and
+    /// for the last, each shown for `frame`. It plays once in any browser
+    /// and stops on the whole chart. The axes are fixed from the start, so
+    /// the scale doesn't shift as the lines grow.
+    pub fn write_animated_svg<W: Write>(
+        &self,
+        writer: W,
+        scale: Scale,
+        every: usize,
+        frame: Duration,
+    ) -> io::Result<()> {
+        self.write_chart(writer, scale, Some((every.max(1), frame)))
+    }
+    /// Renders the animation of
+    /// [`write_animated_svg`](SimulationResult::write_animated_svg) as a
+    /// GIF, in the pixels of [`write_png`](SimulationResult::write_png),
+    /// for slides and other places SVG can't be shown.
+    ///
+    /// GIF measures time in hundredths of a second, so `frame` is rounded
+    /// to one, and each frame only stores what it adds to the one before.
+    pub fn write_gif<W: Write>(
+        &self,
+        writer: W,
+        scale: Scale,
+        every: usize,
+        frame: Duration,
+    ) -> io::Result<()> {
+        let layout = Layout::new(self, scale);
+        let delay = (frame.as_secs_f64() * 100.0)
+            .round()
+            .clamp(1.0, f64::from(u16::MAX)) as u16;
+        let mut canvas = self.draw_axes(&layout);
+        let mut gif = image::Gif::new(writer, canvas.width, canvas.height, &colours())?;
+        let mut last = vec![None; self.series().count()];
+        let points = self.times().len();
+        let mut ends: Vec<usize> = (0..points.saturating_sub(1))
+            .step_by(every.max(1))
+            .collect();
+        ends.push(points.saturating_sub(1));
+        let mut drawn = 0;
+        for end in ends {
+            let to = (end + 1).min(points);
+            self.draw_lines(&mut canvas, &layout, drawn..to, &mut last);
+            drawn = to;
+            gif.frame(&canvas, delay)?;
+        }
+        gif.finish()
+    }
+    /// Draws the frame, grid, labels and legend of a raster chart.
+    fn draw_axes(&self, layout: &Layout) -> Canvas {
+        let mut canvas = Canvas::new(WIDTH as usize, HEIGHT as usize, WHITE);
+        for &tick in &layout.ticks {
+            let position = layout.y(tick);
+            canvas.line((LEFT, position), (WIDTH - RIGHT, position), 1, GREY);
+            canvas.text(LEFT - 6.0, position, Anchor::End, &label(tick), BLACK);
+        }
+        for tick in layout.time_ticks() {
+            let position = layout.x(tick);
+            canvas.text(
+                position,
+                HEIGHT - BOTTOM + 14.0,
+                Anchor::Middle,
+                &label(tick),
+                BLACK,
+            );
+        }
+        let middle = LEFT + (WIDTH - LEFT - RIGHT) / 2.0;
+        canvas.text(middle, HEIGHT - 12.0, Anchor::Middle, "time", BLACK);
+        canvas.rectangle(
+            LEFT,
+            TOP,
+            WIDTH - LEFT - RIGHT,
+            HEIGHT - TOP - BOTTOM,
+            BLACK,
+        );
+        for (index, series) in self.series().enumerate() {
+            let row = Layout::legend(index);
+            canvas.fill(WIDTH - RIGHT + 12.0, row - 1.0, 20, 3, colour(index));
+            canvas.text(
+                WIDTH - RIGHT + 38.0,
+                row,
+                Anchor::Start,
+                series.name(),
+                BLACK,
+            );
+        }
+        canvas
+    }
+    /// Draws the lines of every series through the recorded points in
+    /// `points`, carrying on from the last finite point of each drawn so
+    /// far, which `last` holds.
+    fn draw_lines(
+        &self,
+        canvas: &mut Canvas,
+        layout: &Layout,
+        points: Range<usize>,
+        last: &mut [Option<(f64, f64)>],
+    ) {
+        for ((index, series), last) in self.series().enumerate().zip(last) {
+            for point in points.clone() {
+                let value = series.values()[point];
+                if !value.is_finite() {
+                    continue;
+                }
+                let here = (layout.x(self.times()[point]), layout.y(value));
+                canvas.line(last.unwrap_or(here), here, 2, colour(index));
+                *last = Some(here);
+            }
+        }
+    }
+    fn write_chart<W: Write>(
+        &self,
+        mut writer: W,
+        scale: Scale,
+        animation: Option<(usize, Duration)>,
+    ) -> io::Result<()> {
+        let layout = Layout::new(self, scale);
+        let plot_width = WIDTH - LEFT - RIGHT;
+        let plot_height = HEIGHT - TOP - BOTTOM;
+        let x = |time: f64| layout.x(time);
+        let y = |value: f64| layout.y(value);
+
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif" font-size="12">"#,
+            WIDTH, HEIGHT
+        )?;
+        writeln!(writer, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+        for &tick in &layout.ticks {
+            let position = y(tick);
+            writeln!(
+                writer,
+                r##"<line x1="{}" y1="{2:.2}" x2="{}" y2="{2:.2}" stroke="#e0e0e0"/>"##,
+                LEFT,
+                WIDTH - RIGHT,
+                position
+            )?;
+            writeln!(
+                writer,
+                r#"<text x="{}" y="{:.2}" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+                LEFT - 6.0,
+                position,
+                label(tick)
+            )?;
+        }
+        for tick in layout.time_ticks() {
+            writeln!(
+                writer,
+                r#"<text x="{:.2}" y="{}" text-anchor="middle">{}</text>"#,
+                x(tick),
+                HEIGHT - BOTTOM + 18.0,
+                label(tick)
+            )?;
+        }
+        writeln!(
+            writer,
+            r#"<text x="{}" y="{}" text-anchor="middle">time</text>"#,
+            LEFT + plot_width / 2.0,
+            HEIGHT - 8.0
+        )?;
+        writeln!(
+            writer,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            LEFT, TOP, plot_width, plot_height
+        )?;
+        if let Some((every, frame)) = animation {
+            let times = self.times();
+            // The lines are clipped to end at each frame's point in turn,
+            // and the last frame, at the last point, shows the whole chart.
+            let last = times.len().saturating_sub(1);
+            let mut widths: Vec<f64> = (0..last)
+                .step_by(every)
+                .map(|index| x(times[index]))
+                .collect();
+            widths.push(WIDTH);
+            let widths = widths
+                .iter()
+                .map(|width| format!("{:.2}", width))
+                .collect::<Vec<_>>();
+            writeln!(
+                writer,
+                r#"<clipPath id="reveal"><rect width="0" height="{}"><animate attributeName="width" values="{}" dur="{}s" calcMode="discrete" fill="freeze"/></rect></clipPath>"#,
+                HEIGHT,
+                widths.join(";"),
+                frame.as_secs_f64() * widths.len() as f64
+            )?;
+            writeln!(writer, r#"<g clip-path="url(#reveal)">"#)?;
+        }
+        for (index, series) in self.series().enumerate() {
+            let colour = PALETTE[index % PALETTE.len()];
+            write!(
+                writer,
+                r#"<polyline fill="none" stroke="{}" stroke-width="1.5" points=""#,
+                colour
+            )?;
+            for (&time, &value) in self.times().iter().zip(series) {
+                if value.is_finite() {
+                    write!(writer, "{:.2},{:.2} ", x(time), y(value))?;
+                }
+            }
+            writeln!(writer, r#""/>"#)?;
+        }
+        if animation.is_some() {
+            writeln!(writer, "</g>")?;
+        }
+        for (index, series) in self.series().enumerate() {
+            let colour = PALETTE[index % PALETTE.len()];
+            let row = Layout::legend(index);
+            writeln!(
+                writer,
+                r#"<line x1="{0}" y1="{2}" x2="{1}" y2="{2}" stroke="{3}" stroke-width="3"/>"#,
+                WIDTH - RIGHT + 12.0,
+                WIDTH - RIGHT + 32.0,
+                row,
+                colour
+            )?;
+            writeln!(
+                writer,
+                r#"<text x="{}" y="{}" dominant-baseline="middle">{}</text>"#,
+                WIDTH - RIGHT + 38.0,
+                row,
+                escape(series.name())
+            )?;
+        }
+        writeln!(writer, "</svg>")
+    }
+}
+
+/// Escapes the characters that are significant in SVG text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,391 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::linalg::cholesky;
+use crate::random::ln_gamma;
+use crate::{
+    Distribution, Ensemble, FitError, Model, Observations, ParameterError, ParameterSpace, Rng,
+};
+
+/// The scale of the adaptive Metropolis proposal relative to the posterior
+/// covariance, `2.38^2` divided by the number of parameters, which is
+/// optimal for Gaussian targets.
+const PROPOSAL_SCALE: f64 = 2.38 * 2.38;
+
+/// How many steps of burn-in pass before the proposal starts adapting to
+/// the covariance of the chain.
+const ADAPTATION_START: usize = 100;
+
+/// How observed counts are distributed around what a model predicts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Likelihood {
+    /// Poisson distributed, with the prediction as the mean, for counts
+    /// whose only noise is chance.
+    Poisson,
+    /// Negative binomial, with the prediction as the mean and the given
+    /// dispersion `k`, so the variance is `mean + mean^2 / k`. Smaller
+    /// dispersions allow the overdispersion usual in case reports.
+    NegativeBinomial(f64),
+}
+
+impl Likelihood {
+    /// Returns the log probability of observing `observed` when the model
+    /// predicts `predicted`.
+    pub fn log_probability(&self, observed: f64, predicted: f64) -> f64 {
+        let mean = predicted.max(1e-9);
+        let observed = observed.max(0.0);
+        match *self {
+            Likelihood::Poisson => observed * mean.ln() - mean - ln_gamma(observed + 1.0),
+            Likelihood::NegativeBinomial(k) => {
+                ln_gamma(observed + k) - ln_gamma(k) - ln_gamma(observed + 1.0)
+                    + k * (k / (k + mean)).ln()
+                    + observed * (mean / (k + mean)).ln()
+            }
+        }
+    }
+}
+
+/// Samples of a model's parameters drawn from their posterior distribution
+/// by [`Posterior::sample`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chain {
+    parameters: Vec<String>,
+    samples: Vec<Vec<f64>>,
+    log_posterior: Vec<f64>,
+    acceptance_rate: f64,
+}
+
+impl Chain {
+    /// Returns the path of every parameter sampled.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+    /// Returns every sample, each a value for every parameter.
+    pub fn samples(&self) -> &[Vec<f64>] {
+        &self.samples
+    }
+    /// Returns the log posterior density, up to a constant, of every
+    /// sample.
+    pub fn log_posterior(&self) -> &[f64] {
+        &self.log_posterior
+    }
+    /// Returns the fraction of proposals accepted after burn-in. Rates far
+    /// from about a quarter suggest the chain mixes poorly.
+    pub fn acceptance_rate(&self) -> f64 {
+        self.acceptance_rate
+    }
+    /// Returns the number of samples.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    /// Returns true if there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    /// Returns every sampled value of the parameter at `path`.
+    pub fn values(&self, path: &str) -> Option<Vec<f64>> {
+        let index = self.parameters.iter().position(|p| p == path)?;
+        Some(self.samples.iter().map(|sample| sample[index]).collect())
+    }
+    /// Returns the posterior mean of the parameter at `path`.
+    pub fn mean(&self, path: &str) -> Option<f64> {
+        let values = self.values(path)?;
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+    /// Returns the equal-tailed credible interval of the parameter at
+    /// `path` holding a fraction `level` of the posterior, such as `0.95`.
+    pub fn credible_interval(&self, path: &str, level: f64) -> Option<(f64, f64)> {
+        let mut values = self.values(path)?;
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let tail = (1.0 - level) / 2.0;
+        Some((quantile(&values, tail), quantile(&values, 1.0 - tail)))
+    }
+    /// Returns the sample with the highest posterior density.
+    pub fn maximum(&self) -> Option<&[f64]> {
+        self.log_posterior
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(index, _)| self.samples[index].as_slice())
+    }
+    /// Writes every sample to the file at `path` as CSV, replacing it if it
+    /// exists. See [`write_csv`](Chain::write_csv) for the layout.
+    pub fn to_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+    /// Writes every sample to `writer` as CSV, with a `sample` column, a
+    /// column for each parameter named by its path, and a `log_posterior`
+    /// column.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_field("sample")?;
+        for parameter in &self.parameters {
+            writer.write_field(parameter)?;
+        }
+        writer.write_record(["log_posterior"])?;
+        for (index, (sample, log_posterior)) in
+            self.samples.iter().zip(&self.log_posterior).enumerate()
+        {
+            writer.write_field(index.to_string())?;
+            for value in sample {
+                writer.write_field(value.to_string())?;
+            }
+            writer.write_record([log_posterior.to_string()])?;
+        }
+        writer.flush()
+    }
+}
+
+/// Linearly interpolated quantile of sorted values.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (index, fraction) = (position.floor() as usize, position.fract());
+    match sorted.get(index + 1) {
+        Some(next) => sorted[index] + fraction * (next - sorted[index]),
+        None => sorted[index],
+    }
+}
+
+/// The Bayesian calibration of a model's parameters to observations: a
+/// prior on each free parameter, updated by the likelihood of the
+/// observations given the model's predictions, and sampled by Markov chain
+/// Monte Carlo.
+///
+/// Free parameters are addressed by their
+/// [`path`](crate::Parameter::path). Every evaluation runs a fresh model
+/// built by `build`, as [`Calibration`](crate::Calibration) does.
+///
+/// ```no_run
+/// # use epidemic::{Distribution, Likelihood, Model, Observations, Posterior, Rng, Target};
+/// # fn build() -> Model { unimplemented!() }
+/// let cases = Observations::from_csv(
+///     Target::Incidence("Susceptible.infection".to_owned()),
+///     "cases.csv",
+///     "cases",
+/// )
+/// .unwrap();
+/// let posterior = Posterior::new(build, vec![cases])
+///     .prior("Susceptible.infection.beta", Distribution::Uniform(0.05, 2.0))
+///     .likelihood(Likelihood::NegativeBinomial(10.0));
+/// let chain = posterior.sample(5000, 2000, &mut Rng::new(1)).unwrap();
+/// let (low, high) = chain
+///     .credible_interval("Susceptible.infection.beta", 0.95)
+///     .unwrap();
+/// let trajectories = posterior.trajectories(&chain, 200, 100, &mut Rng::new(2)).unwrap();
+/// let (lower, upper) = trajectories.band("Infected", 0.025, 0.975).unwrap();
+/// ```
+pub struct Posterior<F> {
+    build: F,
+    observations: Vec<Observations>,
+    priors: ParameterSpace,
+    likelihood: Likelihood,
+}
+
+impl<F: Fn() -> Model> Posterior<F> {
+    /// Creates the posterior of the models `build` makes given every set of
+    /// `observations`, with no free parameters yet and a Poisson
+    /// likelihood.
+    pub fn new(build: F, observations: Vec<Observations>) -> Posterior<F> {
+        Posterior {
+            build,
+            observations,
+            priors: ParameterSpace::new(),
+            likelihood: Likelihood::Poisson,
+        }
+    }
+    /// Frees the parameter at `path`, with the prior belief that its value
+    /// is distributed as `prior`.
+    pub fn prior(mut self, path: &str, prior: Distribution) -> Posterior<F> {
+        self.priors = self.priors.parameter(path, prior);
+        self
+    }
+    /// Sets how observations are distributed around the model's
+    /// predictions.
+    pub fn likelihood(mut self, likelihood: Likelihood) -> Posterior<F> {
+        self.likelihood = likelihood;
+        self
+    }
+    /// Returns the free parameters with their priors.
+    pub fn priors(&self) -> &ParameterSpace {
+        &self.priors
+    }
+    /// Builds a model with the free parameters set to `values`, in the
+    /// order they were freed.
+    pub fn model(&self, values: &[f64]) -> Result<Model, FitError> {
+        let mut model = (self.build)();
+        for (path, value) in self.priors.paths().into_iter().zip(values) {
+            model.set_parameter(path, *value)?;
+        }
+        Ok(model)
+    }
+    /// Returns the log likelihood of every set of observations given a
+    /// model with the free parameters set to `values`.
+    pub fn log_likelihood(&self, values: &[f64]) -> Result<f64, FitError> {
+        let mut total = 0.0;
+        for observations in &self.observations {
+            let predicted = observations.predict(&mut self.model(values)?)?;
+            total += observations
+                .values()
+                .iter()
+                .zip(predicted)
+                .map(|(observed, predicted)| self.likelihood.log_probability(*observed, predicted))
+                .sum::<f64>();
+        }
+        Ok(total)
+    }
+    /// Returns the log posterior density, up to a constant, of the free
+    /// parameters taking `values`: the log prior plus the log likelihood.
+    pub fn log_posterior(&self, values: &[f64]) -> Result<f64, FitError> {
+        let prior: f64 = self
+            .priors
+            .distributions()
+            .iter()
+            .zip(values)
+            .map(|(prior, value)| prior.log_density(*value))
+            .sum();
+        if prior == f64::NEG_INFINITY {
+            return Ok(prior);
+        }
+        Ok(prior + self.log_likelihood(values)?)
+    }
+    /// Draws `samples` samples from the posterior by adaptive Metropolis,
+    /// after discarding `burn_in` steps.
+    ///
+    /// The chain starts from the values the built model gives the free
+    /// parameters, or the median of their prior where that has no density.
+    /// During burn-in the Gaussian proposal adapts to the covariance of the
+    /// chain so far, and it is then held fixed while samples are drawn, so
+    /// burn-in should be long enough both to forget the start and to learn
+    /// the shape of the posterior.
+    pub fn sample(&self, samples: usize, burn_in: usize, rng: &mut Rng) -> Result<Chain, FitError> {
+        if self.observations.iter().all(Observations::is_empty) {
+            return Err(FitError::NoObservations);
+        }
+        let priors = self.priors.distributions();
+        let d = priors.len();
+        let known = (self.build)().parameters();
+        let mut current = self
+            .priors
+            .paths()
+            .iter()
+            .zip(&priors)
+            .map(|(path, prior)| {
+                let value = known
+                    .iter()
+                    .find(|parameter| parameter.path() == *path)
+                    .ok_or_else(|| ParameterError::Unknown((*path).to_owned()))?
+                    .value;
+                Ok(if prior.log_density(value).is_finite() {
+                    value
+                } else {
+                    prior.quantile(0.5)
+                })
+            })
+            .collect::<Result<Vec<f64>, FitError>>()?;
+        let mut current_density = self.log_posterior(&current)?;
+        // Until it adapts, the proposal steps a tenth of each prior's
+        // interquartile range.
+        let mut covariance = vec![vec![0.0; d]; d];
+        for (i, prior) in priors.iter().enumerate() {
+            let spread = (prior.quantile(0.75) - prior.quantile(0.25)) / 10.0;
+            covariance[i][i] = spread * spread;
+        }
+        let mut proposal = cholesky(&covariance).unwrap_or_else(|| covariance.clone());
+        let (mut mean, mut scatter) = (vec![0.0; d], vec![vec![0.0; d]; d]);
+        let mut chain = Chain {
+            parameters: self
+                .priors
+                .paths()
+                .iter()
+                .map(|p| (*p).to_owned())
+                .collect(),
+            samples: Vec::with_capacity(samples),
+            log_posterior: Vec::with_capacity(samples),
+            acceptance_rate: 0.0,
+        };
+        let mut accepted = 0;
+        for step in 0..burn_in + samples {
+            let z: Vec<f64> = (0..d).map(|_| rng.normal()).collect();
+            let candidate: Vec<f64> = (0..d)
+                .map(|i| current[i] + (0..=i).map(|j| proposal[i][j] * z[j]).sum::<f64>())
+                .collect();
+            let density = self.log_posterior(&candidate).unwrap_or(f64::NEG_INFINITY);
+            let density = if density.is_nan() {
+                f64::NEG_INFINITY
+            } else {
+                density
+            };
+            if rng.uniform().ln() < density - current_density {
+                current = candidate;
+                current_density = density;
+                if step >= burn_in {
+                    accepted += 1;
+                }
+            }
+            if step < burn_in {
+                // Welford's online update of the mean and covariance.
+                let count = (step + 1) as f64;
+                let delta: Vec<f64> = (0..d).map(|i| current[i] - mean[i]).collect();
+                for i in 0..d {
+                    mean[i] += delta[i] / count;
+                }
+                for i in 0..d {
+                    for j in 0..d {
+                        scatter[i][j] += delta[i] * (current[j] - mean[j]);
+                    }
+                }
+                if step >= ADAPTATION_START {
+                    let adapted: Vec<Vec<f64>> = (0..d)
+                        .map(|i| {
+                            (0..d)
+                                .map(|j| {
+                                    let jitter = if i == j {
+                                        1e-10 * covariance[i][i]
+                                    } else {
+                                        0.0
+                                    };
+                                    PROPOSAL_SCALE / d as f64 * scatter[i][j] / step as f64 + jitter
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    if let Some(factor) = cholesky(&adapted) {
+                        proposal = factor;
+                    }
+                }
+            } else {
+                chain.samples.push(current.clone());
+                chain.log_posterior.push(current_density);
+            }
+        }
+        chain.acceptance_rate = accepted as f64 / samples.max(1) as f64;
+        Ok(chain)
+    }
+    /// Runs the model for `ticks` ticks at `draws` samples spread evenly
+    /// through `chain`, each run with a fresh seed drawn from `rng`, giving
+    /// the posterior distribution of trajectories. Credible intervals for
+    /// each compartment over time are then the
+    /// [`band`](Ensemble::band)s of the ensemble.
+    pub fn trajectories(
+        &self,
+        chain: &Chain,
+        draws: usize,
+        ticks: u64,
+        rng: &mut Rng,
+    ) -> Result<Ensemble, FitError> {
+        let (mut runs, mut seeds) = (Vec::with_capacity(draws), Vec::with_capacity(draws));
+        for draw in 0..draws.min(chain.len()) {
+            let sample = &chain.samples[draw * chain.len() / draws.min(chain.len())];
+            let seed = rng.next_u64();
+            let mut model = self.model(sample)?;
+            model.set_seed(seed);
+            runs.push(model.simulate(ticks));
+            seeds.push(seed);
+        }
+        Ok(Ensemble::new(runs, seeds))
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt::{self, Display, Formatter};
+use std::slice;
+
+use crate::sampling::normal_quantile;
+
+/// The relative rise in squared error below which a profile counts as
+/// flat.
+const FLAT_TOLERANCE: f64 = 1e-6;
+
+/// How well observations pin down a parameter, judged from its profile
+/// likelihood.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Identifiability {
+    /// The profile rises past the confidence threshold on both sides of
+    /// the estimate, so the confidence interval is bounded.
+    Identifiable,
+    /// The profile rises, but stays below the threshold on at least one
+    /// side within the parameter's bounds, so the observations are too
+    /// few or too noisy to bound it there.
+    Practical,
+    /// The profile is flat: other parameters make up for any change in
+    /// this one, so no observations of this kind could pin it down.
+    Structural,
+}
+
+impl Display for Identifiability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifiability::Identifiable => write!(f, "identifiable"),
+            Identifiability::Practical => write!(f, "practically non-identifiable"),
+            Identifiability::Structural => write!(f, "structurally non-identifiable"),
+        }
+    }
+}
+
+/// The profile likelihood of one fitted parameter: the best squared error
+/// the other parameters can reach with it held at each of a range of
+/// values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterProfile {
+    parameter: String,
+    estimate: f64,
+    values: Vec<f64>,
+    sse: Vec<f64>,
+}
+
+impl ParameterProfile {
+    pub(crate) fn new(
+        parameter: String,
+        estimate: f64,
+        values: Vec<f64>,
+        sse: Vec<f64>,
+    ) -> ParameterProfile {
+        ParameterProfile {
+            parameter,
+            estimate,
+            values,
+            sse,
+        }
+    }
+    /// Returns the path of the parameter.
+    pub fn parameter(&self) -> &str {
+        &self.parameter
+    }
+    /// Returns the fitted value of the parameter.
+    pub fn estimate(&self) -> f64 {
+        self.estimate
+    }
+    /// Returns every value the parameter was held at, in increasing order,
+    /// including the estimate.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+    /// Returns the smallest sum of squared residuals found with the
+    /// parameter held at each of its [`values`](ParameterProfile::values).
+    pub fn sse(&self) -> &[f64] {
+        &self.sse
+    }
+}
+
+/// The profile likelihood of every parameter of a fit, found by
+/// [`Calibration::profile`](crate::Calibration::profile), with the
+/// confidence interval and identifiability it implies for each. Displaying
+/// it draws them as a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    profiles: Vec<ParameterProfile>,
+    observations: usize,
+    level: f64,
+    minimum: f64,
+}
+
+impl Profile {
+    pub(crate) fn new(profiles: Vec<ParameterProfile>, observations: usize, level: f64) -> Profile {
+        // A profile may find a better fit than the one it started from, so
+        // everything is measured from the best point seen anywhere.
+        let minimum = profiles
+            .iter()
+            .flat_map(|profile| profile.sse.iter().copied())
+            .fold(f64::INFINITY, f64::min);
+        Profile {
+            profiles,
+            observations,
+            level,
+            minimum,
+        }
+    }
+    /// Returns the profile of every parameter, in the order they were
+    /// freed.
+    pub fn profiles(&self) -> &[ParameterProfile] {
+        &self.profiles
+    }
+    /// Iterates over the profile of every parameter.
+    pub fn iter(&self) -> slice::Iter<'_, ParameterProfile> {
+        self.profiles.iter()
+    }
+    /// Returns the profile of the parameter at `path`.
+    pub fn get(&self, path: &str) -> Option<&ParameterProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.parameter == path)
+    }
+    /// Returns the confidence level of the intervals.
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+    /// Returns how far the profile must rise above its minimum to leave
+    /// the confidence interval: the `level` quantile of the chi-squared
+    /// distribution with one degree of freedom, such as 3.84 at 95%.
+    pub fn threshold(&self) -> f64 {
+        normal_quantile((1.0 + self.level) / 2.0).powi(2)
+    }
+    /// Returns how far the profile of `profile` rises above the best fit at
+    /// each of its values, as twice the drop in log likelihood.
+    pub fn deviance(&self, profile: &ParameterProfile) -> Vec<f64> {
+        let minimum = self.minimum.max(f64::MIN_POSITIVE);
+        profile
+            .sse
+            .iter()
+            .map(|sse| self.observations as f64 * (sse / minimum).ln())
+            .collect()
+    }
+    /// Returns the lower and upper ends of the confidence interval of the
+    /// parameter at `path`, each `None` where the profile never rises past
+    /// the [`threshold`](Profile::threshold) within the parameter's bounds.
+    pub fn interval(&self, path: &str) -> Option<(Option<f64>, Option<f64>)> {
+        let profile = self.get(path)?;
+        let deviance = self.deviance(profile);
+        let threshold = self.threshold();
+        let best = (0..deviance.len()).min_by(|a, b| deviance[*a].total_cmp(&deviance[*b]))?;
+        // Interpolates linearly between the last value inside the interval
+        // and the first outside it.
+        let crossing = |inside: usize, outside: usize| {
+            let (d0, d1) = (deviance[inside], deviance[outside]);
+            let (v0, v1) = (profile.values[inside], profile.values[outside]);
+            if d1.is_finite() {
+                v0 + (threshold - d0) / (d1 - d0) * (v1 - v0)
+            } else {
+                v1
+            }
+        };
+        let lower = (0..best)
+            .rev()
+            .find(|i| deviance[*i] > threshold)
+            .map(|i| crossing(i + 1, i));
+        let upper = (best + 1..deviance.len())
+            .find(|i| deviance[*i] > threshold)
+            .map(|i| crossing(i - 1, i));
+        Some((lower, upper))
+    }
+    /// Returns how well the observations pin down the parameter at `path`.
+    pub fn identifiability(&self, path: &str) -> Option<Identifiability> {
+        let profile = self.get(path)?;
+        let highest = profile.sse.iter().copied().fold(0.0, f64::max);
+        if highest <= self.minimum * (1.0 + FLAT_TOLERANCE) {
+            return Some(Identifiability::Structural);
+        }
+        Some(match self.interval(path)? {
+            (Some(_), Some(_)) => Identifiability::Identifiable,
+            _ => Identifiability::Practical,
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a Profile {
+    type Item = &'a ParameterProfile;
+    type IntoIter = slice::Iter<'a, ParameterProfile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bound =
+            |value: Option<f64>| value.map_or_else(|| "-".to_owned(), |v| format!("{:.6}", v));
+        let rows: Vec<[String; 5]> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                let (lower, upper) = self.interval(&profile.parameter).unwrap_or((None, None));
+                let identifiability = self
+                    .identifiability(&profile.parameter)
+                    .map_or_else(String::new, |i| i.to_string());
+                [
+                    profile.parameter.clone(),
+                    format!("{:.6}", profile.estimate),
+                    bound(lower),
+                    bound(upper),
+                    identifiability,
+                ]
+            })
+            .collect();
+        let lower = format!("{}% lower", self.level * 100.0);
+        let upper = format!("{}% upper", self.level * 100.0);
+        let header = [
+            "Parameter",
+            "Estimate",
+            lower.as_str(),
+            upper.as_str(),
+            "Identifiability",
+        ];
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].chars().count())
+                    .chain([header[column].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |f: &mut Formatter<'_>, cells: [&str; 5]| {
+            writeln!(
+                f,
+                "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {}",
+                cells[0],
+                cells[1],
+                cells[2],
+                cells[3],
+                cells[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+            )
+        };
+        line(f, header)?;
+        for row in &rows {
+            line(f, [&row[0], &row[1], &row[2], &row[3], &row[4]])?;
+        }
+        Ok(())
+    }
+}
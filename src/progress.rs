@@ -0,0 +1,91 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// The width of the bar, in characters.
+const WIDTH: usize = 30;
+
+/// How often the bar is redrawn at most.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A progress bar on stderr for work of a known size, showing the percent
+/// complete, the rate and the estimated time remaining.
+///
+/// Nothing is drawn unless stderr is a terminal, so output redirected to a
+/// file or pipe isn't cluttered with it.
+pub(crate) struct Progress {
+    unit: &'static str,
+    total: u64,
+    done: u64,
+    start: Instant,
+    drawn: Option<Instant>,
+    visible: bool,
+}
+
+impl Progress {
+    /// Starts a bar for `total` pieces of work, each called a `unit`.
+    pub(crate) fn new(total: u64, unit: &'static str) -> Progress {
+        Progress {
+            unit,
+            total,
+            done: 0,
+            start: Instant::now(),
+            drawn: None,
+            visible: io::stderr().is_terminal(),
+        }
+    }
+    /// Records that `amount` more pieces of work are done.
+    pub(crate) fn advance(&mut self, amount: u64) {
+        self.done = (self.done + amount).min(self.total);
+        let due = self
+            .drawn
+            .is_none_or(|drawn| drawn.elapsed() >= REDRAW_INTERVAL);
+        if self.visible && (due || self.done == self.total) {
+            self.draw();
+        }
+    }
+    /// Erases the bar so that something else can be printed in its place.
+    /// It's drawn again on the next [`advance`](Progress::advance).
+    pub(crate) fn clear(&mut self) {
+        if self.drawn.take().is_some() {
+            let _ = write!(io::stderr(), "\r\x1b[K");
+        }
+    }
+    fn draw(&mut self) {
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        };
+        let filled = (fraction * WIDTH as f64) as usize;
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            self.done as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = if rate > 0.0 {
+            format!("{:.0}s", (self.total - self.done) as f64 / rate)
+        } else {
+            "?".to_owned()
+        };
+        let _ = write!(
+            io::stderr(),
+            "\r[{}{}] {:>3.0}% {:.0} {}/s ETA {}\x1b[K",
+            "#".repeat(filled),
+            " ".repeat(WIDTH - filled),
+            fraction * 100.0,
+            rate,
+            self.unit,
+            eta
+        );
+        self.drawn = Some(Instant::now());
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.drawn.is_some() {
+            let _ = writeln!(io::stderr());
+        }
+    }
+}
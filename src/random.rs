@@ -0,0 +1,193 @@
+use std::f64::consts::PI;
+
+use crate::Context;
+
+/// A small, fast, seedable pseudorandom number generator.
+///
+/// This is xoshiro256**, seeded through SplitMix64, so the same seed gives
+/// the same stream of numbers on every platform.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Rng {
+        let mut seed = seed;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [next(), next(), next(), next()],
+        }
+    }
+    pub(crate) fn state(&self) -> [u64; 4] {
+        self.state
+    }
+    pub(crate) fn from_state(state: [u64; 4]) -> Rng {
+        Rng { state }
+    }
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+    /// Returns a standard normally distributed number.
+    pub fn normal(&mut self) -> f64 {
+        let u = 1.0 - self.uniform();
+        let v = self.uniform();
+        (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+    }
+    /// Returns an exponentially distributed number with the given rate.
+    pub fn exponential(&mut self, rate: f64) -> f64 {
+        -(1.0 - self.uniform()).ln() / rate
+    }
+    /// Returns a Poisson distributed count with mean `lambda`, or zero if
+    /// `lambda` is not a positive finite number.
+    pub fn poisson(&mut self, lambda: f64) -> u64 {
+        if !lambda.is_finite() || lambda <= 0.0 {
+            return 0;
+        }
+        if lambda < 30.0 {
+            let limit = (-lambda).exp();
+            let mut count = 0;
+            let mut product = self.uniform();
+            while product > limit {
+                count += 1;
+                product *= self.uniform();
+            }
+            return count;
+        }
+        // Hörmann's transformed rejection with squeeze (PTRS).
+        let slam = lambda.sqrt();
+        let loglam = lambda.ln();
+        let b = 0.931 + 2.53 * slam;
+        let a = -0.059 + 0.02483 * b;
+        let inv_alpha = 1.1239 + 1.1328 / (b - 3.4);
+        let vr = 0.9277 - 3.6224 / (b - 2.0);
+        loop {
+            let u = self.uniform() - 0.5;
+            let v = self.uniform();
+            let us = 0.5 - u.abs();
+            let k = ((2.0 * a / us + b) * u + lambda + 0.43).floor();
+            if us >= 0.07 && v <= vr {
+                return k as u64;
+            }
+            if k < 0.0 || (us < 0.013 && v > us) {
+                continue;
+            }
+            if v.ln() + inv_alpha.ln() - (a / (us * us) + b).ln()
+                <= -lambda + k * loglam - ln_gamma(k + 1.0)
+            {
+                return k as u64;
+            }
+        }
+    }
+    /// Returns the number of successes in `n` independent trials which each
+    /// succeed with probability `p`, which is clamped to `[0, 1]`.
+    pub fn binomial(&mut self, n: u64, p: f64) -> u64 {
+        let p = if p.is_nan() { 0.0 } else { p.clamp(0.0, 1.0) };
+        if p > 0.5 {
+            return n - self.binomial(n, 1.0 - p);
+        }
+        if n == 0 || p == 0.0 {
+            return 0;
+        }
+        let q = 1.0 - p;
+        if n as f64 * p < 10.0 {
+            // Inversion, walking up the cumulative distribution.
+            let ratio = p / q;
+            let mut probability = q.powf(n as f64);
+            let mut u = self.uniform();
+            let mut k = 0;
+            while u > probability && k < n {
+                u -= probability;
+                probability *= ratio * (n - k) as f64 / (k + 1) as f64;
+                k += 1;
+            }
+            return k;
+        }
+        // Hörmann's transformed rejection with squeeze (BTRS).
+        let n_f = n as f64;
+        let spq = (n_f * p * q).sqrt();
+        let b = 1.15 + 2.53 * spq;
+        let a = -0.0873 + 0.0248 * b + 0.01 * p;
+        let c = n_f * p + 0.5;
+        let vr = 0.92 - 4.2 / b;
+        let alpha = (2.83 + 5.1 / b) * spq;
+        let lpq = (p / q).ln();
+        let m = ((n_f + 1.0) * p).floor();
+        let h = ln_gamma(m + 1.0) + ln_gamma(n_f - m + 1.0);
+        loop {
+            let u = self.uniform() - 0.5;
+            let v = self.uniform();
+            let us = 0.5 - u.abs();
+            let k = ((2.0 * a / us + b) * u + c).floor();
+            if k < 0.0 || k > n_f {
+                continue;
+            }
+            if us >= 0.07 && v <= vr {
+                return k as u64;
+            }
+            let v = (v * alpha / (a / (us * us) + b)).ln();
+            if v <= h - ln_gamma(k + 1.0) - ln_gamma(n_f - k + 1.0) + (k - m) * lpq {
+                return k as u64;
+            }
+        }
+    }
+}
+
+/// Returns how many of `n` individuals move with probability `p` each,
+/// drawn from a binomial distribution with the generator of `context` if
+/// `stochastic` is set and otherwise the expected `n * p`.
+pub(crate) fn binomial_or_mean(stochastic: bool, context: &Context, n: f64, p: f64) -> f64 {
+    if stochastic {
+        context.rng().binomial(n.max(0.0).floor() as u64, p) as f64
+    } else {
+        n * p
+    }
+}
+
+/// The natural logarithm of the gamma function, by the Lanczos
+/// approximation.
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
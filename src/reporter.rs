@@ -0,0 +1,115 @@
+use std::io::{self, Stdout, Write};
+
+use crate::{SimulationResult, State};
+
+/// A destination for the states of a model as it runs, driven by
+/// [`Model::run_with`](crate::Model::run_with).
+///
+/// The built-in reporters print to a [table](TableReporter) or
+/// [CSV](CsvReporter), or [discard](NullReporter) everything. Writing
+/// reporters panic if writing fails, as `print!` does.
+pub trait Reporter {
+    /// Called with the state of the model at time `time` every time it's
+    /// reported during a run.
+    fn on_step(&mut self, time: f64, state: &State);
+    /// Called once a run is done, with every state that was reported.
+    fn on_finish(&mut self, result: &SimulationResult) {
+        let _ = result;
+    }
+}
+
+/// Prints every state reported to stdout as a row of a table, with a
+/// column per bucket, under a header row printed with the first.
+pub struct TableReporter {
+    stdout: Stdout,
+    header: bool,
+}
+
+impl TableReporter {
+    /// Creates a reporter printing to stdout.
+    pub fn new() -> TableReporter {
+        TableReporter {
+            stdout: io::stdout(),
+            header: false,
+        }
+    }
+}
+
+impl Default for TableReporter {
+    fn default() -> Self {
+        TableReporter::new()
+    }
+}
+
+/// The width of each column of a [`TableReporter`].
+const COLUMN_WIDTH: usize = 12;
+
+impl Reporter for TableReporter {
+    fn on_step(&mut self, time: f64, state: &State) {
+        let mut stdout = self.stdout.lock();
+        if !self.header {
+            write!(stdout, "{:>width$}", "time", width = COLUMN_WIDTH)
+                .expect("failed to write to stdout");
+            for (name, _) in state.iter() {
+                write!(stdout, " {:>width$}", name, width = COLUMN_WIDTH)
+                    .expect("failed to write to stdout");
+            }
+            writeln!(stdout).expect("failed to write to stdout");
+            self.header = true;
+        }
+        write!(stdout, "{:>width$.2}", time, width = COLUMN_WIDTH)
+            .expect("failed to write to stdout");
+        for (_, quantity) in state.iter() {
+            write!(stdout, " {:>width$.2}", quantity, width = COLUMN_WIDTH)
+                .expect("failed to write to stdout");
+        }
+        writeln!(stdout).expect("failed to write to stdout");
+    }
+}
+
+/// Writes every state reported as a row of CSV, with a header row of
+/// `time` followed by the name of every bucket written with the first.
+///
+/// Rows are flushed as they're written, so the output can be followed
+/// while the model runs.
+pub struct CsvReporter<W: Write> {
+    writer: csv::Writer<W>,
+    header: bool,
+}
+
+impl<W: Write> CsvReporter<W> {
+    /// Creates a reporter writing to `writer`.
+    pub fn new(writer: W) -> CsvReporter<W> {
+        CsvReporter {
+            writer: csv::Writer::from_writer(writer),
+            header: false,
+        }
+    }
+    fn write(&mut self, time: f64, state: &State) -> csv::Result<()> {
+        if !self.header {
+            self.writer.write_field("time")?;
+            self.writer
+                .write_record(state.iter().map(|(name, _)| name))?;
+            self.header = true;
+        }
+        self.writer.write_field(time.to_string())?;
+        self.writer
+            .write_record(state.iter().map(|(_, quantity)| quantity.to_string()))?;
+        Ok(self.writer.flush()?)
+    }
+}
+
+impl<W: Write> Reporter for CsvReporter<W> {
+    fn on_step(&mut self, time: f64, state: &State) {
+        self.write(time, state).expect("failed to write CSV");
+    }
+}
+
+/// Discards everything reported, for runs only wanted for their effect on
+/// the model.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullReporter;
+
+impl Reporter for NullReporter {
+    fn on_step(&mut self, _: f64, _: &State) {}
+}
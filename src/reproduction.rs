@@ -0,0 +1,189 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::context::Shared;
+use crate::linalg::invert;
+use crate::solver;
+use crate::Bucket;
+
+/// An error computing a reproduction number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReproductionError {
+    /// No bucket has the given name.
+    Unknown(String),
+    /// No compartments were declared infected.
+    NoInfected,
+    /// Infection never leaves the infected compartments, so each infected
+    /// individual causes unboundedly many infections.
+    Singular,
+}
+
+impl Display for ReproductionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReproductionError::Unknown(name) => write!(f, "unknown compartment `{}`", name),
+            ReproductionError::NoInfected => write!(f, "no compartments were declared infected"),
+            ReproductionError::Singular => write!(
+                f,
+                "infected individuals never leave the infected compartments"
+            ),
+        }
+    }
+}
+
+impl Error for ReproductionError {}
+
+/// Computes the next-generation matrix `F V^-1` of the buckets at
+/// `infected` in their current state, leaving every bucket as it was.
+///
+/// Every transfer into an infected bucket from outside them is a new
+/// infection and goes into `F`; every other transfer out of or between
+/// infected buckets is a transition and goes into `V`. The column of each
+/// infected bucket is how those rates change per individual when it is
+/// emptied, which for mass-action infection gives exactly the familiar
+/// `beta S / N` however large the bucket, or by a finite difference if it
+/// is already empty.
+pub(crate) fn next_generation(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+    infected: &[usize],
+) -> Result<Vec<Vec<f64>>, ReproductionError> {
+    if infected.is_empty() {
+        return Err(ReproductionError::NoInfected);
+    }
+    let start: Vec<f64> = buckets.iter().map(Bucket::get).collect();
+    let (f0, v0) = rates(buckets, shared, time, dt, infected, &start);
+    let m = infected.len();
+    let (mut f, mut v) = (vec![vec![0.0; m]; m], vec![vec![0.0; m]; m]);
+    for (column, &j) in infected.iter().enumerate() {
+        let mut shifted = start.clone();
+        let h = if start[j] > 0.0 {
+            shifted[j] = 0.0;
+            -start[j]
+        } else {
+            shifted[j] = 1e-6;
+            1e-6
+        };
+        let (f1, v1) = rates(buckets, shared, time, dt, infected, &shifted);
+        for row in 0..m {
+            f[row][column] = ((f1[row] - f0[row]) / h).max(0.0);
+            v[row][column] = (v1[row] - v0[row]) / h;
+        }
+    }
+    buckets
+        .iter()
+        .zip(&start)
+        .for_each(|(bucket, quantity)| bucket.set(*quantity));
+    let v = invert(v).ok_or(ReproductionError::Singular)?;
+    Ok((0..m)
+        .map(|row| {
+            (0..m)
+                .map(|column| {
+                    (0..m)
+                        .map(|k| f[row][k] * v[k][column])
+                        .sum::<f64>()
+                        .max(0.0)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Returns the rate of new infections into each infected bucket, and the
+/// net rate of every other transfer out of each, per tick, with the buckets
+/// holding `quantities`.
+fn rates(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+    infected: &[usize],
+    quantities: &[f64],
+) -> (Vec<f64>, Vec<f64>) {
+    buckets
+        .iter()
+        .zip(quantities)
+        .for_each(|(bucket, quantity)| bucket.set(*quantity));
+    let position = |bucket: &Option<Bucket>| {
+        bucket
+            .as_ref()
+            .and_then(|bucket| infected.iter().position(|&i| buckets[i].ptr_eq(bucket)))
+    };
+    let (mut f, mut v) = (vec![0.0; infected.len()], vec![0.0; infected.len()]);
+    for transfer in solver::transfers(buckets, shared.trial(), time, dt) {
+        let rate = transfer.amount.max(0.0) / dt;
+        match (position(&transfer.from), position(&transfer.to)) {
+            (None, Some(to)) => f[to] += rate,
+            (Some(from), Some(to)) => {
+                v[from] += rate;
+                v[to] -= rate;
+            }
+            (Some(from), None) => v[from] += rate,
+            (None, None) => {}
+        }
+    }
+    (f, v)
+}
+
+/// Returns the spectral radius of a nonnegative square matrix, its
+/// dominant eigenvalue, by power iteration.
+pub(crate) fn spectral_radius(matrix: &[Vec<f64>]) -> f64 {
+    let n = matrix.len();
+    // Shifting by the identity leaves the dominant eigenvector alone but
+    // keeps the iteration from cycling on matrices with a periodic
+    // structure, such as those of host-vector models.
+    let mut vector = vec![1.0 / n as f64; n];
+    let mut radius = 0.0;
+    for _ in 0..10_000 {
+        let next: Vec<f64> = (0..n)
+            .map(|i| vector[i] + (0..n).map(|j| matrix[i][j] * vector[j]).sum::<f64>())
+            .collect();
+        let norm: f64 = next.iter().sum();
+        if !(norm > 0.0 && norm.is_finite()) {
+            return 0.0;
+        }
+        let estimate = norm - 1.0;
+        vector = next.iter().map(|x| x / norm).collect();
+        if (estimate - radius).abs() <= 1e-12 * estimate.abs().max(1.0) {
+            return estimate;
+        }
+        radius = estimate;
+    }
+    radius
+}
+
+/// Solves the final-size relation `ln(S0 / S) = R (S0 + I0 - S) / S0` for
+/// the number `S` left susceptible once an epidemic dies out, starting from
+/// `susceptible` susceptibles and `infected` infected individuals with the
+/// reproduction number `reproduction`.
+///
+/// In terms of the fraction `x = S / S0` left, the relation is convex in
+/// `x` with a single root below one whenever anyone is infected or the
+/// reproduction number is above one, so bisection finds it reliably.
+pub(crate) fn final_susceptible(reproduction: f64, susceptible: f64, infected: f64) -> f64 {
+    if !(susceptible > 0.0 && reproduction > 0.0) {
+        return susceptible.max(0.0);
+    }
+    let scale = reproduction * (1.0 + infected.max(0.0) / susceptible);
+    let relation = |x: f64| -x.ln() - scale + reproduction * x;
+    let mut high = if infected > 0.0 {
+        1.0
+    } else if reproduction > 1.0 {
+        1.0 / reproduction
+    } else {
+        return susceptible;
+    };
+    // The relation is positive here, since `-ln x` is exactly `scale`.
+    let mut low = (-scale).exp();
+    for _ in 0..200 {
+        let middle = 0.5 * (low + high);
+        if relation(middle) > 0.0 {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    0.5 * (low + high) * susceptible
+}
@@ -0,0 +1,354 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::slice;
+
+use crate::Summary;
+
+/// The recorded trajectory of a single bucket.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSeries {
+    name: String,
+    values: Vec<f64>,
+}
+
+impl TimeSeries {
+    pub(crate) fn new(name: String) -> TimeSeries {
+        TimeSeries {
+            name,
+            values: Vec::new(),
+        }
+    }
+    pub(crate) fn push(&mut self, value: f64) {
+        self.values.push(value);
+    }
+    /// Returns the name of the bucket this series was recorded from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the recorded values, one per time point.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+    /// Returns the value recorded at the `index`th time point.
+    pub fn get(&self, index: usize) -> Option<f64> {
+        self.values.get(index).cloned()
+    }
+    /// Returns the last recorded value.
+    pub fn last(&self) -> Option<f64> {
+        self.values.last().cloned()
+    }
+    /// Returns the largest recorded value.
+    pub fn max(&self) -> Option<f64> {
+        self.values
+            .iter()
+            .cloned()
+            .fold(None, |max, value| match max {
+                Some(max) if max >= value => Some(max),
+                _ => Some(value),
+            })
+    }
+    /// Returns the number of recorded time points.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    /// Returns true if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    /// Iterates over the recorded values.
+    pub fn iter(&self) -> slice::Iter<'_, f64> {
+        self.values.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TimeSeries {
+    type Item = &'a f64;
+    type IntoIter = slice::Iter<'a, f64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The trajectories of every bucket in a model over a simulation.
+///
+/// Every series shares the same time points, so the `n`th value of each
+/// series was recorded at `times()[n]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimulationResult {
+    times: Vec<f64>,
+    series: Vec<TimeSeries>,
+}
+
+impl SimulationResult {
+    pub(crate) fn new(names: Vec<String>) -> SimulationResult {
+        SimulationResult {
+            times: Vec::new(),
+            series: names.into_iter().map(TimeSeries::new).collect(),
+        }
+    }
+    pub(crate) fn record(&mut self, time: f64, values: impl IntoIterator<Item = f64>) {
+        self.times.push(time);
+        self.series
+            .iter_mut()
+            .zip(values)
+            .for_each(|(series, value)| series.push(value));
+    }
+    /// Returns the time of every recorded point.
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+    /// Returns the series recorded for the bucket called `name`.
+    pub fn get(&self, name: &str) -> Option<&TimeSeries> {
+        self.series.iter().find(|series| series.name == name)
+    }
+    /// Iterates over the series of every bucket, in the order the buckets
+    /// were added to the model.
+    pub fn series(&self) -> slice::Iter<'_, TimeSeries> {
+        self.series.iter()
+    }
+    /// Returns the names of the recorded buckets.
+    pub fn names(&self) -> Vec<&str> {
+        self.series.iter().map(TimeSeries::name).collect()
+    }
+    /// Returns the value of every bucket at the `index`th time point.
+    pub fn row(&self, index: usize) -> Option<Vec<f64>> {
+        self.series.iter().map(|series| series.get(index)).collect()
+    }
+    /// Iterates over every time point together with the value of each
+    /// bucket at that time.
+    pub fn rows(&self) -> impl Iterator<Item = (f64, Vec<f64>)> + '_ {
+        self.times
+            .iter()
+            .enumerate()
+            .map(move |(index, &time)| (time, self.row(index).unwrap_or_default()))
+    }
+    /// Returns the value of the bucket called `name` at `time`,
+    /// interpolating linearly between the recorded time points on either
+    /// side, or `None` if there is no such bucket or `time` is outside the
+    /// recorded span.
+    pub fn value_at(&self, name: &str, time: f64) -> Option<f64> {
+        let series = self.get(name)?;
+        let (index, fraction) = self.locate(time)?;
+        let before = series.get(index)?;
+        if fraction == 0.0 {
+            return Some(before);
+        }
+        let after = series.get(index + 1)?;
+        Some(before + (after - before) * fraction)
+    }
+    /// Returns the trajectories at each of `times`, interpolating linearly
+    /// between recorded time points, so results recorded on an uneven grid
+    /// can be reported on a uniform one. Times outside the recorded span
+    /// are left out.
+    pub fn resample(&self, times: &[f64]) -> SimulationResult {
+        let mut result = SimulationResult::new(
+            self.series
+                .iter()
+                .map(|series| series.name.clone())
+                .collect(),
+        );
+        times.iter().for_each(|&time| {
+            if let Some((index, fraction)) = self.locate(time) {
+                result.record(
+                    time,
+                    self.series.iter().map(|series| {
+                        let before = series.values[index];
+                        match series.values.get(index + 1) {
+                            Some(after) if fraction > 0.0 => before + (after - before) * fraction,
+                            _ => before,
+                        }
+                    }),
+                );
+            }
+        });
+        result
+    }
+    /// Returns the trajectories every `interval` from the first recorded
+    /// time to the last, interpolating linearly between recorded points.
+    /// The last recorded point is always kept, even where it falls short of
+    /// a whole interval after the one before it, so the final state is
+    /// never lost.
+    pub fn uniform(&self, interval: f64) -> SimulationResult {
+        let (first, last) = match (self.times.first(), self.times.last()) {
+            (Some(first), Some(last)) if interval > 0.0 => (*first, *last),
+            _ => return self.resample(&[]),
+        };
+        let count = ((last - first) / interval + 1e-9).floor() as usize;
+        let mut times: Vec<f64> = (0..=count)
+            .map(|index| (first + index as f64 * interval).min(last))
+            .collect();
+        if times
+            .last()
+            .is_some_and(|&time| last - time > 1e-9 * interval)
+        {
+            times.push(last);
+        }
+        self.resample(&times)
+    }
+    /// Finds the recorded point at or before `time` and how far `time` is
+    /// towards the next one, as a fraction of the gap between them.
+    fn locate(&self, time: f64) -> Option<(usize, f64)> {
+        let first = *self.times.first()?;
+        let last = *self.times.last()?;
+        if time.is_nan() || time < first || time > last {
+            return None;
+        }
+        let next = self.times.partition_point(|&recorded| recorded <= time);
+        let index = next - 1;
+        match self.times.get(next) {
+            Some(&after) if after > self.times[index] => Some((
+                index,
+                (time - self.times[index]) / (after - self.times[index]),
+            )),
+            _ => Some((index, 0.0)),
+        }
+    }
+    /// Returns the trajectories as fractions of the total across every
+    /// series at each time point, so the values at each time point add up
+    /// to one. Time points where the total is zero are left as NaN.
+    ///
+    /// The total is recomputed at every time point, so fractions account
+    /// for births and deaths. Sinks are part of the total, so in a model
+    /// with deaths the total stays the number of people who have ever been
+    /// alive in it.
+    pub fn normalized(&self) -> SimulationResult {
+        let mut normalized =
+            SimulationResult::new(self.names().into_iter().map(String::from).collect());
+        for (time, row) in self.rows() {
+            let total: f64 = row.iter().sum();
+            normalized.record(time, row.iter().map(|value| value / total));
+        }
+        normalized
+    }
+    /// Returns the running total of every series over the recorded time
+    /// points, so that an incidence recorded per tick, as by
+    /// [`Model::simulate_flows`](crate::Model::simulate_flows), becomes the
+    /// cumulative incidence to date.
+    pub fn cumulative(&self) -> SimulationResult {
+        let mut cumulative =
+            SimulationResult::new(self.names().into_iter().map(String::from).collect());
+        let mut totals = vec![0.0; self.series.len()];
+        for (time, row) in self.rows() {
+            totals
+                .iter_mut()
+                .zip(&row)
+                .for_each(|(total, value)| *total += value);
+            cumulative.record(time, totals.iter().copied());
+        }
+        cumulative
+    }
+    /// Returns this run minus `other` at every time point, for every bucket
+    /// recorded in both, such as the number of infections averted at each
+    /// time when this is a baseline and `other` the same model under an
+    /// intervention. Returns `None` unless both runs were recorded at the
+    /// same times.
+    pub fn difference(&self, other: &SimulationResult) -> Option<SimulationResult> {
+        self.combine(other, |value, other| value - other)
+    }
+    /// Returns this run divided by `other` at every time point, for every
+    /// bucket recorded in both, as [`difference`](SimulationResult::difference)
+    /// does. Points where `other` is zero are infinite, or NaN where both
+    /// are.
+    pub fn ratio(&self, other: &SimulationResult) -> Option<SimulationResult> {
+        self.combine(other, |value, other| value / other)
+    }
+    /// Returns the running total of this run minus `other`, as the
+    /// [`cumulative`](SimulationResult::cumulative) of their
+    /// [`difference`](SimulationResult::difference). For incidence, its last
+    /// row is the total number of cases averted over the run.
+    pub fn cumulative_difference(&self, other: &SimulationResult) -> Option<SimulationResult> {
+        Some(self.difference(other)?.cumulative())
+    }
+    /// Combines every series with the series of the same name in `other`,
+    /// point by point.
+    fn combine<F>(&self, other: &SimulationResult, combine: F) -> Option<SimulationResult>
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        if self.times != other.times {
+            return None;
+        }
+        let pairs: Vec<(&TimeSeries, &TimeSeries)> = self
+            .series
+            .iter()
+            .filter_map(|series| Some((series, other.get(&series.name)?)))
+            .collect();
+        let mut result = SimulationResult::new(
+            pairs
+                .iter()
+                .map(|(series, _)| series.name.clone())
+                .collect(),
+        );
+        for (index, time) in self.times.iter().enumerate() {
+            result.record(
+                *time,
+                pairs
+                    .iter()
+                    .map(|(series, other)| combine(series.values[index], other.values[index])),
+            );
+        }
+        Some(result)
+    }
+    /// Summarizes the run as an epidemic spreading out of the compartment
+    /// called `susceptible` into those named in `infected`, with the peak of
+    /// every compartment, the attack rate, the duration and the early
+    /// doubling time. Returns `None` if a compartment isn't in the result or
+    /// nothing was recorded.
+    ///
+    /// Durations and times are in the units of the recorded times, and the
+    /// duration counts down to fewer than one infected individual, so
+    /// summarize the result itself rather than its
+    /// [`normalized`](SimulationResult::normalized) fractions.
+    pub fn summary(&self, susceptible: &str, infected: &[&str]) -> Option<Summary> {
+        Summary::new(self, susceptible, infected)
+    }
+    /// Writes the trajectories to the file at `path` as CSV, replacing it
+    /// if it exists. See [`write_csv`](SimulationResult::write_csv) for the
+    /// layout.
+    pub fn to_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+    /// Writes the trajectories to `writer` as CSV, with a header row of
+    /// `time` followed by the name of every bucket, then one row per time
+    /// point.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_field("time")?;
+        writer.write_record(self.series.iter().map(TimeSeries::name))?;
+        for (time, row) in self.rows() {
+            writer.write_field(time.to_string())?;
+            writer.write_record(row.iter().map(f64::to_string))?;
+        }
+        writer.flush()
+    }
+    /// Returns the number of recorded time points.
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+    /// Returns true if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimulationResult;
+
+    fn result(ticks: usize) -> SimulationResult {
+        let mut result = SimulationResult::new(vec!["A".to_owned()]);
+        (0..=ticks).for_each(|tick| result.record(tick as f64, Some(tick as f64)));
+        result
+    }
+
+    #[test]
+    fn uniform_keeps_the_final_state() {
+        let uniform = result(10).uniform(3.0);
+        assert_eq!(uniform.times(), &[0.0, 3.0, 6.0, 9.0, 10.0]);
+        assert_eq!(uniform.get("A").unwrap().last(), Some(10.0));
+        let uniform = result(9).uniform(3.0);
+        assert_eq!(uniform.times(), &[0.0, 3.0, 6.0, 9.0]);
+    }
+}
@@ -0,0 +1,427 @@
+use std::f64::consts::PI;
+
+use crate::Rng;
+
+/// The distribution of values a parameter is drawn from when sampling a
+/// [`ParameterSpace`], or the prior belief about it in a
+/// [`Posterior`](crate::Posterior).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    /// Uniform between a lower and an upper bound.
+    Uniform(f64, f64),
+    /// Uniform in logarithm between a positive lower and upper bound, for
+    /// parameters spanning orders of magnitude.
+    LogUniform(f64, f64),
+    /// Normal with a mean and standard deviation.
+    Normal(f64, f64),
+    /// Log-normal, the exponential of a normal with a mean and standard
+    /// deviation.
+    LogNormal(f64, f64),
+    /// Triangular between a lower bound, a mode and an upper bound.
+    Triangular(f64, f64, f64),
+}
+
+impl Distribution {
+    /// Returns the value below which a fraction `p` of the distribution
+    /// lies, mapping a uniform number in `[0, 1]` onto the distribution.
+    pub fn quantile(&self, p: f64) -> f64 {
+        match *self {
+            Distribution::Uniform(low, high) => low + p * (high - low),
+            Distribution::LogUniform(low, high) => (low.ln() + p * (high / low).ln()).exp(),
+            Distribution::Normal(mean, sd) => mean + sd * normal_quantile(p),
+            Distribution::LogNormal(mean, sd) => (mean + sd * normal_quantile(p)).exp(),
+            Distribution::Triangular(low, mode, high) => {
+                let split = (mode - low) / (high - low);
+                if p < split {
+                    low + (p * (high - low) * (mode - low)).sqrt()
+                } else {
+                    high - ((1.0 - p) * (high - low) * (high - mode)).sqrt()
+                }
+            }
+        }
+    }
+    /// Draws a value from the distribution.
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        self.quantile(rng.uniform())
+    }
+    /// Returns the natural logarithm of the probability density at `x`,
+    /// which is negative infinity outside the distribution's support.
+    pub fn log_density(&self, x: f64) -> f64 {
+        let normal = |z: f64, sd: f64| -0.5 * z * z - sd.ln() - 0.5 * (2.0 * PI).ln();
+        match *self {
+            Distribution::Uniform(low, high) if (low..=high).contains(&x) => -(high - low).ln(),
+            Distribution::LogUniform(low, high) if (low..=high).contains(&x) => {
+                -x.ln() - (high / low).ln().ln()
+            }
+            Distribution::Normal(mean, sd) => normal((x - mean) / sd, sd),
+            Distribution::LogNormal(mean, sd) if x > 0.0 => {
+                normal((x.ln() - mean) / sd, sd) - x.ln()
+            }
+            Distribution::Triangular(low, mode, high) if low <= x && x < mode => {
+                (2.0 * (x - low) / ((high - low) * (mode - low))).ln()
+            }
+            Distribution::Triangular(low, mode, high) if mode <= x && x <= high => {
+                (2.0 * (high - x) / ((high - low) * (high - mode))).ln()
+            }
+            _ => f64::NEG_INFINITY,
+        }
+    }
+}
+
+/// The quantile function of the standard normal distribution, by Acklam's
+/// rational approximation, accurate to about 1e-9.
+pub(crate) fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.02425;
+    let polynomial = |coefficients: &[f64], x: f64| coefficients.iter().fold(0.0, |y, c| y * x + c);
+    if p <= 0.0 {
+        f64::NEG_INFINITY
+    } else if p >= 1.0 {
+        f64::INFINITY
+    } else if p < LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        polynomial(&C, q) / (polynomial(&D, q) * q + 1.0)
+    } else if p <= 1.0 - LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        polynomial(&A, r) * q / (polynomial(&B, r) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -polynomial(&C, q) / (polynomial(&D, q) * q + 1.0)
+    }
+}
+
+/// A set of parameters, addressed by their
+/// [`path`](crate::Parameter::path), each with the distribution of values
+/// it may take, to be explored by drawing points which fill the space
+/// evenly rather than by a grid.
+///
+/// The points drawn can be run with [`Sweep::list`](crate::Sweep::list):
+///
+/// ```no_run
+/// # use epidemic::{Distribution, Model, ParameterSpace, Rng, Sweep};
+/// # fn build() -> Model { unimplemented!() }
+/// let space = ParameterSpace::new()
+///     .parameter("Susceptible.infection.beta", Distribution::Uniform(0.2, 0.6))
+///     .parameter("Infected.diffusion.probability", Distribution::Uniform(0.05, 0.3));
+/// let points = space.latin_hypercube(100, &mut Rng::new(1));
+/// let sweep = Sweep::list(build, &space.paths(), points, 200, 0).unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterSpace {
+    parameters: Vec<(String, Distribution)>,
+}
+
+impl ParameterSpace {
+    /// Creates a space with no parameters.
+    pub fn new() -> ParameterSpace {
+        ParameterSpace::default()
+    }
+    /// Adds the parameter at `path`, with values drawn from `distribution`.
+    pub fn parameter(mut self, path: &str, distribution: Distribution) -> ParameterSpace {
+        self.parameters.push((path.to_owned(), distribution));
+        self
+    }
+    /// Returns the path of every parameter, in the order they were added.
+    pub fn paths(&self) -> Vec<&str> {
+        self.parameters
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect()
+    }
+    /// Returns the distribution of every parameter, in the order they were
+    /// added.
+    pub fn distributions(&self) -> Vec<Distribution> {
+        self.parameters.iter().map(|(_, d)| *d).collect()
+    }
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.parameters.len()
+    }
+    /// Returns true if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.parameters.is_empty()
+    }
+    /// Maps a point in the unit hypercube onto the space, through the
+    /// quantile function of each parameter's distribution.
+    pub fn map(&self, unit: &[f64]) -> Vec<f64> {
+        self.parameters
+            .iter()
+            .zip(unit)
+            .map(|((_, distribution), u)| distribution.quantile(*u))
+            .collect()
+    }
+    /// Draws `samples` points independently at random.
+    pub fn random(&self, samples: usize, rng: &mut Rng) -> Vec<Vec<f64>> {
+        (0..samples)
+            .map(|_| {
+                let unit: Vec<f64> = (0..self.len()).map(|_| rng.uniform()).collect();
+                self.map(&unit)
+            })
+            .collect()
+    }
+    /// Draws `samples` points by Latin hypercube sampling: the range of
+    /// every parameter is cut into `samples` intervals of equal
+    /// probability, and each interval is sampled exactly once, paired at
+    /// random with the intervals of the other parameters.
+    pub fn latin_hypercube(&self, samples: usize, rng: &mut Rng) -> Vec<Vec<f64>> {
+        let mut unit = vec![Vec::with_capacity(self.len()); samples];
+        for _ in 0..self.len() {
+            let mut strata: Vec<usize> = (0..samples).collect();
+            for i in (1..samples).rev() {
+                strata.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+            }
+            for (point, stratum) in unit.iter_mut().zip(strata) {
+                point.push((stratum as f64 + rng.uniform()) / samples as f64);
+            }
+        }
+        unit.iter().map(|point| self.map(point)).collect()
+    }
+    /// Draws the first `samples` points of a [`Sobol`] sequence, skipping
+    /// its initial point at the corner of the space. Unlike the other
+    /// methods this is deterministic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more parameters than [`Sobol::MAX_DIMENSIONS`].
+    pub fn sobol(&self, samples: usize) -> Vec<Vec<f64>> {
+        Sobol::new(self.len())
+            .skip(1)
+            .take(samples)
+            .map(|point| self.map(&point))
+            .collect()
+    }
+    /// Draws the Saltelli design from which
+    /// [`Sensitivity::sobol`](crate::Sensitivity::sobol) estimates Sobol
+    /// indices: `samples` blocks of points, each of a point `A`, a point
+    /// `B`, then for every parameter in turn `A` with that parameter's value
+    /// taken from `B`. `A` and `B` are the two halves of a [`Sobol`]
+    /// sequence of twice as many dimensions as there are parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if twice the number of parameters is more than
+    /// [`Sobol::MAX_DIMENSIONS`].
+    pub fn saltelli(&self, samples: usize) -> Vec<Vec<f64>> {
+        let k = self.len();
+        let mut points = Vec::with_capacity(samples * (k + 2));
+        for unit in Sobol::new(2 * k).skip(1).take(samples) {
+            let (a, b) = unit.split_at(k);
+            points.push(self.map(a));
+            points.push(self.map(b));
+            for i in 0..k {
+                let mut mixed = a.to_vec();
+                mixed[i] = b[i];
+                points.push(self.map(&mixed));
+            }
+        }
+        points
+    }
+}
+
+/// Primitive polynomials and initial direction numbers for the Sobol
+/// sequence, from Joe and Kuo's `new-joe-kuo-6.21201`, each as the degree,
+/// the coefficients packed into an integer and the initial numbers.
+const DIRECTIONS: [(u32, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+const BITS: usize = 32;
+
+/// A Sobol low-discrepancy sequence of points in the unit hypercube, which
+/// covers it more evenly than random points do.
+///
+/// The sequence starts at the origin and is infinite, though only the first
+/// 2^32 points are distinct.
+#[derive(Clone, Debug)]
+pub struct Sobol {
+    directions: Vec<[u32; BITS]>,
+    point: Vec<u32>,
+    index: u64,
+}
+
+impl Sobol {
+    /// The largest number of dimensions supported.
+    pub const MAX_DIMENSIONS: usize = DIRECTIONS.len() + 1;
+
+    /// Creates a sequence of points with `dimensions` coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dimensions` is more than [`Sobol::MAX_DIMENSIONS`].
+    pub fn new(dimensions: usize) -> Sobol {
+        assert!(
+            dimensions <= Sobol::MAX_DIMENSIONS,
+            "Sobol sequences support at most {} dimensions",
+            Sobol::MAX_DIMENSIONS
+        );
+        let mut directions = Vec::with_capacity(dimensions);
+        if dimensions > 0 {
+            let mut first = [0; BITS];
+            for (bit, direction) in first.iter_mut().enumerate() {
+                *direction = 1 << (BITS - 1 - bit);
+            }
+            directions.push(first);
+        }
+        for &(degree, coefficients, initial) in DIRECTIONS.iter().take(dimensions.saturating_sub(1))
+        {
+            let degree = degree as usize;
+            let mut v = [0; BITS];
+            for bit in 0..BITS {
+                v[bit] = if bit < degree {
+                    initial[bit] << (BITS - 1 - bit)
+                } else {
+                    let mut direction = v[bit - degree] ^ (v[bit - degree] >> degree);
+                    for k in 1..degree {
+                        if (coefficients >> (degree - 1 - k)) & 1 == 1 {
+                            direction ^= v[bit - k];
+                        }
+                    }
+                    direction
+                };
+            }
+            directions.push(v);
+        }
+        Sobol {
+            point: vec![0; dimensions],
+            directions,
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for Sobol {
+    type Item = Vec<f64>;
+
+    fn next(&mut self) -> Option<Vec<f64>> {
+        let scale = 1.0 / (1u64 << BITS) as f64;
+        let point = self.point.iter().map(|x| *x as f64 * scale).collect();
+        // Gray code ordering: the next point flips the direction number of
+        // the lowest zero bit of the current index.
+        let bit = (self.index.trailing_ones() as usize).min(BITS - 1);
+        for (x, v) in self.point.iter_mut().zip(&self.directions) {
+            *x ^= v[bit];
+        }
+        self.index += 1;
+        Some(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Distribution, ParameterSpace, Sobol};
+    use crate::Rng;
+
+    /// Returns how many of `values` fall into each of `bins` equal
+    /// intervals of `[0, 1)`.
+    fn histogram(values: impl Iterator<Item = f64>, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        values.for_each(|value| counts[(value * bins as f64) as usize] += 1);
+        counts
+    }
+
+    fn space(dimensions: usize) -> ParameterSpace {
+        (0..dimensions).fold(ParameterSpace::new(), |space, i| {
+            space.parameter(&format!("p{}", i), Distribution::Uniform(0.0, 1.0))
+        })
+    }
+
+    #[test]
+    fn latin_hypercubes_sample_every_stratum_once() {
+        let points = space(4).latin_hypercube(50, &mut Rng::new(1));
+        assert_eq!(points.len(), 50);
+        for dimension in 0..4 {
+            let values = points.iter().map(|point| point[dimension]);
+            assert_eq!(histogram(values, 50), vec![1; 50]);
+        }
+    }
+
+    #[test]
+    fn sobol_marginals_are_stratified() {
+        // Every one-dimensional projection of the first 2^m points puts
+        // exactly one point in each interval of width 2^-m.
+        let points: Vec<Vec<f64>> = Sobol::new(Sobol::MAX_DIMENSIONS).take(256).collect();
+        for dimension in 0..Sobol::MAX_DIMENSIONS {
+            let values = points.iter().map(|point| point[dimension]);
+            assert_eq!(histogram(values, 256), vec![1; 256], "{}", dimension);
+        }
+        let first: Vec<f64> = Sobol::new(1).take(4).map(|point| point[0]).collect();
+        assert_eq!(first, [0.0, 0.5, 0.75, 0.25]);
+    }
+
+    #[test]
+    fn samples_follow_their_distributions() {
+        let space = ParameterSpace::new()
+            .parameter("uniform", Distribution::Uniform(2.0, 4.0))
+            .parameter("normal", Distribution::Normal(1.0, 0.5));
+        let samples = [
+            space.sobol(1023),
+            space.latin_hypercube(1000, &mut Rng::new(7)),
+        ];
+        for points in &samples {
+            let n = points.len() as f64;
+            let mean = |i: usize| points.iter().map(|point| point[i]).sum::<f64>() / n;
+            let sd = |i: usize| {
+                let mean = mean(i);
+                (points
+                    .iter()
+                    .map(|point| (point[i] - mean).powi(2))
+                    .sum::<f64>()
+                    / n)
+                    .sqrt()
+            };
+            assert!((mean(0) - 3.0).abs() < 0.01, "{}", mean(0));
+            assert!((sd(0) - 2.0 / 12f64.sqrt()).abs() < 0.01, "{}", sd(0));
+            assert!((mean(1) - 1.0).abs() < 0.01, "{}", mean(1));
+            assert!((sd(1) - 0.5).abs() < 0.01, "{}", sd(1));
+            assert!(points.iter().all(|point| (2.0..4.0).contains(&point[0])));
+        }
+    }
+}
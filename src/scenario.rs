@@ -0,0 +1,211 @@
+use std::fmt::{self, Display, Formatter};
+use std::iter;
+
+use crate::{Model, Output, ParameterError, SimulationResult};
+
+/// A change to a model that parameters can't express.
+type Change = dyn Fn(&mut Model);
+
+/// A way of changing a model to compare against its baseline, such as an
+/// intervention that lowers transmission, for
+/// [`Model::compare`](crate::Model::compare).
+pub struct Scenario {
+    name: String,
+    parameters: Vec<(String, f64)>,
+    changes: Vec<Box<Change>>,
+}
+
+impl Scenario {
+    /// Creates a scenario called `name` that doesn't change anything yet.
+    pub fn new(name: &str) -> Scenario {
+        Scenario {
+            name: name.to_owned(),
+            parameters: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+    /// Sets the parameter at `path` to `value` in this scenario.
+    pub fn parameter(mut self, path: &str, value: f64) -> Scenario {
+        self.parameters.push((path.to_owned(), value));
+        self
+    }
+    /// Changes the model with `change` in this scenario, after its
+    /// parameters are set, for changes parameters can't express, such as
+    /// [scheduling](crate::Model::schedule) an importation of cases.
+    pub fn with<F>(mut self, change: F) -> Scenario
+    where
+        F: Fn(&mut Model) + 'static,
+    {
+        self.changes.push(Box::new(change));
+        self
+    }
+    /// Returns the name of the scenario.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the path and value of every parameter the scenario sets.
+    pub fn parameters(&self) -> &[(String, f64)] {
+        &self.parameters
+    }
+    pub(crate) fn apply(&self, model: &mut Model) -> Result<(), ParameterError> {
+        for (path, value) in &self.parameters {
+            model.set_parameter(path, *value)?;
+        }
+        self.changes.iter().for_each(|change| change(model));
+        Ok(())
+    }
+}
+
+/// The trajectories of a model's baseline and of each of a set of
+/// scenarios, found by [`Model::compare`](crate::Model::compare).
+///
+/// Displaying it draws a table of [`Output`]s side by side, each scenario
+/// with its difference from the baseline, such as the deaths averted by an
+/// intervention. The outputs are the peak and final value of every
+/// compartment unless others are chosen with
+/// [`output`](Comparison::output).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    baseline: SimulationResult,
+    scenarios: Vec<(String, SimulationResult)>,
+    outputs: Vec<Output>,
+}
+
+impl Comparison {
+    /// The name the baseline goes by in tables and trajectories.
+    pub const BASELINE: &'static str = "Baseline";
+
+    pub(crate) fn new(
+        baseline: SimulationResult,
+        scenarios: Vec<(String, SimulationResult)>,
+    ) -> Comparison {
+        Comparison {
+            baseline,
+            scenarios,
+            outputs: Vec::new(),
+        }
+    }
+    /// Adds `output` to the outputs the comparison is displayed with.
+    pub fn output(mut self, output: Output) -> Comparison {
+        self.outputs.push(output);
+        self
+    }
+    /// Returns the outputs the comparison is displayed with.
+    pub fn outputs(&self) -> Vec<Output> {
+        if !self.outputs.is_empty() {
+            return self.outputs.clone();
+        }
+        let names = self.baseline.names();
+        names
+            .iter()
+            .map(|name| Output::Peak((*name).to_owned()))
+            .chain(names.iter().map(|name| Output::Final((*name).to_owned())))
+            .collect()
+    }
+    /// Returns the trajectory of the baseline.
+    pub fn baseline(&self) -> &SimulationResult {
+        &self.baseline
+    }
+    /// Returns the name and trajectory of every scenario, in the order they
+    /// were given.
+    pub fn scenarios(&self) -> &[(String, SimulationResult)] {
+        &self.scenarios
+    }
+    /// Returns the trajectory of the scenario called `name`, or of the
+    /// baseline for [`BASELINE`](Comparison::BASELINE).
+    pub fn get(&self, name: &str) -> Option<&SimulationResult> {
+        if name == Comparison::BASELINE {
+            return Some(&self.baseline);
+        }
+        self.scenarios
+            .iter()
+            .find(|(scenario, _)| scenario == name)
+            .map(|(_, result)| result)
+    }
+    /// Measures `output` of the scenario called `name`.
+    pub fn measure(&self, name: &str, output: &Output) -> Option<f64> {
+        Some(output.measure(self.get(name)?))
+    }
+    /// Returns how much lower `output` is in the scenario called `name`
+    /// than in the baseline, such as the deaths averted when it is the
+    /// final number of dead.
+    pub fn averted(&self, name: &str, output: &Output) -> Option<f64> {
+        Some(output.measure(&self.baseline) - self.measure(name, output)?)
+    }
+    /// Returns the baseline minus the scenario called `name` at every time
+    /// point, as by [`SimulationResult::difference`].
+    pub fn difference(&self, name: &str) -> Option<SimulationResult> {
+        self.baseline.difference(self.get(name)?)
+    }
+    /// Returns the trajectory of the compartment called `name` in the
+    /// baseline and in every scenario, as a result with a series for each
+    /// named after it, to chart or write out together.
+    pub fn trajectories(&self, name: &str) -> Option<SimulationResult> {
+        let runs: Vec<(&str, &SimulationResult)> =
+            iter::once((Comparison::BASELINE, &self.baseline))
+                .chain(
+                    self.scenarios
+                        .iter()
+                        .map(|(name, run)| (name.as_str(), run)),
+                )
+                .collect();
+        let series = runs
+            .iter()
+            .map(|(_, run)| run.get(name))
+            .collect::<Option<Vec<_>>>()?;
+        let mut result = SimulationResult::new(
+            runs.iter()
+                .map(|(scenario, _)| (*scenario).to_owned())
+                .collect(),
+        );
+        for (index, time) in self.baseline.times().iter().enumerate() {
+            result.record(
+                *time,
+                series
+                    .iter()
+                    .map(|series| series.get(index).unwrap_or(f64::NAN)),
+            );
+        }
+        Some(result)
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut header = vec!["Output".to_owned(), Comparison::BASELINE.to_owned()];
+        header.extend(self.scenarios.iter().map(|(name, _)| name.clone()));
+        let rows: Vec<Vec<String>> = self
+            .outputs()
+            .iter()
+            .map(|output| {
+                let baseline = output.measure(&self.baseline);
+                let mut row = vec![output.to_string(), format!("{:.2}", baseline)];
+                row.extend(self.scenarios.iter().map(|(_, run)| {
+                    let value = output.measure(run);
+                    format!("{:.2} ({:+.2})", value, value - baseline)
+                }));
+                row
+            })
+            .collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| {
+                rows.iter()
+                    .chain(iter::once(&header))
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in iter::once(&header).chain(&rows) {
+            let mut cells = row.iter().zip(&widths);
+            if let Some((cell, width)) = cells.next() {
+                write!(f, "{:<width$}", cell, width = width)?;
+            }
+            for (cell, width) in cells {
+                write!(f, "  {:>width$}", cell, width = width)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::cell::Cell;
+
+/// How a [`Schedule`] gets from one value to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Each value holds until the time of the next one.
+    Step,
+    /// Values change linearly between consecutive times.
+    Linear,
+}
+
+/// A value that can be linearly interpolated.
+pub trait Interpolate {
+    /// Returns the value `fraction` of the way from `self` to `other`.
+    fn interpolate(&self, other: &Self, fraction: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, fraction: f64) -> Self {
+        self + (other - self) * fraction
+    }
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, fraction: f64) -> Self {
+        self + (other - self) * fraction as f32
+    }
+}
+
+/// A value that changes over time, given by its values at a set of times.
+///
+/// Before the first time the schedule takes its first value, and after the
+/// last time it keeps its last one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule<T> {
+    points: Vec<(f64, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T> Schedule<T> {
+    /// Creates a piecewise-constant schedule starting at `initial`.
+    pub fn new(initial: T) -> Schedule<T> {
+        Schedule {
+            points: vec![(0.0, initial)],
+            interpolation: Interpolation::Step,
+        }
+    }
+    /// Creates a schedule interpolating linearly between the given times and
+    /// values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty.
+    pub fn linear(points: Vec<(f64, T)>) -> Schedule<T> {
+        Schedule::from_points(points, Interpolation::Linear)
+    }
+    /// Creates a schedule from the given times and values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty.
+    pub fn from_points(mut points: Vec<(f64, T)>, interpolation: Interpolation) -> Schedule<T> {
+        assert!(!points.is_empty(), "a schedule needs at least one point");
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Schedule {
+            points,
+            interpolation,
+        }
+    }
+    /// Adds a point, taking `value` from `time` onwards.
+    pub fn then(mut self, time: f64, value: T) -> Self {
+        let index = self.points.partition_point(|(t, _)| *t <= time);
+        self.points.insert(index, (time, value));
+        self
+    }
+    /// Returns the times and values defining the schedule.
+    pub fn points(&self) -> &[(f64, T)] {
+        &self.points
+    }
+}
+
+impl<T: Clone + Interpolate> Schedule<T> {
+    /// Returns the value of the schedule at `time`.
+    pub fn value(&self, time: f64) -> T {
+        let index = self.points.partition_point(|(t, _)| *t <= time);
+        if index == 0 {
+            return self.points[0].1.clone();
+        }
+        let (start, value) = &self.points[index - 1];
+        match (self.interpolation, self.points.get(index)) {
+            (Interpolation::Linear, Some((end, next))) => {
+                value.interpolate(next, (time - start) / (end - start))
+            }
+            _ => value.clone(),
+        }
+    }
+}
+
+/// A behaviour parameter, which is either a constant or follows a
+/// [`Schedule`].
+///
+/// Behaviours take `impl Into<Param>` wherever they take a rate, so plain
+/// numbers and schedules can be used interchangeably.
+#[derive(Clone, Debug)]
+pub struct Param {
+    kind: ParamKind,
+    current: Cell<f64>,
+}
+
+#[derive(Clone, Debug)]
+enum ParamKind {
+    Constant(f64),
+    Scheduled(Schedule<f64>),
+}
+
+impl Param {
+    /// Returns the value of the parameter at `time`.
+    pub fn value(&self, time: f64) -> f64 {
+        let value = match &self.kind {
+            ParamKind::Constant(value) => *value,
+            ParamKind::Scheduled(schedule) => schedule.value(time),
+        };
+        self.current.set(value);
+        value
+    }
+    /// Returns the value the parameter had when it was last used, or its
+    /// initial value if it has not been used yet.
+    pub fn current(&self) -> f64 {
+        self.current.get()
+    }
+    /// Returns true if the parameter follows a schedule.
+    pub fn is_scheduled(&self) -> bool {
+        match self.kind {
+            ParamKind::Scheduled(_) => true,
+            ParamKind::Constant(_) => false,
+        }
+    }
+}
+
+impl From<f64> for Param {
+    fn from(value: f64) -> Param {
+        Param {
+            kind: ParamKind::Constant(value),
+            current: Cell::new(value),
+        }
+    }
+}
+
+impl From<Schedule<f64>> for Param {
+    fn from(schedule: Schedule<f64>) -> Param {
+        Param {
+            current: Cell::new(schedule.value(0.0)),
+            kind: ParamKind::Scheduled(schedule),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::linalg::invert;
+use crate::{SimulationResult, Sweep};
+
+/// A single number summarizing a run, such as the size of its peak, whose
+/// sensitivity to the parameters of a [`Sweep`] can be measured.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// The largest quantity of the named compartment.
+    Peak(String),
+    /// The time at which the named compartment was largest.
+    PeakTime(String),
+    /// The quantity of the named compartment at the end of the run, such as
+    /// the final size of an epidemic when it names the recovered.
+    Final(String),
+    /// How much the named compartment shrank over the run, such as the
+    /// total number ever infected when it names the susceptible.
+    Decrease(String),
+}
+
+impl Output {
+    /// Returns the name of the compartment the output measures.
+    pub fn compartment(&self) -> &str {
+        match self {
+            Output::Peak(name)
+            | Output::PeakTime(name)
+            | Output::Final(name)
+            | Output::Decrease(name) => name,
+        }
+    }
+    /// Measures the output of a run, or returns NaN if the run has no
+    /// compartment by the name it refers to.
+    pub fn measure(&self, result: &SimulationResult) -> f64 {
+        let series = match result.get(self.compartment()) {
+            Some(series) => series,
+            None => return f64::NAN,
+        };
+        match self {
+            Output::Peak(_) => series.max(),
+            Output::PeakTime(_) => series
+                .iter()
+                .zip(result.times())
+                .fold(None, |peak: Option<(f64, f64)>, (value, time)| match peak {
+                    Some((max, _)) if max >= *value => peak,
+                    _ => Some((*value, *time)),
+                })
+                .map(|(_, time)| time),
+            Output::Final(_) => series.last(),
+            Output::Decrease(_) => series.get(0).zip(series.last()).map(|(a, b)| a - b),
+        }
+        .unwrap_or(f64::NAN)
+    }
+}
+
+impl Display for Output {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Peak(name) => write!(f, "peak of {}", name),
+            Output::PeakTime(name) => write!(f, "time of peak of {}", name),
+            Output::Final(name) => write!(f, "final {}", name),
+            Output::Decrease(name) => write!(f, "decrease in {}", name),
+        }
+    }
+}
+
+/// How sensitive one parameter of a [`Sweep`] makes an [`Output`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensitivityIndex {
+    /// The path of the parameter.
+    pub parameter: String,
+    /// The partial rank correlation coefficient of the output with the
+    /// parameter, or its first-order Sobol index.
+    pub value: f64,
+    /// The total-order Sobol index, counting the parameter's interactions
+    /// with every other, or `None` for partial rank correlation.
+    pub total: Option<f64>,
+}
+
+/// The sensitivity of an [`Output`] to each parameter of a [`Sweep`],
+/// ranked from the most influential parameter to the least. Displaying it
+/// draws the ranking as a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sensitivity {
+    output: Output,
+    indices: Vec<SensitivityIndex>,
+}
+
+impl Sensitivity {
+    /// Computes the partial rank correlation coefficient (PRCC) of `output`
+    /// with each parameter of `sweep`: the correlation between the ranks of
+    /// the parameter and of the output once the linear effect of the ranks
+    /// of every other parameter is removed from both. Coefficients range
+    /// from -1 to 1, and are NaN if there are too few runs or a parameter
+    /// was never varied. Ranked by magnitude.
+    ///
+    /// The sweep should sample its parameters independently, as
+    /// [`ParameterSpace::latin_hypercube`](crate::ParameterSpace::latin_hypercube)
+    /// does; a grid works too.
+    pub fn prcc(sweep: &Sweep, output: &Output) -> Sensitivity {
+        let k = sweep.parameters().len();
+        let mut columns: Vec<Vec<f64>> = (0..k)
+            .map(|i| ranks(&sweep.points().iter().map(|p| p[i]).collect::<Vec<_>>()))
+            .collect();
+        columns.push(ranks(
+            &sweep
+                .runs()
+                .iter()
+                .map(|run| output.measure(run))
+                .collect::<Vec<_>>(),
+        ));
+        let correlations: Vec<Vec<f64>> = columns
+            .iter()
+            .map(|a| columns.iter().map(|b| correlation(a, b)).collect())
+            .collect();
+        let precision = if sweep.len() > k + 1 {
+            invert(correlations)
+        } else {
+            None
+        };
+        let coefficients = (0..k).map(|i| match &precision {
+            Some(p) => (-p[i][k] / (p[i][i] * p[k][k]).sqrt()).clamp(-1.0, 1.0),
+            None => f64::NAN,
+        });
+        Sensitivity::ranked(sweep, output, coefficients.map(|c| (c, None)).collect())
+    }
+    /// Estimates the first- and total-order Sobol indices of `output` for
+    /// each parameter of `sweep`, the share of the output's variance due to
+    /// the parameter alone and with all its interactions. Ranked by total
+    /// order.
+    ///
+    /// The sweep must have been run over the points of
+    /// [`ParameterSpace::saltelli`](crate::ParameterSpace::saltelli), in
+    /// order, from which the indices are estimated by Saltelli's and
+    /// Jansen's estimators.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of runs isn't a multiple of the number of
+    /// parameters plus two, as it is for a Saltelli design.
+    pub fn sobol(sweep: &Sweep, output: &Output) -> Sensitivity {
+        let k = sweep.parameters().len();
+        assert!(
+            sweep.len().is_multiple_of(k + 2),
+            "a Saltelli design has a multiple of {} runs",
+            k + 2
+        );
+        let values: Vec<f64> = sweep.runs().iter().map(|run| output.measure(run)).collect();
+        let blocks: Vec<&[f64]> = values.chunks(k + 2).collect();
+        let n = blocks.len() as f64;
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let both = blocks.iter().flat_map(|block| &block[..2]);
+        let variance = both.map(|v| (v - mean).powi(2)).sum::<f64>() / (2.0 * n - 1.0);
+        let indices = (0..k)
+            .map(|i| {
+                let (mut first, mut total) = (0.0, 0.0);
+                for block in &blocks {
+                    let (a, b, ab) = (block[0], block[1], block[2 + i]);
+                    first += b * (ab - a);
+                    total += (a - ab).powi(2);
+                }
+                (first / n / variance, Some(total / (2.0 * n) / variance))
+            })
+            .collect();
+        Sensitivity::ranked(sweep, output, indices)
+    }
+    fn ranked(sweep: &Sweep, output: &Output, values: Vec<(f64, Option<f64>)>) -> Sensitivity {
+        let mut indices: Vec<SensitivityIndex> = sweep
+            .parameters()
+            .iter()
+            .zip(values)
+            .map(|(parameter, (value, total))| SensitivityIndex {
+                parameter: parameter.clone(),
+                value,
+                total,
+            })
+            .collect();
+        let key = |index: &SensitivityIndex| {
+            let key = index.total.unwrap_or(index.value).abs();
+            if key.is_nan() {
+                f64::NEG_INFINITY
+            } else {
+                key
+            }
+        };
+        indices.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        Sensitivity {
+            output: output.clone(),
+            indices,
+        }
+    }
+    /// Returns the output whose sensitivity was measured.
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+    /// Returns the index of every parameter, most influential first.
+    pub fn indices(&self) -> &[SensitivityIndex] {
+        &self.indices
+    }
+    /// Returns the index of the parameter at `path`.
+    pub fn get(&self, path: &str) -> Option<&SensitivityIndex> {
+        self.indices.iter().find(|index| index.parameter == path)
+    }
+}
+
+impl Display for Sensitivity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self
+            .indices
+            .iter()
+            .map(|index| index.parameter.chars().count())
+            .chain(Some("Parameter".len()))
+            .max()
+            .unwrap_or(0);
+        let sobol = self.indices.iter().any(|index| index.total.is_some());
+        writeln!(f, "Sensitivity of the {}", self.output)?;
+        write!(f, "{:>4}  {:<width$}", "Rank", "Parameter", width = width)?;
+        if sobol {
+            writeln!(f, "  {:>11}  {:>11}", "First order", "Total order")?;
+        } else {
+            writeln!(f, "  {:>6}", "PRCC")?;
+        }
+        for (rank, index) in self.indices.iter().enumerate() {
+            write!(
+                f,
+                "{:>4}  {:<width$}",
+                rank + 1,
+                index.parameter,
+                width = width
+            )?;
+            match index.total {
+                Some(total) => writeln!(f, "  {:>11.3}  {:>11.3}", index.value, total)?,
+                None => writeln!(f, "  {:>6.3}", index.value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces every value with its rank, from 1, giving tied values the mean
+/// of their ranks. NaN ranks last.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for index in &order[start..end] {
+            ranks[*index] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// The Pearson correlation of two equally long samples.
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
@@ -0,0 +1,135 @@
+//! Implementations of [`Serialize`] for results and model descriptions,
+//! behind the `serde` feature.
+
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use crate::{
+    CompartmentSummary, Ensemble, Metadata, Parameter, SimulationResult, Solver, State, Summary,
+    TimeSeries,
+};
+
+impl Serialize for TimeSeries {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TimeSeries", 2)?;
+        state.serialize_field("name", self.name())?;
+        state.serialize_field("values", self.values())?;
+        state.end()
+    }
+}
+
+impl Serialize for SimulationResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let series: Vec<&TimeSeries> = self.series().collect();
+        let mut state = serializer.serialize_struct("SimulationResult", 2)?;
+        state.serialize_field("times", self.times())?;
+        state.serialize_field("series", &series)?;
+        state.end()
+    }
+}
+
+impl Serialize for Ensemble {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Ensemble", 2)?;
+        state.serialize_field("seeds", self.seeds())?;
+        state.serialize_field("runs", self.runs())?;
+        state.end()
+    }
+}
+
+/// Serializes as a map from bucket name to quantity, along with the time
+/// and total.
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let quantities = Quantities(self);
+        let mut state = serializer.serialize_struct("State", 3)?;
+        state.serialize_field("time", &self.time())?;
+        state.serialize_field("total", &self.total())?;
+        state.serialize_field("quantities", &quantities)?;
+        state.end()
+    }
+}
+
+struct Quantities<'a>(&'a State);
+
+impl Serialize for Quantities<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, quantity) in self.0.iter() {
+            map.serialize_entry(name, &quantity)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Parameter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Parameter", 4)?;
+        state.serialize_field("bucket", &self.bucket)?;
+        state.serialize_field("behaviour", &self.behaviour)?;
+        state.serialize_field("key", &self.key)?;
+        state.serialize_field("value", &self.value)?;
+        state.end()
+    }
+}
+
+impl Serialize for Solver {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Solver::Euler => serializer.serialize_unit_variant("Solver", 0, "Euler"),
+            Solver::Rk4 => serializer.serialize_unit_variant("Solver", 1, "Rk4"),
+            Solver::DormandPrince { atol, rtol } => {
+                use serde::ser::SerializeStructVariant;
+                let mut state =
+                    serializer.serialize_struct_variant("Solver", 2, "DormandPrince", 2)?;
+                state.serialize_field("atol", &atol)?;
+                state.serialize_field("rtol", &rtol)?;
+                state.end()
+            }
+            Solver::ImplicitEuler => {
+                serializer.serialize_unit_variant("Solver", 3, "ImplicitEuler")
+            }
+            Solver::Gillespie => serializer.serialize_unit_variant("Solver", 4, "Gillespie"),
+            Solver::TauLeaping => serializer.serialize_unit_variant("Solver", 5, "TauLeaping"),
+        }
+    }
+}
+
+impl Serialize for Metadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Metadata", 8)?;
+        state.serialize_field("seed", &self.seed)?;
+        state.serialize_field("dt", &self.dt)?;
+        state.serialize_field("time_unit", &self.time_unit.map(|unit| unit.to_string()))?;
+        state.serialize_field("solver", &self.solver)?;
+        state.serialize_field("time", &self.time)?;
+        state.serialize_field("buckets", &self.buckets)?;
+        state.serialize_field("parameters", &self.parameters)?;
+        state.serialize_field("definition", &self.definition)?;
+        state.end()
+    }
+}
+
+impl Serialize for CompartmentSummary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CompartmentSummary", 5)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("initial", &self.initial)?;
+        state.serialize_field("peak", &self.peak)?;
+        state.serialize_field("peak_time", &self.peak_time)?;
+        state.serialize_field("last", &self.last)?;
+        state.end()
+    }
+}
+
+impl Serialize for Summary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Summary", 6)?;
+        state.serialize_field("compartments", &self.compartments)?;
+        state.serialize_field("infected_peak", &self.infected_peak)?;
+        state.serialize_field("infected_peak_time", &self.infected_peak_time)?;
+        state.serialize_field("attack_rate", &self.attack_rate)?;
+        state.serialize_field("duration", &self.duration)?;
+        state.serialize_field("doubling_time", &self.doubling_time)?;
+        state.end()
+    }
+}
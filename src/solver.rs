@@ -0,0 +1,624 @@
+use crate::context::Shared;
+use crate::{Bucket, Context, Transfer};
+
+/// How a model integrates its flows over each time step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Solver {
+    /// Forward Euler: every behaviour is evaluated once per step, at the
+    /// state at its start.
+    #[default]
+    Euler,
+    /// The classical fourth-order Runge-Kutta method, integrating every
+    /// flow as one coupled system of ODEs.
+    ///
+    /// Behaviours are evaluated four times per step, at intermediate
+    /// states, and the transfers of the four evaluations are averaged
+    /// before they are applied. This suits behaviours that are functions of
+    /// the current state alone. Behaviours that keep state between updates
+    /// or make discrete or random moves, such as
+    /// [`Importation`](crate::Importation) or [`Delayed`](crate::Delayed),
+    /// see every intermediate evaluation and are better run with
+    /// [`Euler`](Solver::Euler). Steps are always
+    /// [synchronous](crate::UpdateMode::Synchronous).
+    Rk4,
+    /// The adaptive Dormand-Prince method, which estimates the error of
+    /// every step and shrinks or grows the step to keep it within an
+    /// absolute tolerance `atol` and a relative tolerance `rtol`.
+    ///
+    /// The step starts at the model's [`dt`](crate::Model::set_dt) and
+    /// never crosses a tick. Rejected steps are retried with a smaller
+    /// step, so the same caveats about stateful behaviours apply as for
+    /// [`Rk4`](Solver::Rk4).
+    DormandPrince {
+        /// The absolute tolerance on each bucket.
+        atol: f64,
+        /// The tolerance relative to the size of each bucket.
+        rtol: f64,
+    },
+    /// The implicit, or backward, Euler method, which takes the flows over
+    /// each step from the state at its end rather than its start.
+    ///
+    /// The end state is found by Newton's method, with the Jacobian of the
+    /// flows estimated by finite differences. Each step costs many more
+    /// evaluations than an explicit one, but stays stable in stiff models
+    /// with some very fast flows, such as vector dynamics alongside host
+    /// dynamics, where explicit methods need a tiny step not to blow up.
+    /// The same caveats about stateful behaviours apply as for
+    /// [`Rk4`](Solver::Rk4).
+    ImplicitEuler,
+    /// Gillespie's exact stochastic simulation algorithm, moving one
+    /// individual at a time at random event times, drawn from the model's
+    /// random number generator (see [`Model::with_seed`](crate::Model::with_seed)).
+    ///
+    /// Every transfer a behaviour makes over a unit step is treated as the
+    /// propensity of an event moving a single individual along it, so the
+    /// usual behaviours describe stochastic dynamics unchanged. Behaviours
+    /// are evaluated again after every event, which suits small
+    /// populations, where chance extinction matters most. Behaviours can't
+    /// [schedule](crate::Context::schedule) events, since their transfers
+    /// are rates rather than moves.
+    Gillespie,
+    /// Tau-leaping, an approximate stochastic method which leaps forward a
+    /// whole time step at a time, drawing the number of individuals moved
+    /// along each transfer from a Poisson distribution with the transfer's
+    /// amount as its mean.
+    ///
+    /// Far cheaper than [`Solver::Gillespie`] in large populations, with the
+    /// time step controlling the tradeoff against accuracy. Leaps that would
+    /// move more individuals out of a bucket than it holds are scaled down.
+    TauLeaping,
+}
+
+/// Collects the transfers every behaviour would make over a step of `dt`
+/// from the current state.
+pub(crate) fn transfers(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+) -> Vec<Transfer> {
+    let context = Context::new(time, dt, buckets, shared);
+    buckets
+        .iter()
+        .flat_map(|bucket| bucket.transfers(&context))
+        .collect()
+}
+
+/// The stages and weights of an explicit Runge-Kutta method.
+struct Tableau {
+    c: &'static [f64],
+    a: &'static [&'static [f64]],
+    b: &'static [f64],
+}
+
+const RK4: Tableau = Tableau {
+    c: &[0.0, 0.5, 0.5, 1.0],
+    a: &[&[], &[0.5], &[0.0, 0.5], &[0.0, 0.0, 1.0]],
+    b: &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+};
+
+const DORMAND_PRINCE: Tableau = Tableau {
+    c: &[0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0],
+    a: &[
+        &[],
+        &[1.0 / 5.0],
+        &[3.0 / 40.0, 9.0 / 40.0],
+        &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+        &[
+            19372.0 / 6561.0,
+            -25360.0 / 2187.0,
+            64448.0 / 6561.0,
+            -212.0 / 729.0,
+        ],
+        &[
+            9017.0 / 3168.0,
+            -355.0 / 33.0,
+            46732.0 / 5247.0,
+            49.0 / 176.0,
+            -5103.0 / 18656.0,
+        ],
+        &[
+            35.0 / 384.0,
+            0.0,
+            500.0 / 1113.0,
+            125.0 / 192.0,
+            -2187.0 / 6784.0,
+            11.0 / 84.0,
+        ],
+    ],
+    b: &[
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+        0.0,
+    ],
+};
+
+/// The weights of the embedded fourth-order solution of
+/// [`DORMAND_PRINCE`], used to estimate the error of a step.
+const DORMAND_PRINCE_EMBEDDED: [f64; 7] = [
+    5179.0 / 57600.0,
+    0.0,
+    7571.0 / 16695.0,
+    393.0 / 640.0,
+    -92097.0 / 339200.0,
+    187.0 / 2100.0,
+    1.0 / 40.0,
+];
+
+/// The smallest step an adaptive solver will take, in ticks.
+pub(crate) const MIN_STEP: f64 = 1e-9;
+
+/// Evaluates the transfers of every stage of `tableau` over a step of
+/// `dt`, leaving every bucket as it was.
+///
+/// Returns `None` if the stages cannot be matched up, because behaviours
+/// switched on or off within the step.
+fn stages(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+    tableau: &Tableau,
+) -> Option<Vec<Vec<Transfer>>> {
+    let start: Vec<f64> = buckets.iter().map(Bucket::get).collect();
+    let mut stages: Vec<Vec<Transfer>> = Vec::new();
+    let mut changes: Vec<Vec<f64>> = Vec::new();
+    for (c, a) in tableau.c.iter().zip(tableau.a) {
+        buckets.iter().enumerate().for_each(|(i, bucket)| {
+            let offset: f64 = a.iter().zip(&changes).map(|(a, k)| a * k[i]).sum();
+            bucket.set(start[i] + offset);
+        });
+        let shared = if stages.is_empty() {
+            shared
+        } else {
+            shared.trial()
+        };
+        let stage = transfers(buckets, shared, time + c * dt, dt);
+        changes.push(self::changes(buckets, &stage));
+        stages.push(stage);
+    }
+    buckets
+        .iter()
+        .zip(&start)
+        .for_each(|(bucket, quantity)| bucket.set(*quantity));
+    if stages.iter().any(|stage| stage.len() != stages[0].len()) {
+        return None;
+    }
+    Some(stages)
+}
+
+/// Combines the transfers of every stage with the given weights, keeping
+/// the endpoints of the first stage.
+fn combine(stages: &[Vec<Transfer>], weights: &[f64]) -> Vec<Transfer> {
+    let mut combined = stages[0].clone();
+    combined.iter_mut().enumerate().for_each(|(i, transfer)| {
+        let amount: f64 = stages
+            .iter()
+            .zip(weights)
+            .map(|(stage, weight)| weight * stage[i].amount.max(0.0))
+            .sum();
+        transfer.amount = amount.max(0.0);
+    });
+    combined
+}
+
+/// Returns the transfers of a fourth-order Runge-Kutta step of `dt`,
+/// leaving every bucket as it was.
+pub(crate) fn rk4(buckets: &[Bucket], shared: Shared<'_>, time: f64, dt: f64) -> Vec<Transfer> {
+    match stages(buckets, shared, time, dt, &RK4) {
+        Some(stages) => combine(&stages, RK4.b),
+        // Behaviours that switched on or off within the step cannot be
+        // matched up between stages, so fall back to an Euler step.
+        None => transfers(buckets, shared, time, dt),
+    }
+}
+
+/// Returns the transfers of a Dormand-Prince step of `dt`, leaving every
+/// bucket as it was, along with the estimated error of the step relative
+/// to the tolerances. The step should be rejected if the error is above 1.
+pub(crate) fn dormand_prince(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+    atol: f64,
+    rtol: f64,
+) -> (Vec<Transfer>, f64) {
+    let stages = match stages(buckets, shared, time, dt, &DORMAND_PRINCE) {
+        Some(stages) => stages,
+        None => return (transfers(buckets, shared, time, dt), 0.0),
+    };
+    let fifth = combine(&stages, DORMAND_PRINCE.b);
+    let weights: Vec<f64> = DORMAND_PRINCE
+        .b
+        .iter()
+        .zip(&DORMAND_PRINCE_EMBEDDED)
+        .map(|(b, e)| b - e)
+        .collect();
+    let mut errors = vec![0.0; buckets.len()];
+    stages.iter().zip(&weights).for_each(|(stage, weight)| {
+        changes(buckets, stage)
+            .iter()
+            .zip(errors.iter_mut())
+            .for_each(|(change, error)| *error += weight * change);
+    });
+    let after = changes(buckets, &fifth);
+    let sum: f64 = buckets
+        .iter()
+        .zip(errors.iter().zip(&after))
+        .map(|(bucket, (error, change))| {
+            let before = bucket.get();
+            let scale = atol + rtol * before.abs().max((before + change).abs());
+            (error / scale).powi(2)
+        })
+        .sum();
+    let error = (sum / buckets.len().max(1) as f64).sqrt();
+    (fifth, error)
+}
+
+/// Returns how much to grow or shrink the step after a step with the given
+/// relative error.
+pub(crate) fn step_factor(error: f64) -> f64 {
+    if error.is_nan() {
+        return 0.2;
+    }
+    if error == 0.0 {
+        return 5.0;
+    }
+    (0.9 * error.powf(-0.2)).clamp(0.2, 5.0)
+}
+
+/// The most Newton iterations an implicit Euler step takes.
+const NEWTON_ITERATIONS: usize = 20;
+
+/// The relative change at which Newton's method is taken to have converged.
+const NEWTON_TOLERANCE: f64 = 1e-10;
+
+/// Returns the change in every bucket over a step of `dt` with the buckets
+/// holding `quantities`, leaving every bucket as it was.
+fn change_at(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+    quantities: &[f64],
+) -> Vec<f64> {
+    let start: Vec<f64> = buckets.iter().map(Bucket::get).collect();
+    buckets
+        .iter()
+        .zip(quantities)
+        .for_each(|(bucket, quantity)| bucket.set(quantity.max(0.0)));
+    let changes = changes(buckets, &transfers(buckets, shared, time, dt));
+    buckets
+        .iter()
+        .zip(&start)
+        .for_each(|(bucket, quantity)| bucket.set(*quantity));
+    changes
+}
+
+/// Returns the transfers of an implicit Euler step of `dt`, leaving every
+/// bucket as it was.
+///
+/// Newton's method solves `y = y0 + F(y)` for the end state `y`, where `F`
+/// is the change over the step evaluated at `y`, and the transfers are then
+/// evaluated at that end state.
+pub(crate) fn implicit_euler(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+) -> Vec<Transfer> {
+    let n = buckets.len();
+    let start: Vec<f64> = buckets.iter().map(Bucket::get).collect();
+    let end_time = time + dt;
+    let mut y = start.clone();
+    for _ in 0..NEWTON_ITERATIONS {
+        let change = change_at(buckets, shared.trial(), end_time, dt, &y);
+        let residual: Vec<f64> = (0..n).map(|i| y[i] - start[i] - change[i]).collect();
+        // The Jacobian of the residual, I - dF/dy, column by column.
+        let mut jacobian = vec![vec![0.0; n]; n];
+        for j in 0..n {
+            let h = 1e-7 * y[j].abs().max(1.0);
+            let mut shifted = y.clone();
+            shifted[j] += h;
+            let shifted = change_at(buckets, shared.trial(), end_time, dt, &shifted);
+            for i in 0..n {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                jacobian[i][j] = identity - (shifted[i] - change[i]) / h;
+            }
+        }
+        let delta = match solve(jacobian, residual) {
+            Some(delta) => delta,
+            None => break,
+        };
+        let mut converged = true;
+        for i in 0..n {
+            y[i] = (y[i] - delta[i]).max(0.0);
+            converged &= delta[i].abs() <= NEWTON_TOLERANCE * y[i].abs().max(1.0);
+        }
+        if converged {
+            break;
+        }
+    }
+    buckets
+        .iter()
+        .zip(&y)
+        .for_each(|(bucket, quantity)| bucket.set(*quantity));
+    let transfers = transfers(buckets, shared, end_time, dt);
+    buckets
+        .iter()
+        .zip(&start)
+        .for_each(|(bucket, quantity)| bucket.set(*quantity));
+    transfers
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting, returning
+/// `None` if `a` is singular.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot =
+            (column..n).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+        let magnitude = a[pivot][column].abs();
+        if magnitude.is_nan() || magnitude <= f64::EPSILON {
+            return None;
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        for row in column + 1..n {
+            let factor = a[row][column] / a[column][column];
+            let (above, below) = a.split_at_mut(row);
+            below[0][column..]
+                .iter_mut()
+                .zip(&above[column][column..])
+                .for_each(|(value, pivot)| *value -= factor * pivot);
+            b[row] -= factor * b[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Runs Gillespie's algorithm from `time` until `end`, returning the
+/// resulting change in total population.
+pub(crate) fn gillespie(buckets: &[Bucket], shared: Shared<'_>, time: f64, end: f64) -> f64 {
+    let mut time = time;
+    let mut net_change = 0.0;
+    loop {
+        let propensities = transfers(buckets, shared.trial(), time, 1.0);
+        let total: f64 = propensities
+            .iter()
+            .map(|transfer| transfer.amount.max(0.0))
+            .sum();
+        if !(total > 0.0 && total.is_finite()) {
+            break;
+        }
+        time += shared.rng.borrow_mut().exponential(total);
+        if time >= end {
+            break;
+        }
+        let mut choice = shared.rng.borrow_mut().uniform() * total;
+        let event = propensities
+            .iter()
+            .find(|transfer| {
+                choice -= transfer.amount.max(0.0);
+                choice < 0.0
+            })
+            .or_else(|| propensities.last());
+        if let Some(event) = event {
+            let mut event = event.clone();
+            event.amount = 1.0;
+            net_change += event.net_change(event.apply());
+        }
+    }
+    net_change
+}
+
+/// Returns a tau-leap over `dt`, with the amount of every transfer replaced by
+/// a Poisson distributed count of individuals.
+pub(crate) fn tau_leap(
+    buckets: &[Bucket],
+    shared: Shared<'_>,
+    time: f64,
+    dt: f64,
+) -> Vec<Transfer> {
+    let transfers = transfers(buckets, shared, time, dt);
+    let mut rng = shared.rng.borrow_mut();
+    transfers
+        .into_iter()
+        .map(|mut transfer| {
+            transfer.amount = rng.poisson(transfer.amount) as f64;
+            transfer
+        })
+        .collect()
+}
+
+/// Returns the change in every bucket if `transfers` were applied in full.
+fn changes(buckets: &[Bucket], transfers: &[Transfer]) -> Vec<f64> {
+    let mut changes = vec![0.0; buckets.len()];
+    let index = |bucket: &Bucket| buckets.iter().position(|other| other.ptr_eq(bucket));
+    transfers.iter().for_each(|transfer| {
+        let amount = transfer.amount.max(0.0);
+        if let Some(from) = transfer.from.as_ref().and_then(index) {
+            changes[from] -= amount;
+        }
+        if let Some(to) = transfer.to.as_ref().and_then(index) {
+            changes[to] += amount;
+        }
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Model, Rate, SimulationResult, Solver, Transmission};
+
+    /// A model of exponential decay out of `A` at rate `k`.
+    fn decay(k: f64) -> Model {
+        Model::builder()
+            .compartment("A", 1000)
+            .compartment("B", 0)
+            .flow("A", "B", Rate::new(k))
+            .build()
+            .unwrap()
+    }
+
+    /// Runs a small SIR model with `solver` from `seed` for 50 ticks.
+    fn sir(solver: Solver, seed: u64) -> SimulationResult {
+        Model::builder()
+            .compartment("S", 95)
+            .compartment("I", 5)
+            .compartment("R", 0)
+            .flow("S", "I", Transmission::new(0.5))
+            .flow("I", "R", Rate::new(0.2))
+            .build()
+            .unwrap()
+            .with_solver(solver)
+            .with_seed(seed)
+            .simulate(50)
+    }
+
+    /// Checks that every bucket of `result` holds a whole, non-negative
+    /// number of individuals and that they add up to 100 throughout.
+    fn assert_whole_and_conserved(result: &SimulationResult) {
+        for index in 0..result.times().len() {
+            let values: Vec<f64> = result
+                .series()
+                .map(|series| series.get(index).unwrap())
+                .collect();
+            assert!(values.iter().all(|v| *v >= 0.0 && v.fract() == 0.0));
+            assert_eq!(values.iter().sum::<f64>(), 100.0);
+        }
+    }
+
+    /// Returns the mean of `A` after 10 ticks of decay at rate 0.1 over 50
+    /// runs by `solver` with steps of `dt`.
+    fn mean_decay(solver: Solver, dt: f64) -> f64 {
+        let mut model = decay(0.1).with_solver(solver).with_seed(1);
+        model.set_dt(dt);
+        model.ensemble(50, 10).mean("A").unwrap().last().unwrap()
+    }
+
+    /// Returns how far `A` ends from its exact value after `ticks` ticks of
+    /// decay at rate `k`, solved by `solver` with steps of `dt`.
+    fn decay_error(solver: Solver, k: f64, dt: f64, ticks: u64) -> f64 {
+        let mut model = decay(k).with_solver(solver);
+        model.set_dt(dt);
+        let result = model.simulate(ticks);
+        let end = result.get("A").unwrap().last().unwrap();
+        (end - 1000.0 * (-k * ticks as f64).exp()).abs()
+    }
+
+    #[test]
+    fn rk4_matches_exponential_decay() {
+        assert!(decay_error(Solver::Rk4, 0.5, 0.1, 10) < 1e-4);
+    }
+
+    #[test]
+    fn rk4_is_fourth_order() {
+        let coarse = decay_error(Solver::Rk4, 0.5, 0.2, 10);
+        let fine = decay_error(Solver::Rk4, 0.5, 0.1, 10);
+        let order = (coarse / fine).log2();
+        assert!((order - 4.0).abs() < 0.2, "order {}", order);
+    }
+
+    #[test]
+    fn dormand_prince_meets_its_tolerance() {
+        for tolerance in [1e-3, 1e-6, 1e-9] {
+            let solver = Solver::DormandPrince {
+                atol: tolerance,
+                rtol: tolerance,
+            };
+            let error = decay_error(solver, 0.5, 1.0, 10);
+            assert!(
+                error < 1000.0 * tolerance,
+                "error {} at {}",
+                error,
+                tolerance
+            );
+        }
+    }
+
+    #[test]
+    fn dormand_prince_error_falls_with_tolerance() {
+        let error = |tolerance| {
+            let solver = Solver::DormandPrince {
+                atol: tolerance,
+                rtol: tolerance,
+            };
+            decay_error(solver, 0.5, 1.0, 10)
+        };
+        assert!(error(1e-5) > 1000.0 * error(1e-9));
+    }
+
+    #[test]
+    fn gillespie_repeats_with_the_same_seed() {
+        assert_eq!(sir(Solver::Gillespie, 7), sir(Solver::Gillespie, 7));
+        assert_ne!(sir(Solver::Gillespie, 7), sir(Solver::Gillespie, 8));
+    }
+
+    #[test]
+    fn gillespie_conserves_whole_individuals() {
+        for seed in 0..20 {
+            assert_whole_and_conserved(&sir(Solver::Gillespie, seed));
+        }
+    }
+
+    #[test]
+    fn gillespie_decays_at_the_mean_rate() {
+        // A binomial with mean 367.9 and a standard deviation of about 2.2
+        // over 50 runs.
+        let expected = 1000.0 * (-1.0f64).exp();
+        assert!((mean_decay(Solver::Gillespie, 1.0) - expected).abs() < 10.0);
+    }
+
+    #[test]
+    fn tau_leaping_repeats_with_the_same_seed() {
+        assert_eq!(sir(Solver::TauLeaping, 7), sir(Solver::TauLeaping, 7));
+        assert_ne!(sir(Solver::TauLeaping, 7), sir(Solver::TauLeaping, 8));
+    }
+
+    #[test]
+    fn tau_leaping_conserves_whole_individuals() {
+        for seed in 0..20 {
+            assert_whole_and_conserved(&sir(Solver::TauLeaping, seed));
+        }
+    }
+
+    #[test]
+    fn tau_leaping_decays_at_the_mean_rate() {
+        // Leaps of 0.05 shrink the bias from leaping to well below the noise.
+        let expected = 1000.0 * (-1.0f64).exp();
+        assert!((mean_decay(Solver::TauLeaping, 0.05) - expected).abs() < 10.0);
+    }
+
+    #[test]
+    fn implicit_euler_is_stable_on_stiff_decay() {
+        // With a rate of 50 and steps of a whole tick, explicit Euler would
+        // try to move fifty times what `A` holds, while each backward step
+        // divides it by 1 + 50.
+        let mut model = decay(50.0).with_solver(Solver::ImplicitEuler);
+        let result = model.simulate(3);
+        let values = result.get("A").unwrap().values();
+        for (step, value) in values.iter().enumerate() {
+            let expected = 1000.0 / 51f64.powi(step as i32);
+            assert!((value - expected).abs() < 1e-6 * expected, "{:?}", values);
+        }
+    }
+
+    #[test]
+    fn implicit_euler_is_first_order() {
+        let coarse = decay_error(Solver::ImplicitEuler, 0.5, 0.2, 10);
+        let fine = decay_error(Solver::ImplicitEuler, 0.5, 0.1, 10);
+        let order = (coarse / fine).log2();
+        assert!((order - 1.0).abs() < 0.1, "order {}", order);
+    }
+}
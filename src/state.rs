@@ -0,0 +1,69 @@
+use std::ops::Index;
+
+use crate::Bucket;
+
+/// A read-only snapshot of every bucket in a model at some tick.
+///
+/// Quantities are looked up by bucket name, either with [`get`](State::get)
+/// or by indexing, as in `state["S"]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct State {
+    time: f64,
+    quantities: Vec<(String, f64)>,
+    total: f64,
+}
+
+impl State {
+    pub(crate) fn new(time: f64, buckets: &[Bucket]) -> State {
+        State {
+            time,
+            quantities: buckets
+                .iter()
+                .map(|bucket| (bucket.name(), bucket.get()))
+                .collect(),
+            total: buckets
+                .iter()
+                .filter(|bucket| !bucket.is_sink())
+                .map(Bucket::get)
+                .sum(),
+        }
+    }
+    /// Returns the time the snapshot was taken at.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    /// Returns the quantity in the bucket called `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.quantities
+            .iter()
+            .find(|(bucket, _)| bucket == name)
+            .map(|(_, quantity)| *quantity)
+    }
+    /// Returns the total living population, leaving out sinks.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+    /// Iterates over the name and quantity of every bucket.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.quantities
+            .iter()
+            .map(|(name, quantity)| (name.as_str(), *quantity))
+    }
+}
+
+impl Index<&str> for State {
+    type Output = f64;
+
+    /// Returns the quantity in the bucket called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no bucket called `name`.
+    fn index(&self, name: &str) -> &f64 {
+        self.quantities
+            .iter()
+            .find(|(bucket, _)| bucket == name)
+            .map(|(_, quantity)| quantity)
+            .unwrap_or_else(|| panic!("no bucket named `{}`", name))
+    }
+}
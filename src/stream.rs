@@ -0,0 +1,194 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Reporter, SimulationResult, State};
+
+/// How often the listener checks for new connections.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a client can hold up the run reading or writing before it's
+/// dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The response header sent to every client before its events.
+pub(crate) const HEADER: &str = "HTTP/1.1 200 OK\r\n\
+    Content-Type: text/event-stream\r\n\
+    Cache-Control: no-cache\r\n\
+    Connection: keep-alive\r\n\
+    Access-Control-Allow-Origin: *\r\n\r\n";
+
+#[derive(Default)]
+struct Clients {
+    streams: Vec<TcpStream>,
+    /// Every event sent so far, replayed to clients that connect late.
+    events: Vec<String>,
+}
+
+impl Clients {
+    fn send(&mut self, event: String) {
+        self.streams
+            .retain_mut(|stream| stream.write_all(event.as_bytes()).is_ok());
+        self.events.push(event);
+    }
+}
+
+/// Serves the states reported during a run as
+/// [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html),
+/// so that a browser can chart a model live with an `EventSource`.
+///
+/// Any request to the address the reporter is bound to opens a stream of
+/// events. Each state reported is sent as a message whose data is a JSON
+/// object, as in
+///
+/// ```text
+/// data: {"time":12,"buckets":{"S":990.5,"I":7.2,"R":2.3}}
+/// ```
+///
+/// and the end of a run as a `finish` event with the number of states
+/// reported. Clients that connect part way through are first sent every
+/// event so far, so they see the whole trajectory.
+pub struct SseReporter {
+    address: SocketAddr,
+    clients: Arc<Mutex<Clients>>,
+}
+
+impl SseReporter {
+    /// Starts serving events on `address`, such as `"127.0.0.1:8080"`.
+    /// Connections are accepted in the background until the reporter is
+    /// dropped.
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<SseReporter> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Clients::default()));
+        let weak = Arc::downgrade(&clients);
+        thread::spawn(move || accept(listener, weak));
+        Ok(SseReporter { address, clients })
+    }
+    /// Returns the address events are served on, which gives the port
+    /// chosen if the reporter was bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+    fn send(&self, event: String) {
+        self.clients
+            .lock()
+            .expect("no thread panics holding the clients")
+            .send(event);
+    }
+}
+
+impl Reporter for SseReporter {
+    fn on_step(&mut self, time: f64, state: &State) {
+        self.send(state_event(time, state));
+    }
+    fn on_finish(&mut self, result: &SimulationResult) {
+        self.send(format!(
+            "event: finish\ndata: {{\"points\":{}}}\n\n",
+            result.len()
+        ));
+    }
+}
+
+/// Formats a state as the data of an event, as described on
+/// [`SseReporter`].
+pub(crate) fn state_event(time: f64, state: &State) -> String {
+    let buckets = state
+        .iter()
+        .map(|(name, quantity)| format!("{}:{}", string(name), number(quantity)))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "data: {{\"time\":{},\"buckets\":{{{}}}}}\n\n",
+        number(time),
+        buckets
+    )
+}
+
+/// Accepts connections until every handle to `clients` is gone.
+fn accept(listener: TcpListener, clients: Weak<Mutex<Clients>>) {
+    while let Some(clients) = clients.upgrade() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Ok(mut stream) = open(stream) {
+                    let mut clients = clients
+                        .lock()
+                        .expect("no thread panics holding the clients");
+                    if clients
+                        .events
+                        .iter()
+                        .all(|event| stream.write_all(event.as_bytes()).is_ok())
+                    {
+                        clients.streams.push(stream);
+                    }
+                }
+            }
+            Err(_) => {
+                drop(clients);
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Reads the request from a new connection and answers with the header of
+/// an event stream.
+fn open(mut stream: TcpStream) -> io::Result<TcpStream> {
+    read_request(&stream)?;
+    stream.write_all(HEADER.as_bytes())?;
+    Ok(stream)
+}
+
+/// Reads the head of an HTTP request from a new connection, returning its
+/// method and path. The connection is left blocking, with timeouts so that
+/// a client can't hold things up for long.
+pub(crate) fn read_request(stream: &TcpStream) -> io::Result<(String, String)> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+    Ok((method, path))
+}
+
+/// Formats `value` as a JSON number, or `null` if it isn't finite.
+fn number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+/// Formats `text` as a JSON string.
+pub(crate) fn string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,190 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::SimulationResult;
+
+/// The fraction of its peak the infected population grows to before the
+/// early phase, in which the doubling time is measured, is taken to be over.
+const EARLY_PHASE: f64 = 0.25;
+
+/// The peak and endpoints of one compartment over a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompartmentSummary {
+    /// The name of the compartment.
+    pub name: String,
+    /// The quantity at the start of the run.
+    pub initial: f64,
+    /// The largest quantity.
+    pub peak: f64,
+    /// The first time the compartment held its largest quantity.
+    pub peak_time: f64,
+    /// The quantity at the end of the run.
+    pub last: f64,
+}
+
+/// The headline statistics of an epidemic run: the peak of every
+/// compartment, how much of the population was infected, how long the
+/// epidemic lasted and how fast it grew. See
+/// [`SimulationResult::summary`].
+///
+/// Displaying a summary draws it as a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// The peak and endpoints of every compartment, in the order the
+    /// buckets were added to the model.
+    pub compartments: Vec<CompartmentSummary>,
+    /// The largest combined quantity of the infected compartments.
+    pub infected_peak: f64,
+    /// The first time the infected compartments held their combined peak.
+    pub infected_peak_time: f64,
+    /// The fraction of the population at the start of the run that left
+    /// the susceptible compartment by the end of it.
+    pub attack_rate: f64,
+    /// The time from the start of the run until fewer than one individual
+    /// remained infected after the peak, or `None` if the epidemic wasn't
+    /// over by the end of the run.
+    pub duration: Option<f64>,
+    /// The time it took the number infected to double while it grew
+    /// exponentially early on, before reaching a quarter of its peak, or
+    /// `None` if it didn't grow.
+    pub doubling_time: Option<f64>,
+}
+
+impl Summary {
+    pub(crate) fn new(
+        result: &SimulationResult,
+        susceptible: &str,
+        infected: &[&str],
+    ) -> Option<Summary> {
+        let times = result.times();
+        let susceptible = result.get(susceptible)?;
+        let infected = infected
+            .iter()
+            .map(|name| result.get(name))
+            .collect::<Option<Vec<_>>>()?;
+        let prevalence: Vec<f64> = (0..times.len())
+            .map(|index| infected.iter().filter_map(|s| s.get(index)).sum())
+            .collect();
+        let (peak_index, infected_peak) = peak(&prevalence)?;
+        let population: f64 = result.row(0)?.iter().sum();
+        let attack_rate = (susceptible.get(0)? - susceptible.last()?) / population;
+        let duration = prevalence[peak_index..]
+            .iter()
+            .position(|value| *value < 1.0)
+            .map(|index| times[peak_index + index] - times[0]);
+        let early: Vec<(f64, f64)> = times
+            .iter()
+            .zip(&prevalence)
+            .take_while(|(_, value)| **value < EARLY_PHASE * infected_peak)
+            .filter(|(_, value)| **value > 0.0)
+            .map(|(time, value)| (*time, value.ln()))
+            .collect();
+        let doubling_time = growth_rate(&early)
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| 2f64.ln() / rate);
+        let compartments = result
+            .series()
+            .map(|series| {
+                let (index, peak) = peak(series.values()).unwrap_or((0, f64::NAN));
+                CompartmentSummary {
+                    name: series.name().to_owned(),
+                    initial: series.get(0).unwrap_or(f64::NAN),
+                    peak,
+                    peak_time: times.get(index).copied().unwrap_or(f64::NAN),
+                    last: series.last().unwrap_or(f64::NAN),
+                }
+            })
+            .collect();
+        Some(Summary {
+            compartments,
+            infected_peak,
+            infected_peak_time: times[peak_index],
+            attack_rate,
+            duration,
+            doubling_time,
+        })
+    }
+    /// Returns the summary of the compartment called `name`.
+    pub fn get(&self, name: &str) -> Option<&CompartmentSummary> {
+        self.compartments
+            .iter()
+            .find(|compartment| compartment.name == name)
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self
+            .compartments
+            .iter()
+            .map(|compartment| compartment.name.chars().count())
+            .chain(Some("Compartment".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            f,
+            "{:<width$}  {:>12}  {:>12}  {:>10}  {:>12}",
+            "Compartment",
+            "Initial",
+            "Peak",
+            "Peak time",
+            "Final",
+            width = width
+        )?;
+        for compartment in &self.compartments {
+            writeln!(
+                f,
+                "{:<width$}  {:>12.2}  {:>12.2}  {:>10.2}  {:>12.2}",
+                compartment.name,
+                compartment.initial,
+                compartment.peak,
+                compartment.peak_time,
+                compartment.last,
+                width = width
+            )?;
+        }
+        writeln!(
+            f,
+            "Infected peak: {:.2} at {:.2}",
+            self.infected_peak, self.infected_peak_time
+        )?;
+        writeln!(f, "Attack rate: {:.1}%", 100.0 * self.attack_rate)?;
+        match self.duration {
+            Some(duration) => writeln!(f, "Duration: {:.2}", duration)?,
+            None => writeln!(f, "Duration: not over by the end of the run")?,
+        }
+        match self.doubling_time {
+            Some(doubling_time) => writeln!(f, "Doubling time: {:.2}", doubling_time),
+            None => writeln!(f, "Doubling time: no early growth"),
+        }
+    }
+}
+
+/// Returns the index and value of the first largest value.
+fn peak(values: &[f64]) -> Option<(usize, f64)> {
+    values
+        .iter()
+        .copied()
+        .enumerate()
+        .fold(None, |peak, (index, value)| match peak {
+            Some((_, max)) if max >= value => peak,
+            _ => Some((index, value)),
+        })
+}
+
+/// Returns the slope of the least-squares line through `points`, or `None`
+/// if there are fewer than two distinct times.
+fn growth_rate(points: &[(f64, f64)]) -> Option<f64> {
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (t, y) in points {
+        covariance += (t - mean_t) * (y - mean_y);
+        variance += (t - mean_t).powi(2);
+    }
+    if variance > 0.0 {
+        Some(covariance / variance)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::ensemble::parallel;
+use crate::{Model, ParameterError, SimulationResult};
+
+/// The trajectories of a model run once for each of a set of parameter
+/// values, for exploring how its dynamics depend on them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sweep {
+    parameters: Vec<String>,
+    points: Vec<Vec<f64>>,
+    runs: Vec<SimulationResult>,
+}
+
+impl Sweep {
+    /// Runs the model for `ticks` ticks at every combination of the values
+    /// given for each parameter in `parameters`, such as every `beta` in a
+    /// list against every `gamma` in another. Parameters are addressed by
+    /// their [`path`](crate::Parameter::path). The first parameter varies
+    /// fastest.
+    ///
+    /// Runs are spread across threads as by [`Sweep::list`].
+    pub fn grid<F>(
+        build: F,
+        parameters: &[(&str, Vec<f64>)],
+        ticks: u64,
+        jobs: usize,
+    ) -> Result<Sweep, ParameterError>
+    where
+        F: Fn() -> Model + Sync,
+    {
+        let combinations = parameters
+            .iter()
+            .map(|(_, values)| values.len())
+            .product::<usize>();
+        let points = (0..combinations)
+            .map(|mut index| {
+                parameters
+                    .iter()
+                    .map(|(_, values)| {
+                        let value = values[index % values.len()];
+                        index /= values.len();
+                        value
+                    })
+                    .collect()
+            })
+            .collect();
+        let paths: Vec<&str> = parameters.iter().map(|(path, _)| *path).collect();
+        Sweep::list(build, &paths, points, ticks, jobs)
+    }
+    /// Runs the model for `ticks` ticks at each of `points`, every one
+    /// giving a value for each of the parameters in `parameters` in order.
+    ///
+    /// Runs are spread across `jobs` threads, or one per core if `jobs` is
+    /// 0, building a fresh model for every run with `build`, as
+    /// [`Ensemble::parallel`](crate::Ensemble::parallel) does. Each model
+    /// keeps the seed `build` gives it. Every path is checked against a
+    /// model before anything is run, and an error returned if one isn't a
+    /// parameter of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a point doesn't have a value for every parameter.
+    pub fn list<F>(
+        build: F,
+        parameters: &[&str],
+        points: Vec<Vec<f64>>,
+        ticks: u64,
+        jobs: usize,
+    ) -> Result<Sweep, ParameterError>
+    where
+        F: Fn() -> Model + Sync,
+    {
+        assert!(
+            points.iter().all(|point| point.len() == parameters.len()),
+            "every point needs a value for every parameter"
+        );
+        let known: Vec<String> = build().parameters().iter().map(|p| p.path()).collect();
+        if let Some(path) = parameters
+            .iter()
+            .find(|path| !known.iter().any(|k| k == *path))
+        {
+            return Err(ParameterError::Unknown((*path).to_owned()));
+        }
+        let runs = parallel(points.len(), jobs, |run| {
+            let mut model = build();
+            for (path, value) in parameters.iter().zip(&points[run]) {
+                model
+                    .set_parameter(path, *value)
+                    .expect("parameters were checked before running");
+            }
+            model.simulate(ticks)
+        });
+        Ok(Sweep {
+            parameters: parameters.iter().map(|path| (*path).to_owned()).collect(),
+            points,
+            runs,
+        })
+    }
+    /// Returns the path of every parameter swept over.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+    /// Returns the parameter values of every run, in the same order as the
+    /// runs.
+    pub fn points(&self) -> &[Vec<f64>] {
+        &self.points
+    }
+    /// Returns the trajectory of every run.
+    pub fn runs(&self) -> &[SimulationResult] {
+        &self.runs
+    }
+    /// Iterates over the parameter values and trajectory of every run.
+    pub fn iter(&self) -> impl Iterator<Item = (&[f64], &SimulationResult)> {
+        self.points.iter().map(Vec::as_slice).zip(&self.runs)
+    }
+    /// Returns the number of runs.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+    /// Returns true if there are no runs.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+    /// Writes every run to the file at `path` as CSV in long format,
+    /// replacing it if it exists. See [`write_csv`](Sweep::write_csv) for
+    /// the layout.
+    pub fn to_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(File::create(path)?)
+    }
+    /// Writes every run to `writer` as CSV in long format, with a `run`
+    /// column, a column for each parameter named by its path, then `time`,
+    /// `compartment` and `value`, and one row per value recorded, as
+    /// [`Ensemble::write_csv`](crate::Ensemble::write_csv) does.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_field("run")?;
+        for parameter in &self.parameters {
+            writer.write_field(parameter)?;
+        }
+        writer.write_record(["time", "compartment", "value"])?;
+        for (run, (point, result)) in self.iter().enumerate() {
+            let mut prefix = vec![run.to_string()];
+            prefix.extend(point.iter().map(f64::to_string));
+            for series in result.series() {
+                for (time, value) in result.times().iter().zip(series) {
+                    for field in &prefix {
+                        writer.write_field(field)?;
+                    }
+                    writer.write_record([&time.to_string(), series.name(), &value.to_string()])?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
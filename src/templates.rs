@@ -0,0 +1,116 @@
+//! Ready-made models of the classic compartmental structures.
+//!
+//! Every template takes its rates per tick and a population of `n`, `i0`
+//! of whom are initially infectious, with everyone else susceptible. The
+//! compartments are named by their usual letters, so `S`, `I` and `R` and
+//! so on, and the templates double as references to check other models
+//! against.
+
+use crate::{
+    Bucket, Fatality, Latency, MaternalImmunity, Model, ModelBuilder, Rate, Transmission, Wane,
+};
+
+/// Builds a template, which is valid whatever its rates, since the
+/// builder only bounds them once a solver and step are chosen.
+fn build(builder: ModelBuilder) -> Model {
+    builder.build().expect("template definitions are valid")
+}
+
+/// Creates an SIR model, where infection gives lasting immunity.
+///
+/// `beta` is the transmission rate and `gamma` the recovery rate.
+pub fn sir(beta: f64, gamma: f64, n: f64, i0: f64) -> Model {
+    build(
+        Model::builder()
+            .compartment("S", n - i0)
+            .compartment("I", i0)
+            .compartment("R", 0)
+            .flow("S", "I", Transmission::new(beta))
+            .flow("I", "R", Rate::new(gamma)),
+    )
+}
+
+/// Creates an SIS model, where recovery gives no immunity.
+///
+/// `beta` is the transmission rate and `gamma` the recovery rate.
+pub fn sis(beta: f64, gamma: f64, n: f64, i0: f64) -> Model {
+    build(
+        Model::builder()
+            .compartment("S", n - i0)
+            .compartment("I", i0)
+            .flow("S", "I", Transmission::new(beta))
+            .flow("I", "S", Rate::new(gamma)),
+    )
+}
+
+/// Creates an SEIR model, where infection is followed by a latent period.
+///
+/// `beta` is the transmission rate, `sigma` the rate at which exposed
+/// individuals become infectious and `gamma` the recovery rate.
+pub fn seir(beta: f64, sigma: f64, gamma: f64, n: f64, i0: f64) -> Model {
+    build(
+        Model::builder()
+            .compartment("S", n - i0)
+            .compartment("E", 0)
+            .compartment("I", i0)
+            .compartment("R", 0)
+            .flow("S", "E", Transmission::new(beta).infectious("I"))
+            .flow("E", "I", Latency::new(sigma))
+            .flow("I", "R", Rate::new(gamma)),
+    )
+}
+
+/// Creates an SEIRS model, an [`seir`] model whose immunity wanes at rate
+/// `omega`.
+pub fn seirs(beta: f64, sigma: f64, gamma: f64, omega: f64, n: f64, i0: f64) -> Model {
+    build(
+        Model::builder()
+            .compartment("S", n - i0)
+            .compartment("E", 0)
+            .compartment("I", i0)
+            .compartment("R", 0)
+            .flow("S", "E", Transmission::new(beta).infectious("I"))
+            .flow("E", "I", Latency::new(sigma))
+            .flow("I", "R", Rate::new(gamma))
+            .flow("R", "S", Wane::new(omega)),
+    )
+}
+
+/// Creates an SEIRD model, an [`seir`] model where the infectious also die
+/// of the disease at rate `mu`, into the sink `D`.
+pub fn seird(beta: f64, sigma: f64, gamma: f64, mu: f64, n: f64, i0: f64) -> Model {
+    build(
+        Model::builder()
+            .compartment("S", n - i0)
+            .compartment("E", 0)
+            .compartment("I", i0)
+            .compartment("R", 0)
+            .sink("D", 0)
+            .flow("S", "E", Transmission::new(beta).infectious("I"))
+            .flow("E", "I", Latency::new(sigma))
+            .flow("I", "R", Rate::new(gamma))
+            .flow("I", "D", Fatality::new(mu)),
+    )
+}
+
+/// Creates an MSIR model, an [`sir`] model with births and deaths where
+/// newborns are protected by maternal immunity.
+///
+/// Births and natural deaths both happen at per-capita rate `mu`, so the
+/// population stays the same size, and newborns enter `M`, whose
+/// protection wanes into `S` at rate `delta`. Deaths leave the model.
+pub fn msir(delta: f64, beta: f64, gamma: f64, mu: f64, n: f64, i0: f64) -> Model {
+    build(
+        Model::builder()
+            .compartment("M", 0)
+            .compartment("S", n - i0)
+            .compartment("I", i0)
+            .compartment("R", 0)
+            .flow("M", "S", move |susceptible: Bucket| {
+                MaternalImmunity::new(susceptible, mu, delta)
+            })
+            .flow("S", "I", Transmission::new(beta))
+            .flow("I", "R", Rate::new(gamma))
+            .natural_death(mu),
+    )
+}
@@ -0,0 +1,212 @@
+use std::io::{self, Stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+#[cfg(windows)]
+use std::time::Instant;
+
+/// A key pressed while a [`Screen`] is active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Set when Ctrl-C is pressed while a [`Screen`] is active.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(any(unix, windows))]
+extern "C" fn interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// What `signal` returns when it fails, which Windows declares as an
+/// integer rather than a handler.
+#[cfg(unix)]
+const SIG_ERR: libc::sighandler_t = libc::SIG_ERR;
+#[cfg(windows)]
+const SIG_ERR: libc::sighandler_t = libc::SIG_ERR as libc::sighandler_t;
+
+// The console functions of the C runtime, which the standard library
+// already links against.
+#[cfg(windows)]
+extern "C" {
+    fn _kbhit() -> libc::c_int;
+    fn _getwch() -> libc::wchar_t;
+}
+
+/// Switches to the terminal's alternate screen and hides the cursor, so
+/// that frames are drawn in place, then puts the terminal back the way it
+/// was when dropped, including when unwinding from a panic.
+///
+/// While a screen is active on Unix and Windows, Ctrl-C is caught and
+/// reported by [`interrupted`](Screen::interrupted) rather than killing the
+/// process, so that the terminal can be restored, and whatever handled it
+/// before is put back afterwards. On Unix, if standard input is a terminal
+/// it stops echoing and buffering lines so that single key presses can be
+/// read with [`key`](Screen::key); on Windows the console is read directly.
+pub(crate) struct Screen {
+    stdout: Stdout,
+    #[cfg(unix)]
+    termios: Option<libc::termios>,
+    /// The handler of SIGINT before the screen was entered, if ours could
+    /// be installed.
+    #[cfg(any(unix, windows))]
+    interrupt: Option<libc::sighandler_t>,
+}
+
+impl Screen {
+    pub(crate) fn enter() -> io::Result<Screen> {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        #[cfg(any(unix, windows))]
+        let previous =
+            unsafe { libc::signal(libc::SIGINT, interrupt as *const () as libc::sighandler_t) };
+        let mut stdout = io::stdout();
+        write!(stdout, "\x1b[?1049h\x1b[?25l")?;
+        stdout.flush()?;
+        Ok(Screen {
+            stdout,
+            #[cfg(unix)]
+            termios: unsafe { cbreak() },
+            #[cfg(any(unix, windows))]
+            interrupt: Some(previous).filter(|previous| *previous != SIG_ERR),
+        })
+    }
+    /// Waits up to `timeout` for a key to be pressed and returns it, or
+    /// returns `None` if no key was pressed in time or the wait was
+    /// interrupted.
+    ///
+    /// Arrow keys arrive as escape sequences, which are decoded into the
+    /// arrow [`Key`]s. Any other escape sequence is skipped.
+    #[cfg(unix)]
+    pub(crate) fn key(&mut self, timeout: Duration) -> Option<Key> {
+        if self.termios.is_none() {
+            std::thread::sleep(timeout);
+            return None;
+        }
+        match read_byte(timeout)? {
+            0x1b => {
+                // The rest of a sequence arrives straight after the escape.
+                let sequence = Duration::from_millis(10);
+                if !matches!(read_byte(sequence)?, b'[' | b'O') {
+                    return None;
+                }
+                match read_byte(sequence)? {
+                    b'A' => Some(Key::Up),
+                    b'B' => Some(Key::Down),
+                    b'C' => Some(Key::Right),
+                    b'D' => Some(Key::Left),
+                    _ => None,
+                }
+            }
+            byte => Some(Key::Char(byte as char)),
+        }
+    }
+    /// Waits up to `timeout` for a key to be pressed in the console and
+    /// returns it, as the Unix version does.
+    ///
+    /// Arrow keys arrive as a prefix followed by a scan code, which are
+    /// decoded into the arrow [`Key`]s.
+    #[cfg(windows)]
+    pub(crate) fn key(&mut self, timeout: Duration) -> Option<Key> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if unsafe { _kbhit() } != 0 {
+                return match unsafe { _getwch() } {
+                    0 | 0xE0 => match unsafe { _getwch() } {
+                        72 => Some(Key::Up),
+                        80 => Some(Key::Down),
+                        77 => Some(Key::Right),
+                        75 => Some(Key::Left),
+                        _ => None,
+                    },
+                    code => char::from_u32(u32::from(code)).map(Key::Char),
+                };
+            }
+            let now = Instant::now();
+            if self.interrupted() || now >= deadline {
+                return None;
+            }
+            std::thread::sleep((deadline - now).min(Duration::from_millis(10)));
+        }
+    }
+    /// Waits for `timeout`. Key presses can't be read on this platform.
+    #[cfg(not(any(unix, windows)))]
+    pub(crate) fn key(&mut self, timeout: Duration) -> Option<Key> {
+        std::thread::sleep(timeout);
+        None
+    }
+    /// Returns true once Ctrl-C has been pressed.
+    pub(crate) fn interrupted(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+    /// Replaces whatever is on screen with `frame`. Each line is
+    /// overwritten rather than the screen being cleared first, so frames
+    /// don't flicker.
+    pub(crate) fn draw(&mut self, frame: &str) -> io::Result<()> {
+        let mut stdout = self.stdout.lock();
+        write!(stdout, "\x1b[H")?;
+        for line in frame.lines() {
+            write!(stdout, "{}\x1b[K\r\n", line)?;
+        }
+        write!(stdout, "\x1b[J")?;
+        stdout.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            if let Some(termios) = &self.termios {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios);
+            }
+        }
+        #[cfg(any(unix, windows))]
+        if let Some(previous) = self.interrupt {
+            unsafe {
+                libc::signal(libc::SIGINT, previous);
+            }
+        }
+        let _ = write!(self.stdout, "\x1b[?25h\x1b[?1049l");
+        let _ = self.stdout.flush();
+    }
+}
+
+/// Waits up to `timeout` for a byte from standard input.
+#[cfg(unix)]
+fn read_byte(timeout: Duration) -> Option<u8> {
+    let mut poll = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+    let mut byte = 0u8;
+    unsafe {
+        if libc::poll(&mut poll, 1, timeout) <= 0
+            || libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut _, 1) != 1
+        {
+            return None;
+        }
+    }
+    Some(byte)
+}
+
+/// Turns off echo and line buffering on standard input, returning the
+/// settings to restore afterwards, or `None` if it isn't a terminal.
+#[cfg(unix)]
+unsafe fn cbreak() -> Option<libc::termios> {
+    let mut original = std::mem::zeroed();
+    if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+        return None;
+    }
+    let mut termios = original;
+    termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+    termios.c_cc[libc::VMIN] = 1;
+    termios.c_cc[libc::VTIME] = 0;
+    libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios);
+    Some(original)
+}
@@ -0,0 +1,185 @@
+use crate::{Bucket, Counter};
+
+/// A movement of population from one bucket into another.
+///
+/// Behaviours describe their effect on the model as transfers, which the
+/// model then applies. A transfer is applied atomically, so whatever leaves
+/// `from` arrives in `to`. A transfer without a `from` bucket brings new
+/// population into the model, as with births, and one without a `to` bucket
+/// removes population from it, as with deaths.
+#[derive(Clone)]
+pub struct Transfer {
+    /// The bucket population is taken from.
+    pub from: Option<Bucket>,
+    /// The bucket population is moved into.
+    pub to: Option<Bucket>,
+    /// How much population to move.
+    pub amount: f64,
+    counters: Vec<Counter>,
+}
+
+impl Transfer {
+    /// Creates a transfer of `amount` from `from` into `to`.
+    pub fn new(from: Bucket, to: Bucket, amount: f64) -> Transfer {
+        Transfer {
+            from: Some(from),
+            to: Some(to),
+            amount,
+            counters: Vec::new(),
+        }
+    }
+    /// Creates a transfer of `amount` new population into `to`.
+    pub fn inflow(to: Bucket, amount: f64) -> Transfer {
+        Transfer {
+            from: None,
+            to: Some(to),
+            amount,
+            counters: Vec::new(),
+        }
+    }
+    /// Creates a transfer of `amount` out of `from` and out of the model.
+    pub fn outflow(from: Bucket, amount: f64) -> Transfer {
+        Transfer {
+            from: Some(from),
+            to: None,
+            amount,
+            counters: Vec::new(),
+        }
+    }
+    /// Moves the population, returning how much was actually moved.
+    ///
+    /// The amount is clamped to what is available in `from`, and negative
+    /// or NaN amounts move nothing.
+    pub fn apply(&self) -> f64 {
+        let available = self.from.as_ref().map_or(f64::INFINITY, Bucket::get);
+        let amount = self.amount.max(0.0).min(available.max(0.0));
+        self.move_amount(amount);
+        amount
+    }
+    /// Applies a set of transfers computed from the same state as one,
+    /// returning the resulting change in total population.
+    ///
+    /// Where the transfers out of a bucket add up to more than it holds,
+    /// each of them is scaled down proportionally, so the result does not
+    /// depend on the order of `transfers`.
+    pub fn apply_all(transfers: &[Transfer]) -> f64 {
+        Transfer::apply_scaled(transfers, false)
+    }
+    /// Applies a set of transfers integrated over a whole step, as by a
+    /// Runge-Kutta solver, returning the resulting change in total
+    /// population.
+    ///
+    /// Population arriving in a bucket within the step can leave it within
+    /// the same step, so transfers out of a bucket are only scaled down
+    /// where they add up to more than it holds and receives together.
+    pub(crate) fn apply_integrated(transfers: &[Transfer]) -> f64 {
+        Transfer::apply_scaled(transfers, true)
+    }
+    fn apply_scaled(transfers: &[Transfer], count_inflows: bool) -> f64 {
+        let mut outflows: Vec<(Bucket, f64)> = Vec::new();
+        transfers.iter().for_each(|transfer| {
+            if let Some(from) = &transfer.from {
+                let amount = transfer.amount.max(0.0);
+                match outflows.iter_mut().find(|(bucket, _)| bucket.ptr_eq(from)) {
+                    Some((_, total)) => *total += amount,
+                    None => outflows.push((from.clone(), amount)),
+                }
+            }
+        });
+        let scales = outflows
+            .into_iter()
+            .map(|(bucket, total)| {
+                let inflows: f64 = if count_inflows {
+                    transfers
+                        .iter()
+                        .filter(|transfer| {
+                            transfer.to.as_ref().is_some_and(|to| to.ptr_eq(&bucket))
+                        })
+                        .map(|transfer| transfer.amount.max(0.0))
+                        .sum()
+                } else {
+                    0.0
+                };
+                let available = (bucket.get() + inflows).max(0.0);
+                let scale = if total > available {
+                    available / total
+                } else {
+                    1.0
+                };
+                (bucket, scale)
+            })
+            .collect::<Vec<_>>();
+        transfers
+            .iter()
+            .map(|transfer| {
+                let scale = transfer.from.as_ref().map_or(1.0, |from| {
+                    scales
+                        .iter()
+                        .find(|(bucket, _)| bucket.ptr_eq(from))
+                        .map_or(1.0, |(_, scale)| *scale)
+                });
+                let amount = transfer.amount.max(0.0) * scale;
+                transfer.move_amount(amount);
+                transfer.net_change(amount)
+            })
+            .sum()
+    }
+    /// Returns how moving `amount` along this transfer changes the total
+    /// population of the model.
+    pub fn net_change(&self, amount: f64) -> f64 {
+        match (&self.from, &self.to) {
+            (None, Some(_)) => amount,
+            (Some(_), None) => -amount,
+            _ => 0.0,
+        }
+    }
+    /// Adds whatever this transfer moves, once applied, to `counter`.
+    pub fn counted(mut self, counter: Counter) -> Transfer {
+        self.counters.push(counter);
+        self
+    }
+    /// Encodes the transfer as its source, its destination and its amount,
+    /// with each bucket written as its position in `buckets`, or -1 for
+    /// none, so that a checkpoint can hold it. Counters are not kept.
+    pub(crate) fn encode(&self, buckets: &[Bucket]) -> [f64; 3] {
+        let position = |bucket: &Option<Bucket>| {
+            bucket
+                .as_ref()
+                .and_then(|bucket| buckets.iter().position(|other| other.ptr_eq(bucket)))
+                .map_or(-1.0, |position| position as f64)
+        };
+        [position(&self.from), position(&self.to), self.amount]
+    }
+    /// Decodes a transfer written by [`encode`](Transfer::encode), returning
+    /// `None` if it refers to a bucket not in `buckets`.
+    pub(crate) fn decode(encoded: &[f64], buckets: &[Bucket]) -> Option<Transfer> {
+        let bucket = |position: f64| -> Option<Option<Bucket>> {
+            if position == -1.0 {
+                Some(None)
+            } else if position >= 0.0 && position.fract() == 0.0 {
+                buckets.get(position as usize).cloned().map(Some)
+            } else {
+                None
+            }
+        };
+        match *encoded {
+            [from, to, amount] => Some(Transfer {
+                from: bucket(from)?,
+                to: bucket(to)?,
+                amount,
+                counters: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+    fn move_amount(&self, amount: f64) {
+        self.counters.iter().for_each(|counter| counter.add(amount));
+        if let Some(from) = &self.from {
+            let mut from = from.clone();
+            from -= amount;
+        }
+        if let Some(to) = &self.to {
+            to.receive(amount);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{self, Display, Formatter};
+
+/// A unit of time, such as the one a model's ticks stand for.
+///
+/// Declaring the unit a model's ticks stand for with
+/// [`Model::set_time_unit`](crate::Model::set_time_unit) lets durations
+/// and rates given in other units be converted into ticks, so that a rate
+/// per week isn't mistaken for a rate per day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// Minutes.
+    Minutes,
+    /// Hours.
+    Hours,
+    /// Days.
+    Days,
+    /// Weeks of seven days.
+    Weeks,
+    /// Julian years of 365.25 days.
+    Years,
+}
+
+impl TimeUnit {
+    /// Returns the unit called `name`, which may be singular, plural or
+    /// abbreviated, as in `day`, `days` or `d`.
+    pub fn parse(name: &str) -> Option<TimeUnit> {
+        Some(match name.trim() {
+            "min" | "minute" | "minutes" => TimeUnit::Minutes,
+            "h" | "hour" | "hours" => TimeUnit::Hours,
+            "d" | "day" | "days" => TimeUnit::Days,
+            "w" | "week" | "weeks" => TimeUnit::Weeks,
+            "y" | "year" | "years" => TimeUnit::Years,
+            _ => return None,
+        })
+    }
+    /// Returns the length of the unit in days.
+    pub fn days(self) -> f64 {
+        match self {
+            TimeUnit::Minutes => 1.0 / 1440.0,
+            TimeUnit::Hours => 1.0 / 24.0,
+            TimeUnit::Days => 1.0,
+            TimeUnit::Weeks => 7.0,
+            TimeUnit::Years => 365.25,
+        }
+    }
+    /// Converts a duration of `amount` of this unit into the unit `to`.
+    pub fn duration_in(self, amount: f64, to: TimeUnit) -> f64 {
+        amount * self.days() / to.days()
+    }
+    /// Converts a rate of `rate` per this unit into a rate per the unit
+    /// `to`.
+    pub fn rate_in(self, rate: f64, to: TimeUnit) -> f64 {
+        rate * to.days() / self.days()
+    }
+    /// Reads a duration such as `5 days`, returning it in units of `tick`.
+    /// A bare number is already in ticks. Returns `None` if the text is
+    /// malformed, or has a unit but `tick` is `None`.
+    pub fn parse_duration(text: &str, tick: Option<TimeUnit>) -> Option<f64> {
+        let text = text.trim();
+        match text.split_once(char::is_whitespace) {
+            Some((amount, unit)) => {
+                Some(TimeUnit::parse(unit)?.duration_in(amount.parse().ok()?, tick?))
+            }
+            None => text.parse().ok(),
+        }
+    }
+    /// Reads a rate such as `0.7 per week` or `0.7/week`, returning it per
+    /// `tick`. A bare number is already per tick. Returns `None` if the text
+    /// is malformed, or has a unit but `tick` is `None`.
+    pub fn parse_rate(text: &str, tick: Option<TimeUnit>) -> Option<f64> {
+        let text = text.trim();
+        let (rate, unit) = match text.split_once('/') {
+            Some(split) => split,
+            None => match text.split_once(char::is_whitespace) {
+                Some((rate, unit)) => (rate, unit.trim().strip_prefix("per ")?),
+                None => return text.parse().ok(),
+            },
+        };
+        Some(TimeUnit::parse(unit)?.rate_in(rate.trim().parse().ok()?, tick?))
+    }
+}
+
+impl Display for TimeUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TimeUnit::Minutes => "minutes",
+            TimeUnit::Hours => "hours",
+            TimeUnit::Days => "days",
+            TimeUnit::Weeks => "weeks",
+            TimeUnit::Years => "years",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,239 @@
+use prettytable::{Cell, Row, Table};
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use crate::Parameter;
+
+/// The number of recent frames shown in a [`View::Table`].
+const TABLE_ROWS: usize = 10;
+
+/// The number of recent frames, one per character, shown in a
+/// [`View::Chart`].
+const CHART_WIDTH: usize = 60;
+
+/// Block characters of increasing height used to draw sparklines.
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The quantities at which a bucket is highlighted in a [`View::Table`], as
+/// set with [`Model::set_threshold`](crate::Model::set_threshold).
+///
+/// Quantities below `warning` are shown in green, those from `warning` up
+/// to `alert` in yellow, and those of `alert` or more in red, so an
+/// intensive care bucket might warn at 80% of capacity and alert at
+/// capacity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Threshold {
+    /// The quantity from which the bucket is shown in yellow.
+    pub warning: f64,
+    /// The quantity from which the bucket is shown in red.
+    pub alert: f64,
+}
+
+impl Threshold {
+    /// Creates a threshold warning from `warning` and alerting from `alert`.
+    pub fn new(warning: f64, alert: f64) -> Threshold {
+        Threshold { warning, alert }
+    }
+    /// Returns the escape sequence setting the colour `quantity` is shown
+    /// in.
+    fn colour(&self, quantity: f64) -> &'static str {
+        if quantity >= self.alert {
+            RED
+        } else if quantity >= self.warning {
+            YELLOW
+        } else {
+            GREEN
+        }
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[39m";
+
+/// What [`Model::run`](crate::Model::run) draws every frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum View {
+    /// A table of the quantity in every bucket over the most recent frames,
+    /// newest first, each with its change since the frame before. Buckets
+    /// with a [`Threshold`] are coloured by it.
+    Table,
+    /// A sparkline per bucket over the most recent frames, oldest on the
+    /// left, each scaled to its own range with its current quantity
+    /// alongside.
+    Chart,
+    /// The chart above the table.
+    Both,
+    /// The simulated time, the chart, and the current value of every
+    /// parameter.
+    #[default]
+    Dashboard,
+}
+
+/// What a [`View`] draws from.
+pub(crate) struct Frame<'a> {
+    /// The name of every bucket.
+    pub(crate) names: &'a [&'a str],
+    /// The model's history, oldest first.
+    pub(crate) history: &'a VecDeque<(f64, Vec<f64>)>,
+    pub(crate) parameters: &'a [Parameter],
+    /// The index of the parameter to mark as selected.
+    pub(crate) selected: usize,
+    /// The threshold of every bucket, lining up with `names`.
+    pub(crate) thresholds: &'a [Option<Threshold>],
+    /// Whether quantities are shown as percentages of the total.
+    pub(crate) normalized: bool,
+}
+
+impl Frame<'_> {
+    /// Returns the quantities in the most recent `count` points of history,
+    /// newest first, as they're to be shown.
+    fn recent(&self, count: usize) -> Vec<Vec<f64>> {
+        self.history
+            .iter()
+            .rev()
+            .take(count)
+            .map(|(_, row)| {
+                if self.normalized {
+                    let total: f64 = row.iter().sum();
+                    row.iter()
+                        .map(|quantity| 100.0 * quantity / total)
+                        .collect()
+                } else {
+                    row.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+impl View {
+    /// Renders `frame` in this view.
+    pub(crate) fn render(self, frame: &Frame<'_>) -> String {
+        match self {
+            View::Table => table(frame),
+            View::Chart => chart(frame),
+            View::Both => chart(frame) + "\n" + &table(frame),
+            View::Dashboard => {
+                let time = frame.history.back().map_or(0.0, |(time, _)| *time);
+                format!(
+                    "time {:.2}{}\n\n{}\n{}",
+                    time,
+                    if frame.normalized {
+                        "  (% of the total population)"
+                    } else {
+                        ""
+                    },
+                    chart(frame),
+                    parameter_list(frame.parameters, frame.selected)
+                )
+            }
+        }
+    }
+}
+
+fn parameter_list(parameters: &[Parameter], selected: usize) -> String {
+    let paths = parameters.iter().map(Parameter::path).collect::<Vec<_>>();
+    let width = paths.iter().map(|path| path.chars().count()).max();
+    let mut list = String::new();
+    for (index, (path, parameter)) in paths.iter().zip(parameters).enumerate() {
+        let _ = writeln!(
+            list,
+            "{} {:<width$} {}",
+            if index == selected { '>' } else { ' ' },
+            path,
+            round(parameter.value),
+            width = width.unwrap_or(0)
+        );
+    }
+    list
+}
+
+/// Rounds `value` to six decimal places, enough to show a parameter without
+/// the noise of nudging it in floating point.
+pub(crate) fn round(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+fn table(frame: &Frame<'_>) -> String {
+    let mut table = Table::new();
+    table.add_row(Row::new(
+        frame.names.iter().map(|name| Cell::new(name)).collect(),
+    ));
+    let raw = frame.history.iter().rev().map(|(_, row)| row);
+    let rows = frame.recent(TABLE_ROWS + 1);
+    for (index, (row, raw)) in rows.iter().zip(raw).enumerate().take(TABLE_ROWS) {
+        let previous = rows.get(index + 1);
+        table.add_row(Row::new(
+            row.iter()
+                .zip(raw)
+                .enumerate()
+                .map(|(bucket, (&shown, &quantity))| {
+                    let mut text = format!("{:.2}", shown);
+                    if let Some(previous) = previous {
+                        text += &format!(" ({:+.2})", shown - previous[bucket]);
+                    }
+                    // Thresholds apply to the quantity itself even when it's
+                    // shown normalized. Escape sequences aren't counted
+                    // towards the width of a cell, so coloured cells still
+                    // line up.
+                    match frame.thresholds.get(bucket).cloned().flatten() {
+                        Some(threshold) => {
+                            Cell::new(&format!("{}{}{}", threshold.colour(quantity), text, RESET))
+                        }
+                        None => Cell::new(&text),
+                    }
+                })
+                .collect(),
+        ));
+    }
+    table.to_string()
+}
+
+fn chart(frame: &Frame<'_>) -> String {
+    let window = frame.recent(CHART_WIDTH);
+    let width = frame.names.iter().map(|name| name.chars().count()).max();
+    let mut chart = String::new();
+    for (index, name) in frame.names.iter().enumerate() {
+        let values = window
+            .iter()
+            .rev()
+            .map(|row| row[index])
+            .collect::<Vec<_>>();
+        let current = values.last().cloned().unwrap_or(0.0);
+        let _ = writeln!(
+            chart,
+            "{:>width$} {:<chart$} {:.2}",
+            name,
+            sparkline(&values),
+            current,
+            width = width.unwrap_or(0),
+            chart = CHART_WIDTH
+        );
+    }
+    chart
+}
+
+/// Draws `values` as a line of block characters, the lowest value as the
+/// shortest block and the highest as the tallest. Values that are not
+/// finite are left blank.
+fn sparkline(values: &[f64]) -> String {
+    let finite = || values.iter().cloned().filter(|value| value.is_finite());
+    let low = finite().fold(f64::INFINITY, f64::min);
+    let high = finite().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|&value| {
+            if !value.is_finite() {
+                ' '
+            } else if high > low {
+                let level = (value - low) / (high - low) * (LEVELS.len() - 1) as f64;
+                LEVELS[level.round() as usize]
+            } else {
+                LEVELS[0]
+            }
+        })
+        .collect()
+}